@@ -0,0 +1,105 @@
+//! Benchmarks of the codecs metadata is stored in Redis with.
+//!
+//! Compares encoding and decoding under each codec for a typical text and a
+//! typical image payload, and prints what each stores. Content is stored as
+//! raw bytes next to the metadata under either codec.
+//!
+//! Run with `cargo bench --bench codec`.
+
+use std::collections::BTreeMap;
+
+use chrono::{Duration, Utc};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use jump::{
+    domain::{metadata::Metadata, payload::Payload},
+    infrastructure::{redis::Codec, schema::PAYLOADS},
+};
+
+const CODECS: [Codec; 2] = [Codec::Json, Codec::Binary];
+
+/// A markdown note of a few kilobytes with a title and tags
+fn text_payload() -> Payload {
+    let content = "## Release notes\n\n- Fixed the flaky checkout retries\n".repeat(50);
+    let metadata = Metadata::default()
+        .with_filename("release-notes.md")
+        .unwrap()
+        .with_title(Some("Release notes".to_string()))
+        .with_tags(BTreeMap::from([
+            ("team".to_string(), "payments".to_string()),
+            ("release".to_string(), "2024.11".to_string()),
+        ]));
+    Payload::new(
+        content.into_bytes(),
+        Some("text/markdown; charset=utf-8".to_string()),
+        Some(Utc::now() + Duration::days(1)),
+    )
+    .unwrap()
+    .with_max_views(Some(10))
+    .with_metadata(metadata)
+}
+
+/// A 64 KiB PNG screenshot with a filename
+fn image_payload() -> Payload {
+    let mut content = b"\x89PNG\r\n\x1a\n".to_vec();
+    content.extend((0..64 * 1024).map(|i: u32| (i.wrapping_mul(2_654_435_761) >> 24) as u8));
+    let metadata = Metadata::default().with_filename("screenshot.png").unwrap();
+    Payload::new(
+        content,
+        Some("image/png".to_string()),
+        Some(Utc::now() + Duration::hours(1)),
+    )
+    .unwrap()
+    .with_metadata(metadata)
+}
+
+fn payloads() -> [(&'static str, Payload); 2] {
+    [("text", text_payload()), ("image", image_payload())]
+}
+
+/// Prints the bytes each codec stores for each payload
+fn report_sizes() {
+    for (name, payload) in payloads() {
+        for codec in CODECS {
+            let meta = codec.encode(&PAYLOADS, &payload).unwrap().len();
+            println!(
+                "{}/{:?}: {} bytes of metadata, {} bytes stored with content",
+                name,
+                codec,
+                meta,
+                meta + payload.content().len()
+            );
+        }
+    }
+}
+
+fn bench_encode(c: &mut Criterion) {
+    report_sizes();
+
+    let mut group = c.benchmark_group("encode");
+    group.throughput(Throughput::Elements(1));
+    for (name, payload) in payloads() {
+        for codec in CODECS {
+            group.bench_with_input(BenchmarkId::new(format!("{:?}", codec), name), &payload, |b, payload| {
+                b.iter(|| codec.encode(&PAYLOADS, black_box(payload)).unwrap())
+            });
+        }
+    }
+    group.finish();
+}
+
+fn bench_decode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("decode");
+    group.throughput(Throughput::Elements(1));
+    for (name, payload) in payloads() {
+        for codec in CODECS {
+            let stored = codec.encode(&PAYLOADS, &payload).unwrap();
+            group.bench_with_input(BenchmarkId::new(format!("{:?}", codec), name), &stored, |b, stored| {
+                b.iter(|| Codec::decode::<Payload>(&PAYLOADS, black_box(stored)).unwrap())
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_encode, bench_decode);
+criterion_main!(benches);
@@ -96,12 +96,10 @@ where
                         tracing::warn!("Rate limit exceeded for IP: {}", client_ip);
                         
                         // Return 429 Too Many Requests with appropriate headers
-                        return Err(actix_web::error::Error::from(
-                            actix_web::error::ErrorTooManyRequests(format!(
+                        return Err(actix_web::error::ErrorTooManyRequests(format!(
                                 "Rate limit exceeded. Try again in {} seconds",
                                 wait_time
-                            ))
-                        ));
+                            )));
                     }
                     RateLimitError::Redis(msg) => {
                         // Log Redis error but don't block the request
@@ -0,0 +1,156 @@
+//! Mapping of use case errors to HTTP responses.
+//!
+//! Status codes are derived from the error variants, never from error
+//! messages, so every handler reports the same failure the same way.
+
+use actix_web::HttpResponse;
+
+use super::payload::etag;
+use crate::application::{repository::RepositoryError, use_cases::UseCaseError};
+
+/// Convert a use case error into an HTTP error response.
+///
+/// Storage failures are reported without their details, which stay in the
+/// logs.
+pub(crate) fn error_response(e: UseCaseError) -> HttpResponse {
+    match e {
+        UseCaseError::ValidationError(msg) => {
+            HttpResponse::BadRequest().json(serde_json::json!({
+                "error": msg
+            }))
+        }
+        UseCaseError::InvalidHashId(err) => {
+            HttpResponse::BadRequest().json(serde_json::json!({
+                "error": err.to_string()
+            }))
+        }
+        UseCaseError::InvalidShareCode(err) => {
+            HttpResponse::BadRequest().json(serde_json::json!({
+                "error": err.to_string()
+            }))
+        }
+        UseCaseError::InvalidExpiry(err) => {
+            HttpResponse::BadRequest().json(serde_json::json!({
+                "error": err.to_string()
+            }))
+        }
+        UseCaseError::DomainError(err) => {
+            HttpResponse::BadRequest().json(serde_json::json!({
+                "error": err.to_string()
+            }))
+        }
+        UseCaseError::NotFound | UseCaseError::RepositoryError(RepositoryError::NotFound) => {
+            HttpResponse::NotFound().json(serde_json::json!({
+                "error": "Payload not found"
+            }))
+        }
+        UseCaseError::Unauthorized
+        | UseCaseError::RepositoryError(RepositoryError::PasswordRequired)
+        | UseCaseError::RepositoryError(RepositoryError::UnlockThrottled) => {
+            HttpResponse::Unauthorized().json(serde_json::json!({
+                "error": "Missing or invalid credentials"
+            }))
+        }
+        UseCaseError::AliasTaken(alias) => {
+            HttpResponse::Conflict().json(serde_json::json!({
+                "error": format!("Alias {} is already taken", alias)
+            }))
+        }
+        UseCaseError::ViewLimitReached => {
+            HttpResponse::Gone().json(serde_json::json!({
+                "error": "Payload view limit reached"
+            }))
+        }
+        UseCaseError::NotModified(version) => {
+            HttpResponse::NotModified().insert_header(etag(version)).finish()
+        }
+        UseCaseError::PreconditionFailed
+        | UseCaseError::RepositoryError(RepositoryError::VersionMismatch(_)) => {
+            HttpResponse::PreconditionFailed().json(serde_json::json!({
+                "error": "Payload has been changed since the given version"
+            }))
+        }
+        UseCaseError::Internal(_) => {
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Internal server error"
+            }))
+        }
+        UseCaseError::RepositoryError(err) => repository_error_response(err),
+    }
+}
+
+/// Convert a repository error other than `NotFound` into an HTTP response.
+fn repository_error_response(e: RepositoryError) -> HttpResponse {
+    match e {
+        RepositoryError::Conflict(msg) => {
+            HttpResponse::Conflict().json(serde_json::json!({
+                "error": msg
+            }))
+        }
+        RepositoryError::Unavailable(_) => {
+            HttpResponse::ServiceUnavailable().json(serde_json::json!({
+                "error": "Storage is temporarily unavailable"
+            }))
+        }
+        RepositoryError::QuotaExceeded(_) => {
+            HttpResponse::InsufficientStorage().json(serde_json::json!({
+                "error": "Storage quota exceeded"
+            }))
+        }
+        RepositoryError::NotFound
+        | RepositoryError::VersionMismatch(_)
+        | RepositoryError::ViewLimitReached
+        | RepositoryError::PasswordRequired
+        | RepositoryError::UnlockThrottled
+        | RepositoryError::Serialization(_)
+        | RepositoryError::Internal(_) => {
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "error": "Internal server error"
+            }))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{expiry::ExpiryError, hash_id::HashIdError, share_code::ShareCodeError};
+    use actix_web::http::StatusCode;
+
+    #[test]
+    fn test_status_codes_follow_error_types() {
+        let cases = [
+            (UseCaseError::ValidationError("bad".to_string()), StatusCode::BAD_REQUEST),
+            (HashIdError::Empty.into(), StatusCode::BAD_REQUEST),
+            (ShareCodeError::Malformed.into(), StatusCode::BAD_REQUEST),
+            (ExpiryError::InPast.into(), StatusCode::BAD_REQUEST),
+            (UseCaseError::NotFound, StatusCode::NOT_FOUND),
+            (UseCaseError::ViewLimitReached, StatusCode::GONE),
+            (UseCaseError::Unauthorized, StatusCode::UNAUTHORIZED),
+            (UseCaseError::Internal("join failed".to_string()), StatusCode::INTERNAL_SERVER_ERROR),
+            (UseCaseError::AliasTaken("notes".to_string()), StatusCode::CONFLICT),
+            (UseCaseError::NotModified(2), StatusCode::NOT_MODIFIED),
+            (UseCaseError::PreconditionFailed, StatusCode::PRECONDITION_FAILED),
+            (RepositoryError::VersionMismatch(2).into(), StatusCode::PRECONDITION_FAILED),
+            (RepositoryError::NotFound.into(), StatusCode::NOT_FOUND),
+            (RepositoryError::Conflict("taken".to_string()).into(), StatusCode::CONFLICT),
+            (
+                RepositoryError::Unavailable("down".to_string()).into(),
+                StatusCode::SERVICE_UNAVAILABLE,
+            ),
+            (
+                RepositoryError::QuotaExceeded("full".to_string()).into(),
+                StatusCode::INSUFFICIENT_STORAGE,
+            ),
+            (
+                RepositoryError::Serialization("bad json".to_string()).into(),
+                StatusCode::INTERNAL_SERVER_ERROR,
+            ),
+        ];
+
+        for (error, expected) in cases {
+            let description = error.to_string();
+            assert_eq!(error_response(error).status(), expected, "{}", description);
+        }
+    }
+}
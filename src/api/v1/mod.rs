@@ -9,6 +9,7 @@
 
 use actix_web::web;
 
+mod error;
 mod payload;
 
 /// Configure v1 API routes.
@@ -21,10 +22,23 @@ pub fn configure() -> impl Fn(&mut web::ServiceConfig) {
                 // Payload routes
                 .route("/payloads", web::post().to(payload::create_payload))
                 .route("/payloads/{id}", web::get().to(payload::get_payload))
+                .route("/payloads/{id}/raw", web::get().to(payload::get_raw_payload))
+                .route("/payloads/{id}/unlock", web::post().to(payload::unlock_payload))
+                .route("/payloads/{id}/revisions/{n}", web::get().to(payload::get_payload_revision))
+                .route("/payloads/{id}", web::put().to(payload::replace_payload))
+                .route("/payloads/{id}", web::patch().to(payload::update_payload))
                 .route("/payloads/{id}", web::delete().to(payload::delete_payload))
+                // Share code routes
+                .route("/codes/{code}", web::get().to(payload::get_payload_by_share_code))
+                // Alias routes
+                .route("/aliases/{alias}", web::get().to(payload::get_payload_by_alias))
         );
     }
 }
 
 // Re-export handlers for testing
-pub use payload::{create_payload, get_payload, delete_payload};
+pub use payload::{
+    create_payload, get_payload, get_payload_by_alias, get_payload_by_share_code,
+    get_payload_revision, get_raw_payload, unlock_payload, replace_payload, update_payload,
+    delete_payload, ShareCodeLimiter,
+};
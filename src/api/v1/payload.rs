@@ -3,26 +3,152 @@
 //! This module contains the API endpoints for creating and retrieving payloads.
 
 use actix_web::{
+    http::header::{self, Charset, ContentDisposition, DispositionParam, DispositionType, ExtendedValue},
+    http::header::{EntityTag, ETag, IfMatch, IfNoneMatch},
     web::{Data, Json, Path},
-    HttpResponse, Responder,
+    HttpMessage, HttpRequest, HttpResponse, Responder,
 };
 use tracing::{info, warn, error};
+use std::net::IpAddr;
 use std::sync::Arc;
 
+use super::error::error_response;
 use crate::{
     application::{
-        dtos::CreatePayloadRequest,
+        dtos::{
+            CreatePayloadRequest, ReplacePayloadRequest, UnlockPayloadRequest,
+            UpdatePayloadRequest, VersionMatch,
+        },
         use_cases::{
             CreatePayloadUseCaseImpl, GetPayloadUseCaseImpl, DeletePayloadUseCaseImpl,
-            CreatePayloadUseCase, GetPayloadUseCase, DeletePayloadUseCase,
-            UseCaseError,
+            UpdatePayloadUseCaseImpl, ReplacePayloadUseCaseImpl, ResolveShareCodeUseCaseImpl,
+            ResolveAliasUseCaseImpl, CreatePayloadUseCase, GetPayloadUseCase, GetRevisionUseCase,
+            DeletePayloadUseCase, UpdatePayloadUseCase, ReplacePayloadUseCase,
+            ResolveShareCodeUseCase, ResolveAliasUseCase, UseCaseError,
         },
     },
+    domain::{alias::Alias, hash_id::HashId, mime_type::{MimePolicy, MimeRegistry, MimeType}, share_code::ShareCode},
+    infrastructure::rate_limit::{RateLimitError, RateLimiter},
 };
 
 /// Maximum payload size in bytes (10MB)
 const MAX_PAYLOAD_SIZE: usize = 10 * 1024 * 1024;
 
+/// Request header carrying the password of a protected payload
+const PASSWORD_HEADER: &str = "X-Payload-Password";
+
+/// Read the payload password from the request headers, if given
+fn password_header(req: &HttpRequest) -> Option<String> {
+    req.headers()
+        .get(PASSWORD_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+}
+
+/// Read the management token from an `Authorization: Bearer` header, if given
+fn bearer_token(req: &HttpRequest) -> Option<&str> {
+    req.headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::trim)
+}
+
+/// Entity tag identifying a payload version
+pub(super) fn etag(version: u64) -> ETag {
+    ETag(EntityTag::new_strong(version.to_string()))
+}
+
+/// Read the payload versions named by an `If-Match` header, if given.
+///
+/// `If-Match` compares entity tags strongly, so weak tags match no version.
+fn if_match(req: &HttpRequest) -> Option<VersionMatch> {
+    match req.get_header::<IfMatch>()? {
+        IfMatch::Any => Some(VersionMatch::Any),
+        IfMatch::Items(tags) => Some(versions(tags.iter().filter(|tag| !tag.weak))),
+    }
+}
+
+/// Read the payload versions named by an `If-None-Match` header, if given.
+fn if_none_match(req: &HttpRequest) -> Option<VersionMatch> {
+    match req.get_header::<IfNoneMatch>()? {
+        IfNoneMatch::Any => Some(VersionMatch::Any),
+        IfNoneMatch::Items(tags) => Some(versions(tags.iter())),
+    }
+}
+
+/// Versions named by entity tags; tags we did not hand out match nothing
+fn versions<'a>(tags: impl Iterator<Item = &'a EntityTag>) -> VersionMatch {
+    VersionMatch::Versions(tags.filter_map(|tag| tag.tag().parse().ok()).collect())
+}
+
+/// Parse a hash ID from the request path.
+///
+/// Handlers answer malformed IDs with `400 Bad Request` before any storage
+/// is touched.
+fn parse_hash_id(id: &str) -> Result<HashId, UseCaseError> {
+    HashId::parse(id).map_err(|e| {
+        warn!(error = %e, "Rejected malformed hash ID");
+        e.into()
+    })
+}
+
+/// Rate limiter for share code lookups.
+///
+/// Share codes are short enough to guess, so lookups by code are limited per
+/// client by a limiter of their own, much stricter than the general one.
+///
+/// Clients are told apart by the address they connect from, which they
+/// cannot choose freely. Only requests from trusted proxies are limited by
+/// the client address the proxies forward in `X-Forwarded-For`.
+#[derive(Clone)]
+pub struct ShareCodeLimiter {
+    limiter: Arc<dyn RateLimiter>,
+    trusted_proxies: Vec<IpAddr>,
+}
+
+impl ShareCodeLimiter {
+    /// Create a share code limiter trusting no proxies
+    pub fn new(limiter: Arc<dyn RateLimiter>) -> Self {
+        Self {
+            limiter,
+            trusted_proxies: Vec::new(),
+        }
+    }
+
+    /// Set the proxies whose forwarded client addresses are trusted
+    pub fn with_trusted_proxies(mut self, trusted_proxies: Vec<IpAddr>) -> Self {
+        self.trusted_proxies = trusted_proxies;
+        self
+    }
+
+    /// Returns the address of the client making a request.
+    ///
+    /// Walks `X-Forwarded-For` back from the nearest hop for as long as the
+    /// hops are trusted proxies, so addresses the client made up itself at
+    /// the start of the list are never used.
+    fn client_addr(&self, req: &HttpRequest) -> Option<IpAddr> {
+        let mut client = req.peer_addr()?.ip();
+        let forwarded: Vec<&str> = req
+            .headers()
+            .get_all("x-forwarded-for")
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(str::trim)
+            .collect();
+        for hop in forwarded.iter().rev() {
+            if !self.trusted_proxies.contains(&client) {
+                break;
+            }
+            match hop.parse() {
+                Ok(addr) => client = addr,
+                Err(_) => break,
+            }
+        }
+        Some(client)
+    }
+}
+
 /// Create a new payload.
 ///
 /// # Request
@@ -31,7 +157,17 @@ const MAX_PAYLOAD_SIZE: usize = 10 * 1024 * 1024;
 /// {
 ///     "content": "Your payload content here",
 ///     "mime_type": "text/plain",
-///     "expiry_time": "2024-03-14T12:00:00Z"
+///     "encoding": "utf8",
+///     "expires_in": "2h",
+///     "one_time": false,
+///     "max_views": 5,
+///     "password": "correct horse battery staple",
+///     "share_code": true,
+///     "alias": "release-notes-42",
+///     "filename": "release-notes.txt",
+///     "title": "Release notes",
+///     "description": "Notes for the 4.2 release",
+///     "tags": { "team": "platform" }
 /// }
 /// ```
 ///
@@ -40,7 +176,15 @@ const MAX_PAYLOAD_SIZE: usize = 10 * 1024 * 1024;
 /// ```json
 /// {
 ///     "hash_id": "unique-hash-id",
-///     "expires_at": "2023-01-01T00:00:00Z"
+///     "mime_type": "text/plain",
+///     "expires_at": "2023-01-01T00:00:00Z",
+///     "one_time": false,
+///     "max_views": 5,
+///     "password_protected": true,
+///     "share_code": "7-crossover-clockwork",
+///     "alias": "release-notes-42",
+///     "version": 1,
+///     "management_token": "secret-management-token"
 /// }
 /// ```
 #[tracing::instrument(
@@ -49,7 +193,10 @@ const MAX_PAYLOAD_SIZE: usize = 10 * 1024 * 1024;
     fields(
         payload_size = %payload.content.len(),
         mime_type = ?payload.mime_type,
-        expiry_time = ?payload.expiry_time
+        expiry_time = ?payload.expiry_time,
+        expires_in = ?payload.expires_in,
+        idle_timeout = ?payload.idle_timeout,
+        expires_after_view = ?payload.expires_after_view
     )
 )]
 pub async fn create_payload(
@@ -74,143 +221,560 @@ pub async fn create_payload(
                 hash_id = %response.hash_id,
                 "Payload created successfully"
             );
-            HttpResponse::Created().json(serde_json::json!({
+            HttpResponse::Created().insert_header(etag(response.version)).json(serde_json::json!({
                 "hash_id": response.hash_id,
-                "expires_at": response.expiry_time
+                "mime_type": response.mime_type,
+                "expires_at": response.expiry_time,
+                "one_time": response.one_time,
+                "max_views": response.max_views,
+                "password_protected": response.password_protected,
+                "share_code": response.share_code,
+                "alias": response.alias,
+                "version": response.version,
+                "management_token": response.management_token
             }))
         }
         Err(e) => {
             error!(error = %e, "Failed to create payload");
-            match e {
-                UseCaseError::ValidationError(msg) => {
-                    HttpResponse::BadRequest().json(serde_json::json!({
-                        "error": msg
-                    }))
-                }
-                UseCaseError::RepositoryError(_) => {
-                    HttpResponse::InternalServerError().json(serde_json::json!({
-                        "error": "Internal server error"
-                    }))
-                }
-                UseCaseError::DomainError(err) => {
-                    HttpResponse::InternalServerError().json(serde_json::json!({
-                        "error": err.to_string()
-                    }))
-                }
-                _ => {
-                    HttpResponse::InternalServerError().json(serde_json::json!({
-                        "error": "An unexpected error occurred"
-                    }))
-                }
-            }
+            error_response(e)
         }
     }
 }
 
 /// Get a payload by ID.
 ///
+/// Password-protected payloads require the password in the
+/// `X-Payload-Password` header. The response carries the payload's version
+/// as `ETag`; a request whose `If-None-Match` names the current version gets
+/// `304 Not Modified` and does not count as a view.
+///
 /// # Response
 ///
 /// ```json
 /// {
 ///     "hash_id": "unique-hash-id",
 ///     "content": "Your payload content here",
+///     "encoding": "utf8",
 ///     "mime_type": "text/plain",
+///     "revision": 1,
+///     "version": 1,
 ///     "created_at": "2023-01-01T00:00:00Z",
 ///     "updated_at": "2023-01-01T00:00:00Z",
 ///     "viewed_at": "2023-01-01T00:00:00Z",
-///     "expiry_time": "2023-01-01T01:00:00Z"
+///     "expiry_time": "2023-01-01T01:00:00Z",
+///     "one_time": false,
+///     "view_count": 1,
+///     "max_views": 5,
+///     "password_protected": false,
+///     "share_code": null,
+///     "alias": null,
+///     "filename": "release-notes.txt",
+///     "title": "Release notes",
+///     "description": null,
+///     "tags": { "team": "platform" }
 /// }
 /// ```
 #[tracing::instrument(
     name = "Get payload",
-    skip(get_payload_use_case),
+    skip(get_payload_use_case, req),
     fields(hash_id = %id)
 )]
 pub async fn get_payload(
     get_payload_use_case: Data<Arc<GetPayloadUseCaseImpl>>,
     id: Path<String>,
+    req: HttpRequest,
 ) -> impl Responder {
     info!("Processing get payload request");
     
-    // Get the ID as a string
-    let id_string = id.into_inner();
+    let hash_id = match parse_hash_id(&id) {
+        Ok(hash_id) => hash_id,
+        Err(e) => return error_response(e),
+    };
     
     // Get payload
-    match get_payload_use_case.execute(id_string).await {
+    match get_payload_use_case.execute(hash_id, password_header(&req), if_none_match(&req)).await {
         Ok(response) => {
             info!(
                 hash_id = %response.hash_id,
                 "Payload retrieved successfully"
             );
-            HttpResponse::Ok().json(response)
+            HttpResponse::Ok().insert_header(etag(response.version)).json(response)
         }
         Err(e) => {
             error!(error = %e, "Failed to retrieve payload");
-            match e {
-                UseCaseError::NotFound => {
-                    HttpResponse::NotFound().json(serde_json::json!({
-                        "error": "Payload not found"
-                    }))
-                }
-                UseCaseError::Expired => {
-                    HttpResponse::Gone().json(serde_json::json!({
-                        "error": "Payload has expired"
-                    }))
-                }
-                _ => {
-                    HttpResponse::InternalServerError().json(serde_json::json!({
+            error_response(e)
+        }
+    }
+}
+
+/// Get an earlier revision of a payload by ID.
+///
+/// Responds like [`get_payload`], with the content and MIME type of the
+/// given revision and its number as `revision`. Reading a revision counts
+/// as a view of the payload.
+#[tracing::instrument(
+    name = "Get payload revision",
+    skip(get_payload_use_case, path, req),
+    fields(hash_id = %path.0, revision = path.1)
+)]
+pub async fn get_payload_revision(
+    get_payload_use_case: Data<Arc<GetPayloadUseCaseImpl>>,
+    path: Path<(String, u64)>,
+    req: HttpRequest,
+) -> impl Responder {
+    info!("Processing get payload revision request");
+
+    let (id, revision) = path.into_inner();
+    let hash_id = match parse_hash_id(&id) {
+        Ok(hash_id) => hash_id,
+        Err(e) => return error_response(e),
+    };
+
+    match get_payload_use_case
+        .execute_revision(hash_id, revision, password_header(&req), if_none_match(&req))
+        .await
+    {
+        Ok(response) => {
+            info!(
+                hash_id = %response.hash_id,
+                "Payload revision retrieved successfully"
+            );
+            HttpResponse::Ok().insert_header(etag(response.version)).json(response)
+        }
+        Err(e) => {
+            error!(error = %e, "Failed to retrieve payload revision");
+            error_response(e)
+        }
+    }
+}
+
+/// Get a payload by its share code.
+///
+/// Responds like [`get_payload`]. Every lookup, successful or not, counts
+/// against the client's [`ShareCodeLimiter`]; once it is used up, lookups
+/// get `429 Too Many Requests` until the window has passed.
+#[tracing::instrument(
+    name = "Get payload by share code",
+    skip_all
+)]
+pub async fn get_payload_by_share_code(
+    resolve_share_code_use_case: Data<Arc<ResolveShareCodeUseCaseImpl>>,
+    get_payload_use_case: Data<Arc<GetPayloadUseCaseImpl>>,
+    limiter: Data<ShareCodeLimiter>,
+    code: Path<String>,
+    req: HttpRequest,
+) -> impl Responder {
+    info!("Processing get payload by share code request");
+
+    let client_ip = limiter
+        .client_addr(&req)
+        .map_or_else(|| "unknown".to_string(), |addr| addr.to_string());
+    match limiter.limiter.check_rate_limit(&format!("share_code:{}", client_ip)).await {
+        Ok(()) => {}
+        Err(RateLimitError::LimitExceeded(wait_time)) => {
+            warn!(client_ip = %client_ip, "Share code rate limit exceeded");
+            return HttpResponse::TooManyRequests()
+                .insert_header((header::RETRY_AFTER, wait_time))
+                .json(serde_json::json!({
+                    "error": format!("Too many share code lookups. Try again in {} seconds", wait_time)
+                }));
+        }
+        Err(e) => {
+            // Unthrottled lookups would make codes guessable, so fail closed
+            error!(error = %e, "Share code rate limit check failed");
+            return HttpResponse::ServiceUnavailable().json(serde_json::json!({
+                "error": "Share code lookups are temporarily unavailable"
+            }));
+        }
+    }
+
+    let code = match ShareCode::parse(&code) {
+        Ok(code) => code,
+        Err(e) => return error_response(e.into()),
+    };
+    let hash_id = match resolve_share_code_use_case.resolve(&code).await {
+        Ok(hash_id) => hash_id,
+        Err(e) => {
+            info!(error = %e, "Failed to resolve share code");
+            return error_response(e);
+        }
+    };
+
+    match get_payload_use_case.execute(hash_id, password_header(&req), if_none_match(&req)).await {
+        Ok(response) => {
+            info!(
+                hash_id = %response.hash_id,
+                "Payload retrieved by share code successfully"
+            );
+            HttpResponse::Ok().insert_header(etag(response.version)).json(response)
+        }
+        Err(e) => {
+            error!(error = %e, "Failed to retrieve payload");
+            error_response(e)
+        }
+    }
+}
+
+/// Get a payload by its alias.
+///
+/// Responds like [`get_payload`].
+#[tracing::instrument(
+    name = "Get payload by alias",
+    skip(resolve_alias_use_case, get_payload_use_case, req),
+    fields(alias = %alias)
+)]
+pub async fn get_payload_by_alias(
+    resolve_alias_use_case: Data<Arc<ResolveAliasUseCaseImpl>>,
+    get_payload_use_case: Data<Arc<GetPayloadUseCaseImpl>>,
+    alias: Path<String>,
+    req: HttpRequest,
+) -> impl Responder {
+    info!("Processing get payload by alias request");
+
+    let alias = match Alias::parse(&alias) {
+        Ok(alias) => alias,
+        Err(e) => return error_response(UseCaseError::ValidationError(e.to_string())),
+    };
+    let hash_id = match resolve_alias_use_case.resolve(&alias).await {
+        Ok(hash_id) => hash_id,
+        Err(e) => {
+            info!(error = %e, "Failed to resolve alias");
+            return error_response(e);
+        }
+    };
+
+    match get_payload_use_case.execute(hash_id, password_header(&req), if_none_match(&req)).await {
+        Ok(response) => {
+            info!(
+                hash_id = %response.hash_id,
+                "Payload retrieved by alias successfully"
+            );
+            HttpResponse::Ok().insert_header(etag(response.version)).json(response)
+        }
+        Err(e) => {
+            error!(error = %e, "Failed to retrieve payload");
+            error_response(e)
+        }
+    }
+}
+
+/// Unlock a password-protected payload.
+///
+/// Works like [`get_payload`], but takes the password in the request body
+/// for clients that cannot set headers.
+///
+/// # Request
+///
+/// ```json
+/// {
+///     "password": "correct horse battery staple"
+/// }
+/// ```
+#[tracing::instrument(
+    name = "Unlock payload",
+    skip(get_payload_use_case, request),
+    fields(hash_id = %id)
+)]
+pub async fn unlock_payload(
+    get_payload_use_case: Data<Arc<GetPayloadUseCaseImpl>>,
+    id: Path<String>,
+    request: Json<UnlockPayloadRequest>,
+) -> impl Responder {
+    info!("Processing unlock payload request");
+
+    let hash_id = match parse_hash_id(&id) {
+        Ok(hash_id) => hash_id,
+        Err(e) => return error_response(e),
+    };
+
+    let password = request.into_inner().password;
+    match get_payload_use_case.execute(hash_id, Some(password), None).await {
+        Ok(response) => {
+            info!(
+                hash_id = %response.hash_id,
+                "Payload unlocked successfully"
+            );
+            HttpResponse::Ok().insert_header(etag(response.version)).json(response)
+        }
+        Err(e) => {
+            error!(error = %e, "Failed to unlock payload");
+            error_response(e)
+        }
+    }
+}
+
+/// Content-Disposition parameters suggesting `filename`.
+///
+/// Filenames outside ASCII are sent as `filename*` (RFC 6266), with an
+/// ASCII `filename` fallback for older clients.
+fn disposition_filename(filename: String) -> Vec<DispositionParam> {
+    if filename.is_ascii() {
+        return vec![DispositionParam::Filename(filename)];
+    }
+    let fallback = filename
+        .chars()
+        .map(|c| if c.is_ascii() { c } else { '_' })
+        .collect();
+    vec![
+        DispositionParam::Filename(fallback),
+        DispositionParam::FilenameExt(ExtendedValue {
+            charset: Charset::Ext("UTF-8".to_string()),
+            language_tag: None,
+            value: filename.into_bytes(),
+        }),
+    ]
+}
+
+/// Get the raw content of a payload by ID.
+///
+/// Unlike [`get_payload`], the stored content is returned as the response
+/// body with the payload's MIME type as `Content-Type`, so it can be used
+/// directly by browsers, `<img>` tags or `curl -o`. Expiry checks, view
+/// tracking and passwords are the same as for the JSON endpoint.
+///
+/// # Response Headers
+///
+/// - `Content-Type`: the stored MIME type
+/// - `Content-Length`: size of the content in bytes
+/// - `Content-Disposition`: `inline`, or `attachment` for MIME types that
+///   must not be displayed by browsers, with the payload's filename if it
+///   has one, or else a name derived from its hash ID
+/// - `Cache-Control`: `no-store`, since payloads are ephemeral and views are tracked
+#[tracing::instrument(
+    name = "Get raw payload",
+    skip(get_payload_use_case, mime_registry, req),
+    fields(hash_id = %id)
+)]
+pub async fn get_raw_payload(
+    get_payload_use_case: Data<Arc<GetPayloadUseCaseImpl>>,
+    mime_registry: Data<Arc<MimeRegistry>>,
+    id: Path<String>,
+    req: HttpRequest,
+) -> impl Responder {
+    info!("Processing get raw payload request");
+
+    let hash_id = match parse_hash_id(&id) {
+        Ok(hash_id) => hash_id,
+        Err(e) => return error_response(e),
+    };
+
+    match get_payload_use_case.execute(hash_id, password_header(&req), if_none_match(&req)).await {
+        Ok(response) => {
+            info!(
+                hash_id = %response.hash_id,
+                "Raw payload retrieved successfully"
+            );
+
+            let content = match response.decoded_content() {
+                Ok(content) => content,
+                Err(e) => {
+                    error!(error = %e, "Failed to decode payload content");
+                    return HttpResponse::InternalServerError().json(serde_json::json!({
                         "error": "An unexpected error occurred"
-                    }))
+                    }));
                 }
-            }
+            };
+
+            let policy = match MimeType::try_from(response.mime_type.as_str()) {
+                Ok(mime_type) => mime_registry.serving_policy(&mime_type),
+                Err(_) => MimePolicy::new("bin").with_inline(false),
+            };
+            let filename = response
+                .filename
+                .clone()
+                .unwrap_or_else(|| format!("{}.{}", response.hash_id, policy.extension()));
+            let disposition = ContentDisposition {
+                disposition: if policy.is_inline() {
+                    DispositionType::Inline
+                } else {
+                    DispositionType::Attachment
+                },
+                parameters: disposition_filename(filename),
+            };
+
+            HttpResponse::Ok()
+                .insert_header(etag(response.version))
+                .content_type(response.mime_type)
+                .insert_header((header::CONTENT_LENGTH, content.len()))
+                .insert_header(disposition)
+                .insert_header((header::CACHE_CONTROL, "private, no-store, max-age=0"))
+                .insert_header((header::X_CONTENT_TYPE_OPTIONS, "nosniff"))
+                // Stored HTML must not run scripts on our origin
+                .insert_header((header::CONTENT_SECURITY_POLICY, "sandbox"))
+                .body(content)
+        }
+        Err(e) => {
+            error!(error = %e, "Failed to retrieve raw payload");
+            error_response(e)
+        }
+    }
+}
+
+/// Change when a payload expires.
+///
+/// This endpoint extends, shortens or revokes the expiry of a payload. Like
+/// deleting, it requires the management token returned at creation as
+/// `Authorization: Bearer <token>`.
+///
+/// # Request
+///
+/// ```json
+/// {
+///     "expires_in": "6h"
+/// }
+/// ```
+///
+/// `expiry_time` sets an absolute expiry instead, and `"revoke": true`
+/// expires the payload immediately. With `If-Match`, the change is only
+/// made if the payload is still at a named version, and answered with
+/// `412 Precondition Failed` otherwise.
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "hash_id": "unique-hash-id",
+///     "version": 2,
+///     "updated_at": "2023-01-01T00:00:00Z",
+///     "expiry_time": "2023-01-01T06:00:00Z"
+/// }
+/// ```
+#[tracing::instrument(
+    name = "Update payload",
+    skip(update_payload_use_case, req, request),
+    fields(hash_id = %id)
+)]
+pub async fn update_payload(
+    update_payload_use_case: Data<Arc<UpdatePayloadUseCaseImpl>>,
+    id: Path<String>,
+    req: HttpRequest,
+    request: Json<UpdatePayloadRequest>,
+) -> impl Responder {
+    info!("Processing update payload request");
+
+    let hash_id = match parse_hash_id(&id) {
+        Ok(hash_id) => hash_id,
+        Err(e) => return error_response(e),
+    };
+
+    match update_payload_use_case
+        .update(&hash_id, bearer_token(&req), if_match(&req), request.into_inner())
+        .await
+    {
+        Ok(response) => {
+            info!(expiry_time = %response.expiry_time, "Payload updated successfully");
+            HttpResponse::Ok().insert_header(etag(response.version)).json(response)
+        }
+        Err(e) => {
+            error!(error = %e, "Failed to update payload");
+            error_response(e)
+        }
+    }
+}
+
+/// Replace the content of a payload.
+///
+/// The previous content is kept as a revision, retrievable from
+/// `/payloads/{id}/revisions/{n}` until the payload expires. Like deleting,
+/// it requires the management token returned at creation as
+/// `Authorization: Bearer <token>`.
+///
+/// # Request
+///
+/// ```json
+/// {
+///     "content": "Your new content here",
+///     "mime_type": "text/plain"
+/// }
+/// ```
+///
+/// `mime_type` keeps the current MIME type when left out, and `encoding`
+/// works as when creating a payload. `If-Match` works as for
+/// [`update_payload`].
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "hash_id": "unique-hash-id",
+///     "revision": 2,
+///     "version": 2,
+///     "mime_type": "text/plain",
+///     "updated_at": "2023-01-01T00:30:00Z",
+///     "expiry_time": "2023-01-01T01:00:00Z"
+/// }
+/// ```
+#[tracing::instrument(
+    name = "Replace payload",
+    skip(replace_payload_use_case, req, request),
+    fields(
+        hash_id = %id,
+        payload_size = %request.content.len(),
+        mime_type = ?request.mime_type
+    )
+)]
+pub async fn replace_payload(
+    replace_payload_use_case: Data<Arc<ReplacePayloadUseCaseImpl>>,
+    id: Path<String>,
+    req: HttpRequest,
+    request: Json<ReplacePayloadRequest>,
+) -> impl Responder {
+    info!("Processing replace payload request");
+
+    let hash_id = match parse_hash_id(&id) {
+        Ok(hash_id) => hash_id,
+        Err(e) => return error_response(e),
+    };
+
+    match replace_payload_use_case
+        .replace(&hash_id, bearer_token(&req), if_match(&req), request.into_inner())
+        .await
+    {
+        Ok(response) => {
+            info!(revision = response.revision, "Payload content replaced successfully");
+            HttpResponse::Ok().insert_header(etag(response.version)).json(response)
+        }
+        Err(e) => {
+            error!(error = %e, "Failed to replace payload content");
+            error_response(e)
         }
     }
 }
 
 /// Delete a payload by ID.
 ///
-/// This endpoint deletes a payload by its ID.
+/// This endpoint deletes a payload by its ID. It requires the management
+/// token returned at creation as `Authorization: Bearer <token>`.
+/// `If-Match` works as for [`update_payload`].
 #[tracing::instrument(
     name = "Delete payload",
-    skip(delete_payload_use_case),
+    skip(delete_payload_use_case, req),
     fields(hash_id = %id)
 )]
 pub async fn delete_payload(
     delete_payload_use_case: Data<Arc<DeletePayloadUseCaseImpl>>,
     id: Path<String>,
+    req: HttpRequest,
 ) -> impl Responder {
     info!("Processing delete payload request");
     
+    let hash_id = match parse_hash_id(&id) {
+        Ok(hash_id) => hash_id,
+        Err(e) => return error_response(e),
+    };
+    
     // Delete the payload
-    match delete_payload_use_case.delete(&id).await {
+    match delete_payload_use_case
+        .delete(&hash_id, bearer_token(&req), if_match(&req))
+        .await
+    {
         Ok(_) => {
             info!("Payload deleted successfully");
             HttpResponse::NoContent().finish()
         }
         Err(e) => {
-            error!(error = %e, error_type = ?std::any::type_name_of_val(&e), "Failed to delete payload");
-            match e {
-                UseCaseError::RepositoryError(e) => {
-                    error!(repo_error = %e, "Repository error details");
-                    // Check if the error message indicates the payload was not found
-                    if e.to_string().contains("not found") {
-                        HttpResponse::NotFound().json(serde_json::json!({
-                            "error": "Payload not found"
-                        }))
-                    } else {
-                        HttpResponse::InternalServerError().json(serde_json::json!({
-                            "error": format!("Failed to delete payload: {}", e)
-                        }))
-                    }
-                }
-                _ => {
-                    error!(other_error = %e, "Unexpected error type");
-                    HttpResponse::InternalServerError().json(serde_json::json!({
-                        "error": format!("An unexpected error occurred: {}", e)
-                    }))
-                }
-            }
+            error!(error = %e, "Failed to delete payload");
+            error_response(e)
         }
     }
 }
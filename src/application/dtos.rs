@@ -8,15 +8,95 @@
 //! DTOs are designed to be API-version specific and decouple the domain model
 //! from the external interface.
 
+use std::collections::BTreeMap;
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
 use chrono::{DateTime, Utc};
-use lazy_static::lazy_static;
-use regex::Regex;
 use serde::{Deserialize, Serialize};
-use validator::Validate;
+use validator::{Validate, ValidationError};
+
+use crate::domain::mime_type::MimeType;
 
-lazy_static! {
-    /// Regular expression for validating MIME types
-    static ref MIME_TYPE_REGEX: Regex = Regex::new(r"^[a-z]+/[a-z0-9.+-]+$").unwrap();
+/// Transport encoding of the `content` field in requests and responses.
+///
+/// Text is sent as a plain JSON string, while binary content such as images
+/// must be base64 encoded to survive JSON transport.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContentEncoding {
+    /// Content is a UTF-8 string (the default)
+    #[default]
+    Utf8,
+    /// Content is standard base64 encoded bytes
+    Base64,
+}
+
+impl ContentEncoding {
+    /// Chooses the encoding used to return stored content.
+    ///
+    /// Textual MIME types holding valid UTF-8 are returned as-is; everything
+    /// else is base64 encoded.
+    pub fn for_content(mime_type: &MimeType, content: &[u8]) -> Self {
+        if mime_type.is_text() && std::str::from_utf8(content).is_ok() {
+            Self::Utf8
+        } else {
+            Self::Base64
+        }
+    }
+
+    /// Decodes transported content into raw bytes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the content is not valid base64 when the
+    /// encoding is `Base64`.
+    pub fn decode(&self, content: &str) -> Result<Vec<u8>, base64::DecodeError> {
+        match self {
+            Self::Utf8 => Ok(content.as_bytes().to_vec()),
+            Self::Base64 => BASE64.decode(content),
+        }
+    }
+
+    /// Encodes raw bytes for transport.
+    ///
+    /// Invalid UTF-8 is replaced when encoding as `Utf8`, so callers should
+    /// pick the encoding with [`ContentEncoding::for_content`].
+    pub fn encode(&self, content: &[u8]) -> String {
+        match self {
+            Self::Utf8 => String::from_utf8_lossy(content).into_owned(),
+            Self::Base64 => BASE64.encode(content),
+        }
+    }
+}
+
+/// Most tags a payload may have
+const MAX_TAGS: usize = 32;
+
+/// Longest tag name, in characters
+const MAX_TAG_NAME_LENGTH: usize = 64;
+
+/// Longest tag value, in characters
+const MAX_TAG_VALUE_LENGTH: usize = 256;
+
+/// Checks the number of tags and the length of their names and values.
+fn validate_tags(tags: &BTreeMap<String, String>) -> Result<(), ValidationError> {
+    let invalid = |message: String| {
+        let mut error = ValidationError::new("tags");
+        error.message = Some(message.into());
+        Err(error)
+    };
+    if tags.len() > MAX_TAGS {
+        return invalid(format!("At most {} tags are allowed", MAX_TAGS));
+    }
+    for (name, value) in tags {
+        if name.is_empty() || name.chars().count() > MAX_TAG_NAME_LENGTH {
+            return invalid(format!("Tag names must be between 1 and {} characters", MAX_TAG_NAME_LENGTH));
+        }
+        if value.chars().count() > MAX_TAG_VALUE_LENGTH {
+            return invalid(format!("Tag values cannot exceed {} characters", MAX_TAG_VALUE_LENGTH));
+        }
+    }
+    Ok(())
 }
 
 /// Request DTO for creating a new payload.
@@ -28,25 +108,90 @@ lazy_static! {
 /// {
 ///   "content": "Your payload content here",
 ///   "mime_type": "text/plain",
-///   "expiry_time": "2024-03-14T12:00:00Z"
+///   "encoding": "utf8",
+///   "expires_in": "2h",
+///   "one_time": false,
+///   "max_views": 5,
+///   "password": "correct horse battery staple",
+///   "share_code": true,
+///   "alias": "release-notes-42",
+///   "filename": "release-notes.txt",
+///   "title": "Release notes",
+///   "description": "Notes for the 4.2 release",
+///   "tags": { "team": "platform" }
 /// }
 /// ```
-#[derive(Debug, Deserialize, Validate)]
+#[derive(Debug, Default, Deserialize, Validate)]
 pub struct CreatePayloadRequest {
     /// The content to be shared. Must not be empty.
     #[validate(length(min = 1, message = "Content cannot be empty"))]
     #[validate(length(max = 1048576, message = "Content cannot exceed 1MB"))]
     pub content: String,
 
-    /// Optional MIME type of the content. If not provided, defaults to "text/plain".
-    #[validate(regex(
-        path = "MIME_TYPE_REGEX",
-        message = "Invalid MIME type format"
-    ))]
+    /// Optional MIME type of the content, with optional parameters such as
+    /// `charset`, or "auto" to detect it from the content. If not provided,
+    /// defaults to the server's default type, usually "text/plain". Must be
+    /// one of the MIME types enabled on the server and match the content.
     pub mime_type: Option<String>,
 
-    /// Optional expiry time. If not provided, defaults to 24 hours from creation.
+    /// Optional encoding of `content`. If not provided, defaults to "utf8".
+    /// Binary content must be sent as "base64".
+    pub encoding: Option<ContentEncoding>,
+
+    /// Optional expiry time. If neither this nor `expires_in` is provided,
+    /// the server's default lifetime applies (24 hours unless configured).
     pub expiry_time: Option<DateTime<Utc>>,
+
+    /// Optional lifetime relative to creation, such as "90s", "2h" or "7d".
+    /// Cannot be combined with `expiry_time`.
+    pub expires_in: Option<String>,
+
+    /// Optional time the payload may go unread, such as "30m". Every read
+    /// pushes the expiry forward, up to the expiry time.
+    pub idle_timeout: Option<String>,
+
+    /// Optional time the payload lives once first read, such as "10m".
+    /// Cannot be combined with `idle_timeout`.
+    pub expires_after_view: Option<String>,
+
+    /// Whether the payload is deleted on its first read. Defaults to false.
+    pub one_time: Option<bool>,
+
+    /// Optional number of times the payload can be viewed. Unlimited if not provided.
+    #[validate(range(min = 1, message = "Maximum views must be at least 1"))]
+    pub max_views: Option<u64>,
+
+    /// Optional password required to read the payload. Only a hash is stored.
+    #[validate(length(min = 1, max = 1024, message = "Password must be between 1 and 1024 characters"))]
+    pub password: Option<String>,
+
+    /// Whether to also reserve a short share code such as
+    /// `7-crossover-clockwork` for the payload. Defaults to false.
+    pub share_code: Option<bool>,
+
+    /// Optional custom name for the payload, such as `release-notes-42`.
+    /// Lowercase letters, digits and hyphens, starting with a letter.
+    pub alias: Option<String>,
+
+    /// Optional filename suggested when the raw content is downloaded, such
+    /// as `report.pdf`. Cannot contain path separators.
+    #[validate(length(min = 1, max = 255, message = "Filename must be between 1 and 255 characters"))]
+    pub filename: Option<String>,
+
+    /// Optional short title of the payload.
+    #[validate(length(min = 1, max = 200, message = "Title must be between 1 and 200 characters"))]
+    pub title: Option<String>,
+
+    /// Optional description of what the payload is for.
+    #[validate(length(max = 2000, message = "Description cannot exceed 2000 characters"))]
+    pub description: Option<String>,
+
+    /// Optional free-form labels, such as `{"team": "payments"}`. Also
+    /// accepted as `labels`. At most 32, with names of up to 64 and values
+    /// of up to 256 characters.
+    #[serde(alias = "labels")]
+    #[validate(custom = "validate_tags")]
+    pub tags: Option<BTreeMap<String, String>>,
 }
 
 /// Response DTO for successful payload creation.
@@ -58,12 +203,18 @@ pub struct CreatePayloadResponse {
     /// The unique identifier for accessing the payload
     pub hash_id: String,
     
-    /// The stored content
+    /// The stored content, encoded as described by `encoding`
     pub content: String,
     
+    /// How `content` is encoded
+    pub encoding: ContentEncoding,
+    
     /// The MIME type of the content
     pub mime_type: String,
     
+    /// Version of the payload, which changes whenever the owner changes it
+    pub version: u64,
+    
     /// When the payload was created
     pub created_at: DateTime<Utc>,
     
@@ -75,6 +226,28 @@ pub struct CreatePayloadResponse {
     
     /// When the payload will expire
     pub expiry_time: DateTime<Utc>,
+    
+    /// Whether the payload is deleted on its first read
+    pub one_time: bool,
+    
+    /// How many times the payload has been viewed
+    pub view_count: u64,
+    
+    /// Maximum number of views allowed, if limited
+    pub max_views: Option<u64>,
+    
+    /// Whether a password is required to read the payload
+    pub password_protected: bool,
+    
+    /// Human-readable code resolving to the payload, if one was requested
+    pub share_code: Option<String>,
+    
+    /// Custom name resolving to the payload, if one was requested
+    pub alias: Option<String>,
+    
+    /// Secret token required to delete or otherwise manage the payload.
+    /// It is only returned here and cannot be recovered later.
+    pub management_token: String,
 }
 
 /// Response DTO for retrieving a payload.
@@ -86,11 +259,20 @@ pub struct GetPayloadResponse {
     /// The unique identifier of the payload
     pub hash_id: String,
     
-    /// The stored content
+    /// The stored content, encoded as described by `encoding`
     pub content: String,
     
+    /// How `content` is encoded
+    pub encoding: ContentEncoding,
+    
     /// The MIME type of the content
     pub mime_type: String,
+
+    /// Number of the content revision returned
+    pub revision: u64,
+
+    /// Version of the payload, which changes whenever the owner changes it
+    pub version: u64,
     
     /// When the payload was created
     pub created_at: DateTime<Utc>,
@@ -103,6 +285,169 @@ pub struct GetPayloadResponse {
     
     /// When the payload will expire
     pub expiry_time: DateTime<Utc>,
+    
+    /// Whether the payload is deleted on its first read
+    pub one_time: bool,
+    
+    /// How many times the payload has been viewed, including this view
+    pub view_count: u64,
+    
+    /// Maximum number of views allowed, if limited
+    pub max_views: Option<u64>,
+    
+    /// Whether a password is required to read the payload
+    pub password_protected: bool,
+    
+    /// Human-readable code resolving to the payload, if it has one
+    pub share_code: Option<String>,
+    
+    /// Custom name resolving to the payload, if it has one
+    pub alias: Option<String>,
+
+    /// Filename suggested for downloads, if one was given
+    pub filename: Option<String>,
+
+    /// Title of the payload, if one was given
+    pub title: Option<String>,
+
+    /// Description of the payload, if one was given
+    pub description: Option<String>,
+
+    /// Free-form labels of the payload
+    pub tags: BTreeMap<String, String>,
+}
+
+impl GetPayloadResponse {
+    /// Decodes `content` back into the raw stored bytes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `content` does not match its `encoding`.
+    pub fn decoded_content(&self) -> Result<Vec<u8>, base64::DecodeError> {
+        self.encoding.decode(&self.content)
+    }
+}
+
+/// Request DTO for unlocking a password-protected payload.
+///
+/// This struct represents the expected JSON structure for
+/// POST /api/v1/payloads/{id}/unlock
+#[derive(Debug, Deserialize)]
+pub struct UnlockPayloadRequest {
+    /// The password the payload was created with
+    pub password: String,
+}
+
+/// Request DTO for changing when a payload expires.
+///
+/// This struct represents the expected JSON structure for
+/// PATCH /api/v1/payloads/{id}. Exactly one of the fields must be given.
+///
+/// # Example JSON
+/// ```json
+/// {
+///   "expires_in": "6h"
+/// }
+/// ```
+#[derive(Debug, Default, Deserialize)]
+pub struct UpdatePayloadRequest {
+    /// New absolute expiry time
+    pub expiry_time: Option<DateTime<Utc>>,
+
+    /// New lifetime counted from now, such as "90s", "2h" or "7d"
+    pub expires_in: Option<String>,
+
+    /// Whether to expire the payload immediately
+    pub revoke: Option<bool>,
+}
+
+/// Response DTO for a changed payload.
+#[derive(Debug, Serialize)]
+pub struct UpdatePayloadResponse {
+    /// The unique identifier of the payload
+    pub hash_id: String,
+
+    /// New version of the payload
+    pub version: u64,
+
+    /// When the payload was last updated
+    pub updated_at: DateTime<Utc>,
+
+    /// When the payload will expire
+    pub expiry_time: DateTime<Utc>,
+}
+
+/// Request DTO for replacing the content of a payload.
+///
+/// This struct represents the expected JSON structure for
+/// PUT /api/v1/payloads/{id}. The replaced content stays available as a
+/// revision.
+///
+/// # Example JSON
+/// ```json
+/// {
+///   "content": "Updated content",
+///   "mime_type": "text/plain",
+///   "encoding": "utf8"
+/// }
+/// ```
+#[derive(Debug, Deserialize, Validate)]
+pub struct ReplacePayloadRequest {
+    /// The new content. Must not be empty.
+    #[validate(length(min = 1, message = "Content cannot be empty"))]
+    #[validate(length(max = 1048576, message = "Content cannot exceed 1MB"))]
+    pub content: String,
+
+    /// Optional MIME type of the new content, or "auto" to detect it from
+    /// the content. If not provided, the current MIME type is kept, and the
+    /// new content must match it.
+    pub mime_type: Option<String>,
+
+    /// Optional encoding of `content`. If not provided, defaults to "utf8".
+    pub encoding: Option<ContentEncoding>,
+}
+
+/// Response DTO for replaced payload content.
+#[derive(Debug, Serialize)]
+pub struct ReplacePayloadResponse {
+    /// The unique identifier of the payload
+    pub hash_id: String,
+
+    /// Number of the new content revision
+    pub revision: u64,
+
+    /// New version of the payload
+    pub version: u64,
+
+    /// The MIME type of the new content
+    pub mime_type: String,
+
+    /// When the payload was last updated
+    pub updated_at: DateTime<Utc>,
+
+    /// When the payload will expire
+    pub expiry_time: DateTime<Utc>,
+}
+
+/// Payload versions named by a conditional request, such as the entity
+/// tags of an `If-Match` or `If-None-Match` header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VersionMatch {
+    /// Any version at all
+    Any,
+
+    /// Only the listed versions
+    Versions(Vec<u64>),
+}
+
+impl VersionMatch {
+    /// Returns true if `version` is one of the named versions.
+    pub fn matches(&self, version: u64) -> bool {
+        match self {
+            Self::Any => true,
+            Self::Versions(versions) => versions.contains(&version),
+        }
+    }
 }
 
 /// Error response DTO.
@@ -129,24 +474,78 @@ mod tests {
         let valid_request = CreatePayloadRequest {
             content: "Test content".to_string(),
             mime_type: Some("text/plain".to_string()),
-            expiry_time: None,
+            ..Default::default()
         };
         assert!(valid_request.validate().is_ok());
 
         // Empty content
         let empty_content = CreatePayloadRequest {
             content: "".to_string(),
-            mime_type: None,
-            expiry_time: None,
+            ..Default::default()
         };
         assert!(empty_content.validate().is_err());
+    }
 
-        // Invalid MIME type
-        let invalid_mime = CreatePayloadRequest {
-            content: "Test content".to_string(),
-            mime_type: Some("invalid-mime-type".to_string()),
-            expiry_time: None,
+    #[test]
+    fn test_metadata_validation() {
+        let request = |json: serde_json::Value| {
+            let mut json = json;
+            json["content"] = "Test content".into();
+            serde_json::from_value::<CreatePayloadRequest>(json).unwrap()
         };
-        assert!(invalid_mime.validate().is_err());
+
+        let valid = request(serde_json::json!({
+            "filename": "notes.txt",
+            "title": "Notes",
+            "description": "",
+            "labels": { "team": "payments" }
+        }));
+        assert!(valid.validate().is_ok());
+        assert_eq!(valid.tags.unwrap()["team"], "payments");
+
+        for invalid in [
+            serde_json::json!({ "filename": "" }),
+            serde_json::json!({ "filename": "a".repeat(256) }),
+            serde_json::json!({ "title": "" }),
+            serde_json::json!({ "description": "a".repeat(2001) }),
+            serde_json::json!({ "tags": { "": "empty name" } }),
+            serde_json::json!({ "tags": { "team": "a".repeat(257) } }),
+            serde_json::json!({ "tags": (0..33).map(|i| (i.to_string(), "x")).collect::<BTreeMap<_, _>>() }),
+        ] {
+            assert!(request(invalid.clone()).validate().is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_content_encoding_round_trip() {
+        let bytes = vec![0x47, 0x49, 0x46, 0x00, 0xff];
+        let encoded = ContentEncoding::Base64.encode(&bytes);
+        assert_eq!(ContentEncoding::Base64.decode(&encoded).unwrap(), bytes);
+        assert!(ContentEncoding::Base64.decode("not base64!").is_err());
+        assert_eq!(ContentEncoding::Utf8.decode("héllo").unwrap(), "héllo".as_bytes());
+    }
+
+    #[test]
+    fn test_content_encoding_for_content() {
+        assert_eq!(
+            ContentEncoding::for_content(&MimeType::text_plain(), b"hello"),
+            ContentEncoding::Utf8
+        );
+        assert_eq!(
+            ContentEncoding::for_content(&MimeType::text_plain(), &[0xff, 0xfe]),
+            ContentEncoding::Base64
+        );
+        assert_eq!(
+            ContentEncoding::for_content(&MimeType::try_from("image/png").unwrap(), b"ascii"),
+            ContentEncoding::Base64
+        );
+    }
+
+    #[test]
+    fn test_encoding_deserializes_from_lowercase() {
+        let request: CreatePayloadRequest = serde_json::from_str(
+            r#"{"content": "aGk=", "encoding": "base64"}"#
+        ).unwrap();
+        assert_eq!(request.encoding, Some(ContentEncoding::Base64));
     }
 }
@@ -1,18 +1,241 @@
 //! Repository trait for data persistence.
 
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use thiserror::Error;
 
-use crate::domain::{hash_id::HashId, payload::Payload};
+use crate::domain::{hash_id::HashId, payload::Payload, revision::Revision};
+
+/// Errors that can occur in a repository.
+///
+/// Backends translate their own failures into these variants so callers can
+/// react to the kind of failure rather than to its message.
+#[derive(Debug, Error)]
+pub enum RepositoryError {
+    /// The payload does not exist (or has expired)
+    #[error("Payload not found")]
+    NotFound,
+
+    /// The operation conflicts with the current state of the payload
+    #[error("Conflict: {0}")]
+    Conflict(String),
+
+    /// The payload is no longer at the version the change was based on
+    #[error("Payload is no longer at version {0}")]
+    VersionMismatch(u64),
+
+    /// The payload has used up its views
+    #[error("Payload view limit reached")]
+    ViewLimitReached,
+
+    /// The payload requires a password that has not been checked
+    #[error("Payload requires a password")]
+    PasswordRequired,
+
+    /// Password attempts on the payload are being throttled
+    #[error("Password attempts are being throttled")]
+    UnlockThrottled,
+
+    /// The storage backend could not be reached
+    #[error("Storage unavailable: {0}")]
+    Unavailable(String),
+
+    /// A payload could not be serialized or deserialized
+    #[error("Serialization error: {0}")]
+    Serialization(String),
+
+    /// The storage backend has no room for the payload
+    #[error("Storage quota exceeded: {0}")]
+    QuotaExceeded(String),
+
+    /// Any other storage failure
+    #[error("Storage error: {0}")]
+    Internal(String),
+}
+
+impl From<serde_json::Error> for RepositoryError {
+    fn from(err: serde_json::Error) -> Self {
+        RepositoryError::Serialization(err.to_string())
+    }
+}
+
+/// Reserve a password attempt on a stored payload, like
+/// [`Repository::try_unlock_attempt`], returning the payload as it was
+/// before.
+///
+/// Shared by backends that update payloads in process; must be called while
+/// holding the lock that makes the update atomic.
+pub fn reserve_unlock_attempt(stored: &mut Payload, at: DateTime<Utc>) -> Result<Payload, RepositoryError> {
+    let before = stored.clone();
+    if stored.is_password_protected() && !stored.reserve_unlock_attempt(at) {
+        return Err(RepositoryError::UnlockThrottled);
+    }
+    Ok(before)
+}
+
+/// Number of the oldest revision kept once `previous` is stored, keeping at
+/// most `max_revisions` earlier revisions of a payload.
+///
+/// Revisions are numbered without gaps, so every revision numbered below it
+/// is dropped.
+pub fn oldest_kept_revision(previous: &Revision, max_revisions: usize) -> u64 {
+    (previous.number() + 1).saturating_sub(max_revisions as u64)
+}
+
+/// Check that a stored payload is at the expected version, if one is given.
+///
+/// Shared by backends that compare versions in process; the check must be
+/// made while holding whatever lock makes the following change atomic.
+pub fn check_version(stored: &Payload, version: Option<u64>) -> Result<(), RepositoryError> {
+    match version {
+        Some(version) if version != stored.version() => Err(RepositoryError::VersionMismatch(version)),
+        _ => Ok(()),
+    }
+}
 
 /// Repository trait for storing and retrieving payloads.
 #[async_trait]
 pub trait Repository: Send + Sync {
     /// Save a payload to the repository.
-    async fn save(&self, payload: &Payload) -> Result<(), anyhow::Error>;
+    ///
+    /// Replaces a stored payload with the same hash ID, including any
+    /// revisions kept for it.
+    async fn save(&self, payload: &Payload) -> Result<(), RepositoryError>;
+
+    /// Save a new payload, reserving its hash ID.
+    ///
+    /// Unlike [`Repository::save`], this never replaces a stored payload:
+    /// it returns `RepositoryError::Conflict` if the hash ID is already
+    /// taken, atomically with respect to concurrent inserts.
+    async fn insert(&self, payload: &Payload) -> Result<(), RepositoryError>;
 
     /// Get a payload from the repository by its hash ID.
-    async fn get(&self, hash_id: &HashId) -> Result<Option<Payload>, anyhow::Error>;
+    ///
+    /// Returns `Ok(None)` if the payload does not exist or has expired.
+    async fn get(&self, hash_id: &HashId) -> Result<Option<Payload>, RepositoryError>;
+
+    /// Atomically fetch a payload and record a view of it.
+    ///
+    /// In one step, checks that the payload has not expired, deleting it if
+    /// it has, returns `RepositoryError::PasswordRequired` for a
+    /// password-protected payload unless its password was checked and
+    /// `unlocked` is set, returns `RepositoryError::ViewLimitReached` if the
+    /// payload has used up its views, and otherwise marks it as viewed and
+    /// increments its view count in place, so concurrent views are never
+    /// lost. No view is recorded on errors. One-time payloads are removed as
+    /// they are viewed, so at most one of any number of concurrent callers
+    /// receives them. The expiry stays as stored unless the payload's expiry
+    /// mode moves it with views. Returns the payload as viewed, or `Ok(None)`
+    /// if it does not exist or has expired.
+    async fn view(&self, hash_id: &HashId, unlocked: bool) -> Result<Option<Payload>, RepositoryError>;
+
+    /// Atomically reserve a password attempt made at `at`.
+    ///
+    /// In one step, returns `RepositoryError::UnlockThrottled` if password
+    /// attempts on the payload are being throttled, and otherwise records
+    /// the attempt as a wrong password, so concurrent guesses are throttled
+    /// as they are made rather than once they have been checked. Attempts
+    /// that turn out to be right are handed back with
+    /// [`Repository::release_unlock_attempt`]. Nothing is reserved for
+    /// payloads without a password. Returns the payload as it was before the
+    /// attempt, or `Ok(None)` if it does not exist or has expired.
+    async fn try_unlock_attempt(
+        &self,
+        hash_id: &HashId,
+        at: DateTime<Utc>,
+    ) -> Result<Option<Payload>, RepositoryError>;
+
+    /// Hand back a password attempt reserved at `at` that turned out to be
+    /// right, like [`Payload::release_unlock_attempt`], in place. `previous`
+    /// is the time of the failed unlock before it. Does nothing if the
+    /// payload does not exist.
+    async fn release_unlock_attempt(
+        &self,
+        hash_id: &HashId,
+        at: DateTime<Utc>,
+        previous: Option<DateTime<Utc>>,
+    ) -> Result<(), RepositoryError>;
+
+    /// Atomically change when a payload expires.
+    ///
+    /// Sets the expiry time, marks the payload as updated and moves it to
+    /// its next version without rewriting its content where the backend
+    /// allows it. An expiry time that has already passed expires the payload
+    /// at once. If `version` is given, returns
+    /// `RepositoryError::VersionMismatch` unless the stored payload is still
+    /// at that version. Returns the payload as updated, or `Ok(None)` if it
+    /// does not exist or has expired.
+    async fn update_expiry(
+        &self,
+        hash_id: &HashId,
+        expiry_time: DateTime<Utc>,
+        version: Option<u64>,
+    ) -> Result<Option<Payload>, RepositoryError>;
+
+    /// Atomically replace the content of a stored payload.
+    ///
+    /// Stores the content, MIME type, revision and version numbers and
+    /// update time of `payload`, keeping `previous` as an earlier revision
+    /// and dropping the oldest revisions beyond `max_revisions`. View and
+    /// unlock counters and the expiry stay as stored. Returns
+    /// `RepositoryError::VersionMismatch` unless the stored payload is still
+    /// at `version`, so concurrent changes never lose content. Returns the
+    /// payload as updated, or `Ok(None)` if it does not exist or has expired.
+    async fn replace_content(
+        &self,
+        payload: &Payload,
+        previous: &Revision,
+        version: u64,
+        max_revisions: usize,
+    ) -> Result<Option<Payload>, RepositoryError>;
+
+    /// Get an earlier revision of a payload's content.
+    ///
+    /// Revisions expire and are deleted together with their payload. Returns
+    /// `Ok(None)` if the payload or the revision does not exist; the current
+    /// content is not returned as a revision.
+    async fn get_revision(
+        &self,
+        hash_id: &HashId,
+        number: u64,
+    ) -> Result<Option<Revision>, RepositoryError>;
 
     /// Delete a payload from the repository by its hash ID.
-    async fn delete(&self, hash_id: &HashId) -> Result<(), anyhow::Error>;
+    ///
+    /// Returns `RepositoryError::NotFound` if the payload does not exist. If
+    /// `version` is given, returns `RepositoryError::VersionMismatch` and
+    /// keeps the payload unless it is still at that version.
+    async fn delete(&self, hash_id: &HashId, version: Option<u64>) -> Result<(), RepositoryError>;
+
+    /// Reserve an alias resolving to a payload until `expiry_time`.
+    ///
+    /// Aliases are alternative names for a hash ID, such as share codes.
+    /// Returns `RepositoryError::Conflict` if the alias is already reserved,
+    /// atomically with respect to concurrent reservations. An alias stays
+    /// reserved until it expires, or until the payload naming it as its
+    /// alias or share code is deleted, viewed for the last time or expired
+    /// early, which releases it in the same step.
+    async fn insert_alias(
+        &self,
+        alias: &str,
+        hash_id: &HashId,
+        expiry_time: DateTime<Utc>,
+    ) -> Result<(), RepositoryError>;
+
+    /// Change when an alias reserved for `hash_id` expires.
+    ///
+    /// Keeps an alias in step with its payload's expiry. Does nothing if the
+    /// alias does not exist, has expired or resolves to a different payload.
+    /// An expiry time that has already passed releases the alias at once.
+    async fn update_alias_expiry(
+        &self,
+        alias: &str,
+        hash_id: &HashId,
+        expiry_time: DateTime<Utc>,
+    ) -> Result<(), RepositoryError>;
+
+    /// Resolve an alias to the hash ID it was reserved for.
+    ///
+    /// Returns `Ok(None)` if the alias does not exist or has expired.
+    async fn resolve_alias(&self, alias: &str) -> Result<Option<HashId>, RepositoryError>;
 }
@@ -1,5 +1,8 @@
 //! Test utilities for the application layer.
 
+pub mod repository_contract;
+mod use_cases_tests;
+
 use std::collections::HashMap;
 use std::sync::Mutex;
 use async_trait::async_trait;
@@ -7,19 +10,23 @@ use chrono::{DateTime, Utc};
 
 use crate::domain::hash_id::HashId;
 use crate::domain::payload::Payload;
-use crate::application::repository::Repository;
+use crate::domain::revision::Revision;
+use crate::application::repository::{
+    check_version, oldest_kept_revision, reserve_unlock_attempt, Repository, RepositoryError,
+};
 
 /// A mock repository implementation for testing.
+#[derive(Default)]
 pub struct MockRepository {
     payloads: Mutex<HashMap<String, Payload>>,
+    revisions: Mutex<HashMap<String, Vec<Revision>>>,
+    aliases: Mutex<HashMap<String, (HashId, DateTime<Utc>)>>,
 }
 
 impl MockRepository {
     /// Create a new mock repository.
     pub fn new() -> Self {
-        Self {
-            payloads: Mutex::new(HashMap::new()),
-        }
+        Self::default()
     }
     
     /// Add a payload to the repository for testing.
@@ -32,27 +39,227 @@ impl MockRepository {
     pub fn count(&self) -> usize {
         self.payloads.lock().unwrap().len()
     }
+
+    /// Release the names of a removed payload that still resolve to it.
+    fn release_names(&self, payload: &Payload) {
+        let mut aliases = self.aliases.lock().unwrap();
+        for name in payload.names() {
+            if aliases.get(name).is_some_and(|(owner, _)| owner == payload.hash_id()) {
+                aliases.remove(name);
+            }
+        }
+    }
+
+    /// Apply an update to a stored, unexpired payload and return it.
+    fn update(&self, hash_id: &HashId, update: impl FnOnce(&mut Payload)) -> Option<Payload> {
+        let mut payloads = self.payloads.lock().unwrap();
+        payloads
+            .get_mut(hash_id.as_string())
+            .filter(|payload| !payload.is_expired())
+            .map(|payload| {
+                update(payload);
+                payload.clone()
+            })
+    }
 }
 
 #[async_trait]
 impl Repository for MockRepository {
-    async fn save(&self, payload: &Payload) -> Result<(), anyhow::Error> {
+    async fn save(&self, payload: &Payload) -> Result<(), RepositoryError> {
         let hash_id = payload.hash_id().as_string().to_string();
+        self.revisions.lock().unwrap().remove(&hash_id);
         self.payloads.lock().unwrap().insert(hash_id, payload.clone());
         Ok(())
     }
     
-    async fn get(&self, hash_id: &HashId) -> Result<Option<Payload>, anyhow::Error> {
+    async fn insert(&self, payload: &Payload) -> Result<(), RepositoryError> {
+        let hash_id = payload.hash_id().as_string().to_string();
+        let mut payloads = self.payloads.lock().unwrap();
+        if payloads.get(&hash_id).is_some_and(|existing| !existing.is_expired()) {
+            return Err(RepositoryError::Conflict(format!("Hash ID {} is already taken", hash_id)));
+        }
+        self.revisions.lock().unwrap().remove(&hash_id);
+        payloads.insert(hash_id, payload.clone());
+        Ok(())
+    }
+    
+    async fn get(&self, hash_id: &HashId) -> Result<Option<Payload>, RepositoryError> {
         let hash_id_str = hash_id.as_string();
-        let result = self.payloads.lock().unwrap().get(hash_id_str).cloned();
+        // Storage backends never return payloads past their expiry time
+        let result = self.payloads.lock().unwrap()
+            .get(hash_id_str)
+            .filter(|payload| !payload.is_expired())
+            .cloned();
         Ok(result)
     }
     
-    async fn delete(&self, hash_id: &HashId) -> Result<(), anyhow::Error> {
+    async fn view(&self, hash_id: &HashId, unlocked: bool) -> Result<Option<Payload>, RepositoryError> {
+        let hash_id_str = hash_id.as_string();
+        let mut payloads = self.payloads.lock().unwrap();
+        let Some(stored) = payloads
+            .get_mut(hash_id_str)
+            .filter(|stored| !stored.is_expired())
+        else {
+            return Ok(None);
+        };
+        if stored.is_password_protected() && !unlocked {
+            return Err(RepositoryError::PasswordRequired);
+        }
+        if stored.view_limit_reached() {
+            return Err(RepositoryError::ViewLimitReached);
+        }
+        stored.mark_viewed();
+        let viewed = stored.clone();
+        if viewed.is_one_time() {
+            payloads.remove(hash_id_str);
+            self.revisions.lock().unwrap().remove(hash_id_str);
+            self.release_names(&viewed);
+        }
+        Ok(Some(viewed))
+    }
+
+    async fn try_unlock_attempt(
+        &self,
+        hash_id: &HashId,
+        at: DateTime<Utc>,
+    ) -> Result<Option<Payload>, RepositoryError> {
+        let mut reserved = None;
+        self.update(hash_id, |payload| reserved = Some(reserve_unlock_attempt(payload, at)));
+        reserved.transpose()
+    }
+
+    async fn release_unlock_attempt(
+        &self,
+        hash_id: &HashId,
+        at: DateTime<Utc>,
+        previous: Option<DateTime<Utc>>,
+    ) -> Result<(), RepositoryError> {
+        self.update(hash_id, |payload| payload.release_unlock_attempt(at, previous));
+        Ok(())
+    }
+    
+    async fn update_expiry(
+        &self,
+        hash_id: &HashId,
+        expiry_time: DateTime<Utc>,
+        version: Option<u64>,
+    ) -> Result<Option<Payload>, RepositoryError> {
+        let mut payloads = self.payloads.lock().unwrap();
+        let Some(stored) = payloads
+            .get_mut(hash_id.as_string())
+            .filter(|stored| !stored.is_expired())
+        else {
+            return Ok(None);
+        };
+        check_version(stored, version)?;
+        stored.set_expiry_time(expiry_time);
+        let updated = stored.clone();
+        if updated.is_expired() {
+            self.release_names(&updated);
+        }
+        Ok(Some(updated))
+    }
+
+    async fn replace_content(
+        &self,
+        payload: &Payload,
+        previous: &Revision,
+        version: u64,
+        max_revisions: usize,
+    ) -> Result<Option<Payload>, RepositoryError> {
+        let hash_id = payload.hash_id();
+        let mut payloads = self.payloads.lock().unwrap();
+        let Some(stored) = payloads
+            .get_mut(hash_id.as_string())
+            .filter(|stored| !stored.is_expired())
+        else {
+            return Ok(None);
+        };
+        check_version(stored, Some(version))?;
+
+        stored.restore_revision(payload);
+        let oldest = oldest_kept_revision(previous, max_revisions);
+        let mut revisions = self.revisions.lock().unwrap();
+        let kept = revisions.entry(hash_id.as_string().to_string()).or_default();
+        kept.push(previous.clone());
+        kept.retain(|revision| revision.number() >= oldest);
+        Ok(Some(stored.clone()))
+    }
+
+    async fn get_revision(
+        &self,
+        hash_id: &HashId,
+        number: u64,
+    ) -> Result<Option<Revision>, RepositoryError> {
+        let live = self.get(hash_id).await?.is_some();
+        let result = self.revisions.lock().unwrap()
+            .get(hash_id.as_string())
+            .filter(|_| live)
+            .and_then(|revisions| revisions.iter().find(|revision| revision.number() == number))
+            .cloned();
+        Ok(result)
+    }
+
+    async fn delete(&self, hash_id: &HashId, version: Option<u64>) -> Result<(), RepositoryError> {
         let hash_id_str = hash_id.as_string();
-        self.payloads.lock().unwrap().remove(hash_id_str);
+        let mut payloads = self.payloads.lock().unwrap();
+        if let Some(stored) = payloads.get(hash_id_str).filter(|stored| !stored.is_expired()) {
+            check_version(stored, version)?;
+        }
+        self.revisions.lock().unwrap().remove(hash_id_str);
+        let removed = payloads.remove(hash_id_str).inspect(|payload| self.release_names(payload));
+        match removed {
+            Some(payload) if !payload.is_expired() => Ok(()),
+            _ => Err(RepositoryError::NotFound),
+        }
+    }
+
+    async fn insert_alias(
+        &self,
+        alias: &str,
+        hash_id: &HashId,
+        expiry_time: DateTime<Utc>,
+    ) -> Result<(), RepositoryError> {
+        let mut aliases = self.aliases.lock().unwrap();
+        if aliases.get(alias).is_some_and(|(_, existing)| *existing > Utc::now()) {
+            return Err(RepositoryError::Conflict(format!("Alias {} is already taken", alias)));
+        }
+        aliases.insert(alias.to_string(), (hash_id.clone(), expiry_time));
+        Ok(())
+    }
+
+    async fn update_alias_expiry(
+        &self,
+        alias: &str,
+        hash_id: &HashId,
+        expiry_time: DateTime<Utc>,
+    ) -> Result<(), RepositoryError> {
+        let mut aliases = self.aliases.lock().unwrap();
+        if let Some(reserved) = aliases
+            .get_mut(alias)
+            .filter(|(existing, existing_expiry)| existing == hash_id && *existing_expiry > Utc::now())
+        {
+            reserved.1 = expiry_time;
+        }
         Ok(())
     }
+
+    async fn resolve_alias(&self, alias: &str) -> Result<Option<HashId>, RepositoryError> {
+        let result = self.aliases.lock().unwrap()
+            .get(alias)
+            .filter(|(_, expiry_time)| *expiry_time > Utc::now())
+            .map(|(hash_id, _)| hash_id.clone());
+        Ok(result)
+    }
+}
+
+mod contract {
+    use std::sync::Arc;
+
+    use super::MockRepository;
+    use super::repository_contract::repository_contract_tests;
+
+    repository_contract_tests!(((), Arc::new(MockRepository::new())));
 }
 
 /// Create a test payload with the given content and expiry time.
@@ -61,7 +268,7 @@ pub fn create_test_payload(
     expiry_time: Option<DateTime<Utc>>
 ) -> Payload {
     Payload::new(
-        content.to_string(),
+        content.as_bytes().to_vec(),
         Some("text/plain".to_string()),
         expiry_time,
     ).unwrap()
@@ -0,0 +1,803 @@
+//! Conformance suite for `Repository` implementations.
+//!
+//! Every backend is expected to behave identically with respect to the
+//! checks in this module. Backends instantiate the suite with the
+//! [`repository_contract_tests!`] macro, which expands to one test per check:
+//!
+//! ```ignore
+//! repository_contract_tests!(((), Arc::new(InMemoryRepository::default())));
+//! ```
+//!
+//! The argument is an expression evaluating to `(guard, repository)`. The
+//! guard is kept alive for the duration of each test, which lets backends
+//! tie temporary resources such as data directories to the test. Attributes
+//! given after the expression (e.g. `#[ignore = "..."]`) are applied to
+//! every generated test.
+
+use std::sync::Arc;
+
+use chrono::{Duration, Utc};
+use uuid::Uuid;
+
+use crate::application::repository::{Repository, RepositoryError};
+use crate::domain::{
+    alias::Alias, expiry::ExpiryMode, hash_id::HashId, metadata::Metadata, payload::Payload,
+    revision::DEFAULT_MAX_REVISIONS, share_code::ShareCode,
+};
+
+/// Number of tasks used by the concurrency checks
+const CONCURRENT_TASKS: usize = 16;
+
+/// A fresh alias starting with `prefix`. Aliases outlive a test on
+/// persistent backends, so each run reserves names of its own.
+fn unique_alias(prefix: &str) -> String {
+    format!("{}-{}", prefix, Uuid::new_v4().simple())
+}
+
+fn payload_with(content: &[u8], mime_type: Option<&str>, expires_in: Duration) -> Payload {
+    Payload::new(
+        content.to_vec(),
+        mime_type.map(str::to_string),
+        Some(Utc::now() + expires_in),
+    )
+    .unwrap()
+}
+
+/// A saved payload can be read back with identical content and metadata.
+pub async fn save_and_get(repository: Arc<dyn Repository>) {
+    let content = [0x89, b'P', b'N', b'G', b'\n', 0x00, 0xff, b'"'];
+    let metadata = Metadata::default()
+        .with_filename("image.png")
+        .unwrap()
+        .with_description(Some("Pixels".to_string()))
+        .with_tags([("team".to_string(), "design".to_string())].into());
+    let payload = payload_with(&content, Some("image/png"), Duration::hours(1)).with_metadata(metadata);
+
+    repository.save(&payload).await.unwrap();
+
+    let retrieved = repository
+        .get(payload.hash_id())
+        .await
+        .unwrap()
+        .expect("saved payload should be returned");
+    assert_eq!(retrieved.hash_id(), payload.hash_id());
+    assert_eq!(retrieved.content(), content.as_slice());
+    assert_eq!(retrieved.mime_type(), payload.mime_type());
+    assert_eq!(retrieved.created_at(), payload.created_at());
+    assert_eq!(retrieved.updated_at(), payload.updated_at());
+    assert_eq!(retrieved.viewed_at(), payload.viewed_at());
+    assert_eq!(retrieved.expiry_time(), payload.expiry_time());
+    assert_eq!(retrieved.metadata(), payload.metadata());
+}
+
+/// Inserting reserves a hash ID: a second payload with the same ID is
+/// rejected and the first one is kept.
+pub async fn insert(repository: Arc<dyn Repository>) {
+    let payload = payload_with(b"first", None, Duration::hours(1));
+    repository.insert(&payload).await.unwrap();
+
+    let duplicate = payload_with(b"second", None, Duration::hours(1))
+        .with_hash_id(payload.hash_id().clone());
+    let result = repository.insert(&duplicate).await;
+    assert!(
+        matches!(result, Err(RepositoryError::Conflict(_))),
+        "unexpected result: {:?}",
+        result
+    );
+
+    let retrieved = repository.get(payload.hash_id()).await.unwrap().unwrap();
+    assert_eq!(retrieved.content(), b"first");
+}
+
+/// Getting an unknown hash ID returns `None` rather than an error.
+pub async fn get_missing(repository: Arc<dyn Repository>) {
+    let result = repository.get(&HashId::new()).await.unwrap();
+    assert!(result.is_none());
+}
+
+/// A deleted payload can no longer be read.
+pub async fn delete(repository: Arc<dyn Repository>) {
+    let payload = payload_with(b"to be deleted", None, Duration::hours(1));
+    repository.save(&payload).await.unwrap();
+
+    repository.delete(payload.hash_id(), None).await.unwrap();
+
+    assert!(repository.get(payload.hash_id()).await.unwrap().is_none());
+}
+
+/// Deleting an unknown hash ID reports that the payload was not found.
+pub async fn delete_missing(repository: Arc<dyn Repository>) {
+    let result = repository.delete(&HashId::new(), None).await;
+    assert!(
+        matches!(result, Err(RepositoryError::NotFound)),
+        "unexpected result: {:?}",
+        result
+    );
+}
+
+/// Viewing a payload records the view until its views are used up, and
+/// removes one-time payloads. Password-protected payloads need unlocking.
+pub async fn view(repository: Arc<dyn Repository>) {
+    let payload = payload_with(b"limited", None, Duration::hours(1)).with_max_views(Some(2));
+    repository.save(&payload).await.unwrap();
+
+    for count in 1..=2 {
+        let viewed = repository.view(payload.hash_id(), false).await.unwrap().unwrap();
+        assert_eq!(viewed.view_count(), count);
+        assert!(viewed.viewed_at().is_some());
+        assert_eq!(viewed.content(), b"limited");
+    }
+    let result = repository.view(payload.hash_id(), false).await;
+    assert!(
+        matches!(result, Err(RepositoryError::ViewLimitReached)),
+        "unexpected result: {:?}",
+        result
+    );
+    let retrieved = repository.get(payload.hash_id()).await.unwrap().unwrap();
+    assert_eq!(retrieved.view_count(), 2);
+    assert_eq!(retrieved.expiry_time(), payload.expiry_time());
+
+    let one_time = payload_with(b"read once", None, Duration::hours(1)).with_one_time(true);
+    repository.save(&one_time).await.unwrap();
+    let viewed = repository.view(one_time.hash_id(), false).await.unwrap().unwrap();
+    assert_eq!(viewed.content(), b"read once");
+    assert_eq!(viewed.view_count(), 1);
+    assert!(repository.get(one_time.hash_id()).await.unwrap().is_none());
+    assert!(repository.view(one_time.hash_id(), false).await.unwrap().is_none());
+
+    assert!(repository.view(&HashId::new(), false).await.unwrap().is_none());
+
+    // Password-protected payloads are only viewed once unlocked
+    let protected = payload_with(b"secret", None, Duration::hours(1))
+        .with_password("open sesame")
+        .unwrap();
+    repository.save(&protected).await.unwrap();
+    let result = repository.view(protected.hash_id(), false).await;
+    assert!(
+        matches!(result, Err(RepositoryError::PasswordRequired)),
+        "unexpected result: {:?}",
+        result
+    );
+    assert_eq!(repository.get(protected.hash_id()).await.unwrap().unwrap().view_count(), 0);
+    let viewed = repository.view(protected.hash_id(), true).await.unwrap().unwrap();
+    assert_eq!(viewed.view_count(), 1);
+}
+
+/// A reserved password attempt counts as a wrong password until it is
+/// released, and attempts are refused once they are throttled.
+pub async fn unlock_attempts(repository: Arc<dyn Repository>) {
+    let payload = payload_with(b"locked", None, Duration::hours(1))
+        .with_password("open sesame")
+        .unwrap();
+    repository.save(&payload).await.unwrap();
+
+    let at = Utc::now();
+    let before = repository
+        .try_unlock_attempt(payload.hash_id(), at)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(before.failed_unlocks(), 0);
+    let reserved = repository.get(payload.hash_id()).await.unwrap().unwrap();
+    assert_eq!(reserved.failed_unlocks(), 1);
+    assert!(reserved.failed_unlock_at().is_some());
+    assert!(reserved.password_matches(Some("open sesame")));
+
+    // A right password hands the attempt back
+    repository
+        .release_unlock_attempt(payload.hash_id(), at, before.failed_unlock_at())
+        .await
+        .unwrap();
+    let released = repository.get(payload.hash_id()).await.unwrap().unwrap();
+    assert_eq!(released.failed_unlocks(), 0);
+    assert!(released.failed_unlock_at().is_none());
+
+    // Wrong passwords are kept until attempts are throttled
+    let mut attempts = 0;
+    loop {
+        match repository.try_unlock_attempt(payload.hash_id(), Utc::now()).await {
+            Ok(Some(_)) => attempts += 1,
+            Err(RepositoryError::UnlockThrottled) => break,
+            other => panic!("Expected a reserved or throttled attempt, got {:?}", other),
+        }
+        assert!(attempts < 100, "attempts should be throttled");
+    }
+    let throttled = repository.get(payload.hash_id()).await.unwrap().unwrap();
+    assert_eq!(throttled.failed_unlocks(), attempts);
+    assert!(throttled.unlock_retry_after().is_some());
+
+    // Payloads without a password are never throttled
+    let open = payload_with(b"open", None, Duration::hours(1));
+    repository.save(&open).await.unwrap();
+    for _ in 0..=attempts {
+        repository.try_unlock_attempt(open.hash_id(), Utc::now()).await.unwrap().unwrap();
+    }
+    assert_eq!(repository.get(open.hash_id()).await.unwrap().unwrap().failed_unlocks(), 0);
+
+    assert!(repository.try_unlock_attempt(&HashId::new(), Utc::now()).await.unwrap().is_none());
+}
+
+/// Changing the expiry keeps the payload alive past its old expiry time,
+/// and an expiry in the past removes it at once.
+pub async fn update_expiry(repository: Arc<dyn Repository>) {
+    let payload = payload_with(b"extended", None, Duration::seconds(2));
+    repository.save(&payload).await.unwrap();
+    repository.view(payload.hash_id(), false).await.unwrap().unwrap();
+
+    let expiry_time = Utc::now() + Duration::hours(1);
+    let updated = repository
+        .update_expiry(payload.hash_id(), expiry_time, None)
+        .await
+        .unwrap()
+        .unwrap();
+    // Redis keeps expiry times to the millisecond
+    assert_eq!(updated.expiry_time().timestamp_millis(), expiry_time.timestamp_millis());
+    assert!(updated.updated_at() > payload.updated_at());
+    assert_eq!(updated.view_count(), 1);
+
+    tokio::time::sleep(std::time::Duration::from_millis(3000)).await;
+
+    let retrieved = repository.get(payload.hash_id()).await.unwrap().unwrap();
+    assert_eq!(retrieved.expiry_time(), updated.expiry_time());
+    assert_eq!(retrieved.updated_at(), updated.updated_at());
+    assert_eq!(retrieved.content(), b"extended");
+
+    repository
+        .update_expiry(payload.hash_id(), Utc::now() - Duration::seconds(1), None)
+        .await
+        .unwrap();
+    assert!(repository.get(payload.hash_id()).await.unwrap().is_none());
+
+    assert!(repository.update_expiry(&HashId::new(), expiry_time, None).await.unwrap().is_none());
+}
+
+/// Views push the expiry of idle payloads forward and start the countdown
+/// of payloads expiring after their first view.
+pub async fn expiry_modes(repository: Arc<dyn Repository>) {
+    let idle = payload_with(b"idle", None, Duration::hours(1))
+        .with_expiry_mode(ExpiryMode::Idle { timeout_secs: 3 });
+    let after_view = payload_with(b"after view", None, Duration::hours(1))
+        .with_expiry_mode(ExpiryMode::AfterFirstView { delay_secs: 2 });
+    repository.save(&idle).await.unwrap();
+    repository.save(&after_view).await.unwrap();
+
+    tokio::time::sleep(std::time::Duration::from_millis(1000)).await;
+    let viewed = repository.view(idle.hash_id(), false).await.unwrap().unwrap();
+    assert!(viewed.expiry_time() > idle.expiry_time());
+    assert_eq!(viewed.expiry_limit().timestamp_millis(), idle.expiry_limit().timestamp_millis());
+
+    // The idle payload outlives its first timeout, and the other one has
+    // not started counting down
+    tokio::time::sleep(std::time::Duration::from_millis(2500)).await;
+    assert!(repository.get(idle.hash_id()).await.unwrap().is_some());
+    let viewed = repository.view(after_view.hash_id(), false).await.unwrap().unwrap();
+    assert!(viewed.expiry_time() < after_view.expiry_time());
+
+    tokio::time::sleep(std::time::Duration::from_millis(2500)).await;
+    assert!(repository.get(idle.hash_id()).await.unwrap().is_none());
+    assert!(repository.get(after_view.hash_id()).await.unwrap().is_none());
+}
+
+/// Replacing content keeps the previous content as a revision, which goes
+/// away together with the payload.
+pub async fn replace_content(repository: Arc<dyn Repository>) {
+    let payload = payload_with(b"first", Some("text/plain"), Duration::hours(1));
+    repository.save(&payload).await.unwrap();
+    repository.view(payload.hash_id(), false).await.unwrap().unwrap();
+
+    let mut replacement = payload.clone();
+    let previous = replacement
+        .replace_content(b"{\"second\": true}".to_vec(), Some("application/json".to_string()))
+        .unwrap();
+    let replaced = repository
+        .replace_content(&replacement, &previous, payload.version(), DEFAULT_MAX_REVISIONS)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(replaced.revision(), 2);
+    assert_eq!(replaced.version(), 2);
+    assert_eq!(replaced.content(), b"{\"second\": true}");
+    assert_eq!(replaced.mime_type().to_string(), "application/json");
+    assert_eq!(replaced.view_count(), 1);
+
+    let retrieved = repository.get(payload.hash_id()).await.unwrap().unwrap();
+    assert_eq!(retrieved.revision(), 2);
+    assert_eq!(retrieved.version(), 2);
+    assert_eq!(retrieved.content(), replaced.content());
+
+    let revision = repository.get_revision(payload.hash_id(), 1).await.unwrap().unwrap();
+    assert_eq!(revision.number(), 1);
+    assert_eq!(revision.content(), b"first");
+    assert_eq!(revision.mime_type().to_string(), "text/plain");
+    assert!(repository.get_revision(payload.hash_id(), 2).await.unwrap().is_none());
+
+    // A replacement based on the old revision lost the race
+    let mut stale = payload.clone();
+    let previous = stale.replace_content(b"stale".to_vec(), None).unwrap();
+    assert!(matches!(
+        repository.replace_content(&stale, &previous, payload.version(), DEFAULT_MAX_REVISIONS).await,
+        Err(RepositoryError::VersionMismatch(1))
+    ));
+
+    repository
+        .update_expiry(payload.hash_id(), Utc::now() - Duration::seconds(1), None)
+        .await
+        .unwrap();
+    assert!(repository.get_revision(payload.hash_id(), 1).await.unwrap().is_none());
+
+    assert!(repository
+        .replace_content(&replacement, &previous, 2, DEFAULT_MAX_REVISIONS)
+        .await
+        .unwrap()
+        .is_none());
+}
+
+/// Replacing content beyond the revision limit drops the oldest revisions.
+pub async fn revision_limit(repository: Arc<dyn Repository>) {
+    let mut payload = payload_with(b"content 1", None, Duration::hours(1));
+    repository.save(&payload).await.unwrap();
+
+    for number in 2..=5 {
+        let version = payload.version();
+        let previous = payload.replace_content(format!("content {}", number).into_bytes(), None).unwrap();
+        payload = repository
+            .replace_content(&payload, &previous, version, 2)
+            .await
+            .unwrap()
+            .unwrap();
+    }
+    assert_eq!(payload.revision(), 5);
+
+    for number in 1..=2 {
+        assert!(repository.get_revision(payload.hash_id(), number).await.unwrap().is_none());
+    }
+    for number in 3..=4 {
+        let revision = repository.get_revision(payload.hash_id(), number).await.unwrap().unwrap();
+        assert_eq!(revision.content(), format!("content {}", number).as_bytes());
+    }
+
+    // Lowering the limit drops every revision beyond it at once
+    let version = payload.version();
+    let previous = payload.replace_content(b"content 6".to_vec(), None).unwrap();
+    repository.replace_content(&payload, &previous, version, 0).await.unwrap().unwrap();
+    for number in 1..=5 {
+        assert!(repository.get_revision(payload.hash_id(), number).await.unwrap().is_none());
+    }
+}
+
+/// Changes by the owner move a payload to its next version, and changes
+/// expecting another version leave it alone.
+pub async fn versions(repository: Arc<dyn Repository>) {
+    let payload = payload_with(b"versioned", None, Duration::hours(1));
+    repository.save(&payload).await.unwrap();
+    let viewed = repository.view(payload.hash_id(), false).await.unwrap().unwrap();
+    assert_eq!(viewed.version(), 1);
+
+    let expiry_time = Utc::now() + Duration::hours(2);
+    assert!(matches!(
+        repository.update_expiry(payload.hash_id(), expiry_time, Some(2)).await,
+        Err(RepositoryError::VersionMismatch(2))
+    ));
+    let updated = repository
+        .update_expiry(payload.hash_id(), expiry_time, Some(1))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(updated.version(), 2);
+    let updated = repository
+        .update_expiry(payload.hash_id(), expiry_time, None)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(updated.version(), 3);
+    assert_eq!(repository.get(payload.hash_id()).await.unwrap().unwrap().version(), 3);
+
+    assert!(matches!(
+        repository.delete(payload.hash_id(), Some(2)).await,
+        Err(RepositoryError::VersionMismatch(2))
+    ));
+    assert!(repository.get(payload.hash_id()).await.unwrap().is_some());
+    repository.delete(payload.hash_id(), Some(3)).await.unwrap();
+    assert!(matches!(
+        repository.delete(payload.hash_id(), Some(3)).await,
+        Err(RepositoryError::NotFound)
+    ));
+}
+
+/// Saving a payload with an existing hash ID replaces the stored payload.
+pub async fn overwrite(repository: Arc<dyn Repository>) {
+    let mut payload = payload_with(b"overwritten", None, Duration::hours(1));
+    repository.save(&payload).await.unwrap();
+
+    payload.mark_viewed();
+    repository.save(&payload).await.unwrap();
+
+    let retrieved = repository.get(payload.hash_id()).await.unwrap().unwrap();
+    assert_eq!(retrieved.viewed_at(), payload.viewed_at());
+    assert_eq!(retrieved.content(), b"overwritten");
+}
+
+/// Payloads are not returned once their expiry time has passed.
+pub async fn ttl_expiry(repository: Arc<dyn Repository>) {
+    // Redis expiry has one second granularity, so leave some headroom
+    let payload = payload_with(b"short-lived", None, Duration::seconds(2));
+    repository.save(&payload).await.unwrap();
+    assert!(repository.get(payload.hash_id()).await.unwrap().is_some());
+
+    tokio::time::sleep(std::time::Duration::from_millis(3000)).await;
+
+    assert!(repository.get(payload.hash_id()).await.unwrap().is_none());
+}
+
+/// Concurrent saves and reads of distinct payloads do not interfere.
+pub async fn concurrent_access(repository: Arc<dyn Repository>) {
+    let tasks: Vec<_> = (0..CONCURRENT_TASKS)
+        .map(|i| {
+            let repository = repository.clone();
+            tokio::spawn(async move {
+                let content = format!("concurrent payload {}", i);
+                let payload = payload_with(content.as_bytes(), None, Duration::hours(1));
+                repository.save(&payload).await.unwrap();
+
+                let retrieved = repository.get(payload.hash_id()).await.unwrap().unwrap();
+                assert_eq!(retrieved.content(), content.as_bytes());
+            })
+        })
+        .collect();
+
+    for task in tasks {
+        task.await.unwrap();
+    }
+}
+
+/// When many callers delete the same payload at once, exactly one succeeds.
+pub async fn concurrent_delete(repository: Arc<dyn Repository>) {
+    let payload = payload_with(b"contended", None, Duration::hours(1));
+    repository.save(&payload).await.unwrap();
+
+    let tasks: Vec<_> = (0..CONCURRENT_TASKS)
+        .map(|_| {
+            let repository = repository.clone();
+            let hash_id = payload.hash_id().clone();
+            tokio::spawn(async move { repository.delete(&hash_id, None).await.is_ok() })
+        })
+        .collect();
+
+    let mut deleted = 0;
+    for task in tasks {
+        if task.await.unwrap() {
+            deleted += 1;
+        }
+    }
+    assert_eq!(deleted, 1, "exactly one concurrent delete should succeed");
+}
+
+/// When many callers view the same one-time payload at once, exactly one
+/// receives it.
+pub async fn concurrent_one_time_view(repository: Arc<dyn Repository>) {
+    let payload = payload_with(b"contended secret", None, Duration::hours(1)).with_one_time(true);
+    repository.save(&payload).await.unwrap();
+
+    let tasks: Vec<_> = (0..CONCURRENT_TASKS)
+        .map(|_| {
+            let repository = repository.clone();
+            let hash_id = payload.hash_id().clone();
+            tokio::spawn(async move { repository.view(&hash_id, false).await.unwrap().is_some() })
+        })
+        .collect();
+
+    let mut viewed = 0;
+    for task in tasks {
+        if task.await.unwrap() {
+            viewed += 1;
+        }
+    }
+    assert_eq!(viewed, 1, "exactly one concurrent view should receive the payload");
+}
+
+/// Concurrent views of a payload never record more views than it allows.
+pub async fn concurrent_view(repository: Arc<dyn Repository>) {
+    let max_views = CONCURRENT_TASKS as u64 / 2;
+    let payload = payload_with(b"limited", None, Duration::hours(1)).with_max_views(Some(max_views));
+    repository.save(&payload).await.unwrap();
+
+    let tasks: Vec<_> = (0..CONCURRENT_TASKS)
+        .map(|_| {
+            let repository = repository.clone();
+            let hash_id = payload.hash_id().clone();
+            tokio::spawn(async move { repository.view(&hash_id, false).await })
+        })
+        .collect();
+
+    let mut recorded = 0;
+    for task in tasks {
+        match task.await.unwrap() {
+            Ok(Some(_)) => recorded += 1,
+            Err(RepositoryError::ViewLimitReached) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+    }
+    assert_eq!(recorded, max_views, "only the allowed views should be recorded");
+
+    let retrieved = repository.get(payload.hash_id()).await.unwrap().unwrap();
+    assert_eq!(retrieved.view_count(), max_views);
+}
+
+/// When many callers insert payloads with the same hash ID at once, exactly
+/// one succeeds.
+pub async fn concurrent_insert(repository: Arc<dyn Repository>) {
+    let hash_id = HashId::new();
+
+    let tasks: Vec<_> = (0..CONCURRENT_TASKS)
+        .map(|i| {
+            let repository = repository.clone();
+            let payload = payload_with(format!("contender {}", i).as_bytes(), None, Duration::hours(1))
+                .with_hash_id(hash_id.clone());
+            tokio::spawn(async move { repository.insert(&payload).await.is_ok() })
+        })
+        .collect();
+
+    let mut inserted = 0;
+    for task in tasks {
+        if task.await.unwrap() {
+            inserted += 1;
+        }
+    }
+    assert_eq!(inserted, 1, "exactly one concurrent insert should succeed");
+}
+
+/// An alias resolves to its hash ID, and cannot be reserved twice.
+pub async fn alias(repository: Arc<dyn Repository>) {
+    let alias = unique_alias("crossover-clockwork");
+    let hash_id = HashId::new();
+    let expiry_time = Utc::now() + Duration::hours(1);
+    repository.insert_alias(&alias, &hash_id, expiry_time).await.unwrap();
+
+    let resolved = repository.resolve_alias(&alias).await.unwrap();
+    assert_eq!(resolved, Some(hash_id));
+
+    let result = repository
+        .insert_alias(&alias, &HashId::new(), expiry_time)
+        .await;
+    assert!(
+        matches!(result, Err(RepositoryError::Conflict(_))),
+        "unexpected result: {:?}",
+        result
+    );
+
+    let missing = unique_alias("crossover-clockwork");
+    assert!(repository.resolve_alias(&missing).await.unwrap().is_none());
+}
+
+/// Aliases no longer resolve once expired, and can then be reserved again.
+pub async fn alias_expiry(repository: Arc<dyn Repository>) {
+    let alias = unique_alias("anchor-bagel");
+    repository
+        .insert_alias(&alias, &HashId::new(), Utc::now() + Duration::seconds(2))
+        .await
+        .unwrap();
+    assert!(repository.resolve_alias(&alias).await.unwrap().is_some());
+
+    tokio::time::sleep(std::time::Duration::from_millis(3000)).await;
+
+    assert!(repository.resolve_alias(&alias).await.unwrap().is_none());
+    let hash_id = HashId::new();
+    repository
+        .insert_alias(&alias, &hash_id, Utc::now() + Duration::hours(1))
+        .await
+        .unwrap();
+    assert_eq!(repository.resolve_alias(&alias).await.unwrap(), Some(hash_id));
+}
+
+/// An alias's expiry can be changed only for the payload it resolves to.
+pub async fn update_alias_expiry(repository: Arc<dyn Repository>) {
+    let alias = unique_alias("anchor-comet");
+    let hash_id = HashId::new();
+    repository
+        .insert_alias(&alias, &hash_id, Utc::now() + Duration::seconds(2))
+        .await
+        .unwrap();
+
+    // Another payload cannot touch the alias
+    repository
+        .update_alias_expiry(&alias, &HashId::new(), Utc::now() - Duration::seconds(1))
+        .await
+        .unwrap();
+    repository
+        .update_alias_expiry(&alias, &hash_id, Utc::now() + Duration::hours(1))
+        .await
+        .unwrap();
+
+    tokio::time::sleep(std::time::Duration::from_millis(3000)).await;
+
+    assert_eq!(repository.resolve_alias(&alias).await.unwrap(), Some(hash_id.clone()));
+
+    repository
+        .update_alias_expiry(&alias, &hash_id, Utc::now() - Duration::seconds(1))
+        .await
+        .unwrap();
+    assert!(repository.resolve_alias(&alias).await.unwrap().is_none());
+
+    // Missing aliases are left alone
+    let missing = unique_alias("anchor-comet");
+    repository
+        .update_alias_expiry(&missing, &hash_id, Utc::now() + Duration::hours(1))
+        .await
+        .unwrap();
+    assert!(repository.resolve_alias(&missing).await.unwrap().is_none());
+}
+
+/// When many callers reserve the same alias at once, exactly one succeeds.
+pub async fn concurrent_insert_alias(repository: Arc<dyn Repository>) {
+    let alias = unique_alias("comet-canoe");
+    let expiry_time = Utc::now() + Duration::hours(1);
+
+    let tasks: Vec<_> = (0..CONCURRENT_TASKS)
+        .map(|_| {
+            let repository = repository.clone();
+            let alias = alias.clone();
+            tokio::spawn(async move {
+                repository
+                    .insert_alias(&alias, &HashId::new(), expiry_time)
+                    .await
+                    .is_ok()
+            })
+        })
+        .collect();
+
+    let mut reserved = 0;
+    for task in tasks {
+        if task.await.unwrap() {
+            reserved += 1;
+        }
+    }
+    assert_eq!(reserved, 1, "exactly one concurrent alias reservation should succeed");
+}
+
+/// Concurrent password attempts are throttled as they are made, so no more
+/// get through than sequential ones would.
+pub async fn concurrent_unlock_attempts(repository: Arc<dyn Repository>) {
+    let payload = payload_with(b"locked", None, Duration::hours(1))
+        .with_password("open sesame")
+        .unwrap();
+    repository.save(&payload).await.unwrap();
+
+    // Find how many sequential attempts get through on a twin payload
+    let twin = payload_with(b"locked", None, Duration::hours(1))
+        .with_password("open sesame")
+        .unwrap();
+    repository.save(&twin).await.unwrap();
+    let mut allowed = 0;
+    while repository.try_unlock_attempt(twin.hash_id(), Utc::now()).await.is_ok() {
+        allowed += 1;
+    }
+    assert!(allowed < CONCURRENT_TASKS as u64);
+
+    let tasks: Vec<_> = (0..CONCURRENT_TASKS)
+        .map(|_| {
+            let repository = Arc::clone(&repository);
+            let hash_id = payload.hash_id().clone();
+            tokio::spawn(async move {
+                match repository.try_unlock_attempt(&hash_id, Utc::now()).await {
+                    Ok(Some(_)) => true,
+                    Err(RepositoryError::UnlockThrottled) => false,
+                    other => panic!("Expected a reserved or throttled attempt, got {:?}", other),
+                }
+            })
+        })
+        .collect();
+
+    let mut reserved = 0;
+    for task in tasks {
+        if task.await.unwrap() {
+            reserved += 1;
+        }
+    }
+    assert!(reserved >= 1 && reserved <= allowed, "{} attempts got through", reserved);
+
+    let stored = repository.get(payload.hash_id()).await.unwrap().unwrap();
+    assert_eq!(stored.failed_unlocks(), reserved);
+}
+
+/// Store a payload holding a fresh alias starting with `prefix` and a fresh
+/// share code, reserving both.
+async fn save_named(repository: &Arc<dyn Repository>, prefix: &str, one_time: bool) -> Payload {
+    let payload = payload_with(b"named", None, Duration::hours(1))
+        .with_alias(Alias::parse(&unique_alias(prefix)).unwrap())
+        .with_share_code(ShareCode::generate())
+        .with_one_time(one_time);
+    repository.save(&payload).await.unwrap();
+    for name in payload.names() {
+        repository.insert_alias(name, payload.hash_id(), payload.expiry_time()).await.unwrap();
+    }
+    payload
+}
+
+/// Deleting, viewing a one-time payload or moving its expiry into the
+/// past releases its alias and share code, but not names that have since
+/// been reserved for another payload.
+pub async fn names_released(repository: Arc<dyn Repository>) {
+    let deleted = save_named(&repository, "released-by-delete", false).await;
+    repository.delete(deleted.hash_id(), None).await.unwrap();
+
+    let viewed = save_named(&repository, "released-by-view", true).await;
+    repository.view(viewed.hash_id(), false).await.unwrap().unwrap();
+
+    let revoked = save_named(&repository, "released-by-revoke", false).await;
+    repository
+        .update_expiry(revoked.hash_id(), Utc::now() - Duration::seconds(1), None)
+        .await
+        .unwrap();
+
+    for payload in [&deleted, &viewed, &revoked] {
+        for name in payload.names() {
+            assert!(repository.resolve_alias(name).await.unwrap().is_none(), "{} was not released", name);
+        }
+    }
+
+    // The alias key was reserved for another payload after the first expired
+    let alias = unique_alias("kept-by-other");
+    let stale = payload_with(b"stale", None, Duration::hours(1))
+        .with_alias(Alias::parse(&alias).unwrap());
+    repository.save(&stale).await.unwrap();
+    let owner = HashId::new();
+    repository
+        .insert_alias(&alias, &owner, Utc::now() + Duration::hours(1))
+        .await
+        .unwrap();
+    repository.delete(stale.hash_id(), None).await.unwrap();
+    assert_eq!(repository.resolve_alias(&alias).await.unwrap(), Some(owner));
+}
+
+/// Instantiate the repository contract suite for a backend.
+///
+/// See the [module documentation](self) for usage.
+macro_rules! repository_contract_tests {
+    ($setup:expr $(, #[$attr:meta])* $(,)?) => {
+        $crate::application::tests::repository_contract::repository_contract_tests!(
+            @tests [$(#[$attr])*] $setup;
+            save_and_get,
+            insert,
+            get_missing,
+            delete,
+            delete_missing,
+            view,
+            unlock_attempts,
+            update_expiry,
+            expiry_modes,
+            replace_content,
+            revision_limit,
+            versions,
+            overwrite,
+            ttl_expiry,
+            concurrent_access,
+            concurrent_delete,
+            concurrent_one_time_view,
+            concurrent_view,
+            concurrent_insert,
+            alias,
+            alias_expiry,
+            update_alias_expiry,
+            concurrent_insert_alias,
+            concurrent_unlock_attempts,
+            names_released,
+        );
+    };
+    (@tests $attrs:tt $setup:expr; $($check:ident,)*) => {
+        $(
+            $crate::application::tests::repository_contract::repository_contract_tests!(
+                @test $attrs $setup; $check
+            );
+        )*
+    };
+    (@test [$(#[$attr:meta])*] $setup:expr; $check:ident) => {
+        #[tokio::test(flavor = "multi_thread")]
+        $(#[$attr])*
+        async fn $check() {
+            let (_guard, repository) = $setup;
+            $crate::application::tests::repository_contract::$check(repository).await;
+        }
+    };
+}
+
+pub(crate) use repository_contract_tests;
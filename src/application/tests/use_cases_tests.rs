@@ -3,6 +3,7 @@
 use std::sync::Arc;
 use chrono::{Duration, Utc};
 
+use crate::domain::hash_id::HashId;
 use crate::application::{
     dtos::CreatePayloadRequest,
     use_cases::{
@@ -11,7 +12,6 @@ use crate::application::{
         UseCaseError,
     },
 };
-use crate::domain::hash_id::HashId;
 use super::{MockRepository, create_test_payload};
 
 #[tokio::test]
@@ -23,7 +23,7 @@ async fn test_create_payload_with_valid_data() {
     let request = CreatePayloadRequest {
         content: "Test content".to_string(),
         mime_type: Some("text/plain".to_string()),
-        expiry_time: None,
+        ..Default::default()
     };
     
     // Act
@@ -48,6 +48,7 @@ async fn test_create_payload_with_custom_expiry() {
         content: "Test content".to_string(),
         mime_type: Some("text/plain".to_string()),
         expiry_time: Some(expiry_time),
+        ..Default::default()
     };
     
     // Act
@@ -72,19 +73,19 @@ async fn test_create_payload_with_empty_content() {
     let request = CreatePayloadRequest {
         content: "".to_string(),
         mime_type: Some("text/plain".to_string()),
-        expiry_time: None,
+        ..Default::default()
     };
     
     // Act
     let result = use_case.execute(request).await;
     
-    // Assert
+    // Assert - request validation rejects it before the domain is involved
     assert!(result.is_err(), "Expected error for empty content");
     match result {
-        Err(UseCaseError::DomainError(_)) => {
+        Err(UseCaseError::ValidationError(_)) => {
             // This is the expected error type
         }
-        _ => panic!("Expected DomainError for empty content"),
+        _ => panic!("Expected ValidationError for empty content"),
     }
     assert_eq!(repository.count(), 0, "Expected no payloads in repository");
 }
@@ -98,7 +99,7 @@ async fn test_create_payload_with_invalid_mime_type() {
     let request = CreatePayloadRequest {
         content: "Test content".to_string(),
         mime_type: Some("invalid/type".to_string()),
-        expiry_time: None,
+        ..Default::default()
     };
     
     // Act
@@ -123,11 +124,11 @@ async fn test_get_payload_with_valid_id() {
     
     // Create a test payload and add it to the repository
     let payload = create_test_payload("Test content", None);
-    let hash_id = payload.hash_id().as_string().to_string();
+    let hash_id = payload.hash_id().clone();
     repository.add_payload(payload);
     
     // Act
-    let result = use_case.execute(hash_id).await;
+    let result = use_case.execute(hash_id, None, None).await;
     
     // Assert
     assert!(result.is_ok(), "Expected successful payload retrieval");
@@ -143,7 +144,7 @@ async fn test_get_payload_with_nonexistent_id() {
     let use_case = GetPayloadUseCaseImpl::new(repository.clone());
     
     // Act
-    let result = use_case.execute("nonexistent-id".to_string()).await;
+    let result = use_case.execute(HashId::parse("nonexistent-id").unwrap(), None, None).await;
     
     // Assert
     assert!(result.is_err(), "Expected error for nonexistent ID");
@@ -164,18 +165,18 @@ async fn test_get_expired_payload() {
     // Create an expired payload and add it to the repository
     let expired_time = Utc::now() - Duration::hours(1);
     let payload = create_test_payload("Expired content", Some(expired_time));
-    let hash_id = payload.hash_id().as_string().to_string();
+    let hash_id = payload.hash_id().clone();
     repository.add_payload(payload);
     
     // Act
-    let result = use_case.execute(hash_id).await;
+    let result = use_case.execute(hash_id, None, None).await;
     
-    // Assert
+    // Assert - like every backend, the repository no longer returns it
     assert!(result.is_err(), "Expected error for expired payload");
     match result {
-        Err(UseCaseError::Expired) => {
+        Err(UseCaseError::NotFound) => {
             // This is the expected error type
         }
-        _ => panic!("Expected Expired error for expired payload"),
+        _ => panic!("Expected NotFound error for expired payload"),
     }
 }
@@ -4,22 +4,35 @@
 //! - Creating new payloads
 //! - Retrieving existing payloads
 //! - Handling payload expiry
+//! - Replacing payload content and reading earlier revisions
 //! 
 //! Use cases are the primary way that the API layer interacts with the domain model.
 //! They encapsulate all business rules and coordinate between different parts of the system.
 
 use async_trait::async_trait;
+use chrono::Utc;
 use std::sync::Arc;
 use thiserror::Error;
 use validator::Validate;
 
 use crate::domain::{
+    alias::Alias,
+    expiry::{parse_duration, ExpiryError, ExpiryMode, ExpiryPolicy},
     payload::{Payload, PayloadError},
-    hash_id::HashId,
+    hash_id::{AlphabetGenerator, HashId, HashIdError, HashIdGenerator},
+    management_token::ManagementToken,
+    metadata::Metadata,
+    mime_type::{MimeRegistry, MimeType},
+    revision::{Revision, DEFAULT_MAX_REVISIONS},
+    share_code::{ShareCode, ShareCodeError},
 };
 use super::{
-    dtos::{CreatePayloadRequest, CreatePayloadResponse, GetPayloadResponse},
-    repository::Repository,
+    dtos::{
+        ContentEncoding, CreatePayloadRequest, CreatePayloadResponse, GetPayloadResponse,
+        ReplacePayloadRequest, ReplacePayloadResponse, UpdatePayloadRequest, UpdatePayloadResponse,
+        VersionMatch,
+    },
+    repository::{Repository, RepositoryError},
 };
 
 /// Errors that can occur in use cases.
@@ -29,23 +42,86 @@ pub enum UseCaseError {
     #[error("Validation error: {0}")]
     ValidationError(String),
 
+    /// The hash ID given by the client is malformed
+    #[error("Invalid hash ID: {0}")]
+    InvalidHashId(#[from] HashIdError),
+
+    /// The share code given by the client is malformed
+    #[error("Invalid share code: {0}")]
+    InvalidShareCode(#[from] ShareCodeError),
+
+    /// The requested expiry is malformed or outside the expiry policy
+    #[error("Invalid expiry: {0}")]
+    InvalidExpiry(#[from] ExpiryError),
+
     /// Payload was not found
     #[error("Payload not found")]
     NotFound,
 
-    /// Payload has expired
-    #[error("Payload has expired")]
-    Expired,
+    /// The requested alias is already reserved for another payload
+    #[error("Alias {0} is already taken")]
+    AliasTaken(String),
+
+    /// Payload has been viewed as many times as allowed
+    #[error("Payload view limit reached")]
+    ViewLimitReached,
+
+    /// The password or management token is missing or wrong, or password
+    /// attempts are being throttled
+    #[error("Unauthorized")]
+    Unauthorized,
+
+    /// The client already has the current version of the payload
+    #[error("Payload not modified since version {0}")]
+    NotModified(u64),
+
+    /// The payload is not at the version the client expected
+    #[error("Payload version does not match")]
+    PreconditionFailed,
 
     /// Repository error
     #[error("Repository error: {0}")]
-    RepositoryError(#[from] anyhow::Error),
+    RepositoryError(#[from] RepositoryError),
 
     /// Domain error
     #[error("Domain error: {0}")]
     DomainError(#[from] PayloadError),
+
+    /// The request failed on the server, through no fault of the client
+    #[error("Internal error: {0}")]
+    Internal(String),
 }
 
+/// Protect a payload with a password. Argon2 is deliberately slow, so the
+/// password is hashed on the blocking thread pool. Failing to hash it is a
+/// server error, not a problem with the request.
+async fn protect_with_password(payload: Payload, password: String) -> Result<Payload, UseCaseError> {
+    tokio::task::spawn_blocking(move || payload.with_password(&password))
+        .await
+        .map_err(|e| UseCaseError::Internal(e.to_string()))?
+        .map_err(|e| match e {
+            PayloadError::PasswordHash(msg) => UseCaseError::Internal(msg),
+            e => UseCaseError::DomainError(e),
+        })
+}
+
+/// Check a password against a payload on the blocking thread pool, handing
+/// the payload back with the outcome
+async fn check_password(payload: Payload, password: Option<String>) -> Result<(Payload, bool), UseCaseError> {
+    tokio::task::spawn_blocking(move || {
+        let matches = payload.password_matches(password.as_deref());
+        (payload, matches)
+    })
+    .await
+    .map_err(|e| UseCaseError::Internal(e.to_string()))
+}
+
+/// Attempts at finding an unused hash ID before creating a payload fails
+const MAX_ID_ATTEMPTS: usize = 5;
+
+/// Attempts at finding an unused share code before creating a payload fails
+const MAX_SHARE_CODE_ATTEMPTS: usize = 5;
+
 /// Use case for creating a new payload.
 #[async_trait]
 pub trait CreatePayloadUseCase: Send + Sync {
@@ -60,25 +136,206 @@ pub trait CreatePayloadUseCase: Send + Sync {
 #[async_trait]
 pub trait GetPayloadUseCase: Send + Sync {
     /// Execute the use case.
-    async fn execute(&self, hash_id: String) -> Result<GetPayloadResponse, UseCaseError>;
+    ///
+    /// `password` is required for password-protected payloads and ignored
+    /// otherwise. If the payload is at a version named by `if_none_match`,
+    /// returns `UseCaseError::NotModified` without counting a view.
+    async fn execute(
+        &self,
+        hash_id: HashId,
+        password: Option<String>,
+        if_none_match: Option<VersionMatch>,
+    ) -> Result<GetPayloadResponse, UseCaseError>;
+}
+
+/// Use case for retrieving an earlier revision of a payload's content.
+#[async_trait]
+pub trait GetRevisionUseCase: Send + Sync {
+    /// Execute the use case.
+    ///
+    /// Reading a revision is subject to the same checks as reading the
+    /// payload, and counts as a view of it.
+    async fn execute_revision(
+        &self,
+        hash_id: HashId,
+        revision: u64,
+        password: Option<String>,
+        if_none_match: Option<VersionMatch>,
+    ) -> Result<GetPayloadResponse, UseCaseError>;
 }
 
 /// Use case for deleting an existing payload.
 #[async_trait]
 pub trait DeletePayloadUseCase: Send + Sync {
     /// Execute the use case.
-    async fn delete(&self, id: &str) -> Result<(), UseCaseError>;
+    ///
+    /// Requires the management token returned when the payload was created,
+    /// and the payload to be at a version named by `if_match`, if given.
+    async fn delete(
+        &self,
+        hash_id: &HashId,
+        token: Option<&str>,
+        if_match: Option<VersionMatch>,
+    ) -> Result<(), UseCaseError>;
+}
+
+/// Use case for changing an existing payload.
+#[async_trait]
+pub trait UpdatePayloadUseCase: Send + Sync {
+    /// Execute the use case.
+    ///
+    /// Requires the management token returned when the payload was created,
+    /// and the payload to be at a version named by `if_match`, if given.
+    async fn update(
+        &self,
+        hash_id: &HashId,
+        token: Option<&str>,
+        if_match: Option<VersionMatch>,
+        request: UpdatePayloadRequest,
+    ) -> Result<UpdatePayloadResponse, UseCaseError>;
+}
+
+/// Use case for replacing the content of an existing payload.
+#[async_trait]
+pub trait ReplacePayloadUseCase: Send + Sync {
+    /// Execute the use case.
+    ///
+    /// Requires the management token returned when the payload was created,
+    /// and the payload to be at a version named by `if_match`, if given.
+    async fn replace(
+        &self,
+        hash_id: &HashId,
+        token: Option<&str>,
+        if_match: Option<VersionMatch>,
+        request: ReplacePayloadRequest,
+    ) -> Result<ReplacePayloadResponse, UseCaseError>;
+}
+
+/// Use case for resolving a share code to the payload it stands for.
+#[async_trait]
+pub trait ResolveShareCodeUseCase: Send + Sync {
+    /// Execute the use case, returning the hash ID of the payload.
+    async fn resolve(&self, code: &ShareCode) -> Result<HashId, UseCaseError>;
+}
+
+/// Use case for resolving an alias to the payload it names.
+#[async_trait]
+pub trait ResolveAliasUseCase: Send + Sync {
+    /// Execute the use case, returning the hash ID of the payload.
+    async fn resolve(&self, alias: &Alias) -> Result<HashId, UseCaseError>;
 }
 
 /// Implementation of the create payload use case.
 pub struct CreatePayloadUseCaseImpl {
     repository: Arc<dyn Repository>,
+    id_generator: Arc<dyn HashIdGenerator>,
+    expiry_policy: ExpiryPolicy,
+    mime_registry: Arc<MimeRegistry>,
 }
 
 impl CreatePayloadUseCaseImpl {
     /// Create a new instance of the use case implementation.
+    ///
+    /// Hash IDs are generated by the default [`AlphabetGenerator`],
+    /// expiries follow the default [`ExpiryPolicy`], and the MIME types of
+    /// the default [`MimeRegistry`] are accepted.
     pub fn new(repository: Arc<dyn Repository>) -> Self {
-        Self { repository }
+        Self {
+            repository,
+            id_generator: Arc::new(AlphabetGenerator::default()),
+            expiry_policy: ExpiryPolicy::default(),
+            mime_registry: Arc::new(MimeRegistry::default()),
+        }
+    }
+
+    /// Use a different generator for the hash IDs of new payloads.
+    pub fn with_id_generator(mut self, id_generator: Arc<dyn HashIdGenerator>) -> Self {
+        self.id_generator = id_generator;
+        self
+    }
+
+    /// Use a different policy for the expiry of new payloads.
+    pub fn with_expiry_policy(mut self, expiry_policy: ExpiryPolicy) -> Self {
+        self.expiry_policy = expiry_policy;
+        self
+    }
+
+    /// Accept the MIME types of a different registry for new payloads.
+    pub fn with_mime_registry(mut self, mime_registry: Arc<MimeRegistry>) -> Self {
+        self.mime_registry = mime_registry;
+        self
+    }
+
+    /// Reserve the requested alias and, if asked for, a share code for a
+    /// stored payload, returning the share code. The alias is released again
+    /// if no share code can be reserved.
+    ///
+    /// Names are reserved until the payload's expiry limit, as views may
+    /// keep it alive that long.
+    async fn reserve_names(
+        &self,
+        payload: &Payload,
+        alias: Option<&Alias>,
+        share_code: bool,
+    ) -> Result<Option<ShareCode>, UseCaseError> {
+        if let Some(alias) = alias {
+            self.repository
+                .insert_alias(alias.as_str(), payload.hash_id(), payload.expiry_limit())
+                .await
+                .map_err(|e| match e {
+                    RepositoryError::Conflict(_) => UseCaseError::AliasTaken(alias.to_string()),
+                    e => UseCaseError::RepositoryError(e),
+                })?;
+        }
+
+        if !share_code {
+            return Ok(None);
+        }
+        match self.reserve_share_code(payload).await {
+            Ok(share_code) => Ok(Some(share_code)),
+            Err(e) => {
+                if let Some(alias) = alias {
+                    self.release_name(alias.as_str(), payload).await;
+                }
+                Err(e.into())
+            }
+        }
+    }
+
+    /// Release a name reserved for a payload; an expiry time that has
+    /// already passed releases it at once. Failures are ignored, as the name
+    /// is released when the payload would have expired anyway.
+    async fn release_name(&self, name: &str, payload: &Payload) {
+        let _ = self
+            .repository
+            .update_alias_expiry(name, payload.hash_id(), Utc::now())
+            .await;
+    }
+
+    /// Give up on a stored payload whose names could not all be reserved,
+    /// releasing the names it holds and deleting it.
+    async fn abandon(&self, payload: &Payload) {
+        for name in payload.names() {
+            self.release_name(name, payload).await;
+        }
+        let _ = self.repository.delete(payload.hash_id(), None).await;
+    }
+
+    /// Reserve a fresh share code for a stored payload, expiring with it
+    async fn reserve_share_code(&self, payload: &Payload) -> Result<ShareCode, RepositoryError> {
+        let mut attempt = 1;
+        loop {
+            let share_code = ShareCode::generate();
+            match self
+                .repository
+                .insert_alias(share_code.as_str(), payload.hash_id(), payload.expiry_limit())
+                .await
+            {
+                Ok(()) => return Ok(share_code),
+                Err(RepositoryError::Conflict(_)) if attempt < MAX_SHARE_CODE_ATTEMPTS => attempt += 1,
+                Err(e) => return Err(e),
+            }
+        }
     }
 }
 
@@ -91,28 +348,120 @@ impl CreatePayloadUseCase for CreatePayloadUseCaseImpl {
         // Validate request
         request.validate().map_err(|e| UseCaseError::ValidationError(e.to_string()))?;
 
-        // Create payload
-        let payload = Payload::new(
-            request.content,
-            request.mime_type,
-            request.expiry_time,
-        ).map_err(UseCaseError::DomainError)?;
+        let alias = request
+            .alias
+            .as_deref()
+            .map(Alias::parse)
+            .transpose()
+            .map_err(|e| UseCaseError::ValidationError(e.to_string()))?;
+        let mut metadata = Metadata::default()
+            .with_title(request.title)
+            .with_description(request.description)
+            .with_tags(request.tags.unwrap_or_default());
+        if let Some(filename) = &request.filename {
+            metadata = metadata
+                .with_filename(filename)
+                .map_err(|e| UseCaseError::ValidationError(e.to_string()))?;
+        }
 
-        // Save payload
-        self.repository
-            .save(&payload)
-            .await
-            .map_err(UseCaseError::RepositoryError)?;
+        // Work out the expiry, rejecting any the policy does not allow
+        let expires_in = request.expires_in.as_deref().map(parse_duration).transpose()?;
+        let expiry_time = self
+            .expiry_policy
+            .resolve(request.expiry_time, expires_in, Utc::now())?;
+        let idle_timeout = request.idle_timeout.as_deref().map(parse_duration).transpose()?;
+        let expires_after_view = request.expires_after_view.as_deref().map(parse_duration).transpose()?;
+        let expiry_mode = self
+            .expiry_policy
+            .check_mode(ExpiryMode::from_timeouts(idle_timeout, expires_after_view)?)?;
+
+        // Decode the transported content into raw bytes
+        let content = request
+            .encoding
+            .unwrap_or_default()
+            .decode(&request.content)
+            .map_err(|e| UseCaseError::ValidationError(format!("Invalid base64 content: {}", e)))?;
+
+        // Work out the MIME type, checking it against the content
+        let declared = match request.mime_type.as_deref() {
+            Some(mime_type) => MimeType::parse_requested(mime_type).map_err(PayloadError::from)?,
+            None => self.mime_registry.default_type().cloned(),
+        };
+        let mime_type = self
+            .mime_registry
+            .resolve(declared, &content)
+            .map_err(PayloadError::from)?;
+
+        // Create payload, owned by whoever receives the management token
+        let management_token = ManagementToken::generate();
+        let mut payload = Payload::new(
+            content,
+            Some(mime_type.to_string()),
+            Some(expiry_time),
+        )
+        .map_err(UseCaseError::DomainError)?
+        .with_management_token(&management_token)
+        .with_expiry_mode(expiry_mode)
+        .with_one_time(request.one_time.unwrap_or(false))
+        .with_max_views(request.max_views)
+        .with_metadata(metadata);
+        if let Some(password) = request.password {
+            payload = protect_with_password(payload, password).await?;
+        }
+
+        // Save payload under a fresh hash ID, retrying if it is already taken
+        for attempt in 1..=MAX_ID_ATTEMPTS {
+            payload = payload.with_hash_id(self.id_generator.generate());
+            match self.repository.insert(&payload).await {
+                Ok(()) => break,
+                Err(RepositoryError::Conflict(_)) if attempt < MAX_ID_ATTEMPTS => {}
+                Err(e) => return Err(UseCaseError::RepositoryError(e)),
+            }
+        }
+
+        // Reserve the requested alias and share code, giving up on the
+        // payload if they cannot be reserved
+        let share_code = request.share_code.unwrap_or(false);
+        if alias.is_some() || share_code {
+            match self.reserve_names(&payload, alias.as_ref(), share_code).await {
+                Ok(share_code) => {
+                    if let Some(alias) = alias {
+                        payload = payload.with_alias(alias);
+                    }
+                    if let Some(share_code) = share_code {
+                        payload = payload.with_share_code(share_code);
+                    }
+                    if let Err(e) = self.repository.save(&payload).await {
+                        self.abandon(&payload).await;
+                        return Err(e.into());
+                    }
+                }
+                Err(e) => {
+                    self.abandon(&payload).await;
+                    return Err(e);
+                }
+            }
+        }
 
         // Return response
+        let encoding = ContentEncoding::for_content(payload.mime_type(), payload.content());
         Ok(CreatePayloadResponse {
             hash_id: payload.hash_id().as_string().to_string(),
-            content: payload.content().to_string(),
+            content: encoding.encode(payload.content()),
+            encoding,
             mime_type: payload.mime_type().to_string(),
+            version: payload.version(),
             created_at: payload.created_at(),
             updated_at: payload.updated_at(),
             viewed_at: payload.viewed_at(),
             expiry_time: payload.expiry_time(),
+            one_time: payload.is_one_time(),
+            view_count: payload.view_count(),
+            max_views: payload.max_views(),
+            password_protected: payload.is_password_protected(),
+            share_code: payload.share_code().map(ToString::to_string),
+            alias: payload.alias().map(ToString::to_string),
+            management_token: management_token.as_string().to_string(),
         })
     }
 }
@@ -129,47 +478,177 @@ impl GetPayloadUseCaseImpl {
     }
 }
 
-#[async_trait]
-impl GetPayloadUseCase for GetPayloadUseCaseImpl {
-    async fn execute(&self, hash_id: String) -> Result<GetPayloadResponse, UseCaseError> {
-        // Create HashId from string
-        let hash_id = HashId::from_string(hash_id);
-
-        // Get payload from repository
-        let mut payload = self.repository
-            .get(&hash_id)
-            .await
-            .map_err(UseCaseError::RepositoryError)?
-            .ok_or(UseCaseError::NotFound)?;
+impl GetPayloadUseCaseImpl {
+    /// Read a payload, returning the given revision of its content or the
+    /// current one
+    async fn read(
+        &self,
+        hash_id: HashId,
+        revision: Option<u64>,
+        password: Option<String>,
+        if_none_match: Option<VersionMatch>,
+    ) -> Result<GetPayloadResponse, UseCaseError> {
+        // Answers to password attempts must not reveal whether the payload exists
+        let not_found = || match password {
+            Some(_) => UseCaseError::Unauthorized,
+            None => UseCaseError::NotFound,
+        };
 
-        // Check if payload has expired
-        if payload.is_expired() {
-            // Delete expired payload
-            self.repository
-                .delete(&hash_id)
+        // Reads that only want the current content leave every check to the
+        // view below; the payload is fetched first only to check a password,
+        // compare versions, or fetch a revision a one-time view would remove
+        let mut unlocked = false;
+        let mut archived = None;
+        if password.is_some() || if_none_match.is_some() || revision.is_some() {
+            let mut payload = self.repository
+                .get(&hash_id)
                 .await
-                .map_err(UseCaseError::RepositoryError)?;
-            return Err(UseCaseError::Expired);
+                .map_err(UseCaseError::RepositoryError)?
+                .ok_or_else(not_found)?;
+
+            if payload.is_password_protected() {
+                // Only actual guesses count towards throttling
+                if password.is_none() {
+                    return Err(UseCaseError::Unauthorized);
+                }
+
+                // Reserve the attempt before checking it, so concurrent
+                // guesses are throttled as they are made
+                let attempt_at = Utc::now();
+                payload = self.repository
+                    .try_unlock_attempt(&hash_id, attempt_at)
+                    .await
+                    .map_err(|e| match e {
+                        RepositoryError::UnlockThrottled => UseCaseError::Unauthorized,
+                        e => UseCaseError::RepositoryError(e),
+                    })?
+                    .ok_or_else(not_found)?;
+
+                let matches;
+                (payload, matches) = check_password(payload, password.clone()).await?;
+                if !matches {
+                    return Err(UseCaseError::Unauthorized);
+                }
+                self.repository
+                    .release_unlock_attempt(&hash_id, attempt_at, payload.failed_unlock_at())
+                    .await
+                    .map_err(UseCaseError::RepositoryError)?;
+                unlocked = true;
+            }
+
+            // Clients with the current version keep it without using up a view
+            if if_none_match.is_some_and(|versions| versions.matches(payload.version())) {
+                return Err(UseCaseError::NotModified(payload.version()));
+            }
+
+            if payload.view_limit_reached() {
+                return Err(UseCaseError::ViewLimitReached);
+            }
+
+            archived = match revision {
+                Some(number) if number != payload.revision() => {
+                    Some(self.fetch_revision(&hash_id, number).await?)
+                }
+                _ => None,
+            };
         }
 
-        // Mark payload as viewed
-        payload.mark_viewed();
-        self.repository
-            .save(&payload)
+        // Count the view in storage, checking the expiry, password and view
+        // limit in the same step, so concurrent readers are all counted,
+        // never exceed the limit, and only one of them receives a one-time
+        // payload
+        let payload = self.repository
+            .view(&hash_id, unlocked)
             .await
-            .map_err(UseCaseError::RepositoryError)?;
+            .map_err(|e| match e {
+                RepositoryError::ViewLimitReached => UseCaseError::ViewLimitReached,
+                RepositoryError::PasswordRequired => UseCaseError::Unauthorized,
+                e => UseCaseError::RepositoryError(e),
+            })?
+            .ok_or_else(not_found)?;
+
+        // The content may have been replaced since it was checked
+        if let Some(number) = revision {
+            if archived.is_none() && number != payload.revision() {
+                archived = Some(self.fetch_revision(&hash_id, number).await?);
+            }
+        }
+        let revision = archived.unwrap_or_else(|| payload.current_revision());
 
         // Return response
+        let encoding = ContentEncoding::for_content(revision.mime_type(), revision.content());
         Ok(GetPayloadResponse {
             hash_id: payload.hash_id().as_string().to_string(),
-            content: payload.content().to_string(),
-            mime_type: payload.mime_type().to_string(),
+            content: encoding.encode(revision.content()),
+            encoding,
+            mime_type: revision.mime_type().to_string(),
+            revision: revision.number(),
+            version: payload.version(),
             created_at: payload.created_at(),
             updated_at: payload.updated_at(),
             viewed_at: payload.viewed_at(),
             expiry_time: payload.expiry_time(),
+            one_time: payload.is_one_time(),
+            view_count: payload.view_count(),
+            max_views: payload.max_views(),
+            password_protected: payload.is_password_protected(),
+            share_code: payload.share_code().map(ToString::to_string),
+            alias: payload.alias().map(ToString::to_string),
+            filename: payload.metadata().filename().map(str::to_string),
+            title: payload.metadata().title().map(str::to_string),
+            description: payload.metadata().description().map(str::to_string),
+            tags: payload.metadata().tags().clone(),
         })
     }
+
+    /// Fetch an earlier revision of a payload
+    async fn fetch_revision(&self, hash_id: &HashId, number: u64) -> Result<Revision, UseCaseError> {
+        self.repository
+            .get_revision(hash_id, number)
+            .await
+            .map_err(UseCaseError::RepositoryError)?
+            .ok_or(UseCaseError::NotFound)
+    }
+}
+
+#[async_trait]
+impl GetPayloadUseCase for GetPayloadUseCaseImpl {
+    async fn execute(
+        &self,
+        hash_id: HashId,
+        password: Option<String>,
+        if_none_match: Option<VersionMatch>,
+    ) -> Result<GetPayloadResponse, UseCaseError> {
+        self.read(hash_id, None, password, if_none_match).await
+    }
+}
+
+#[async_trait]
+impl GetRevisionUseCase for GetPayloadUseCaseImpl {
+    async fn execute_revision(
+        &self,
+        hash_id: HashId,
+        revision: u64,
+        password: Option<String>,
+        if_none_match: Option<VersionMatch>,
+    ) -> Result<GetPayloadResponse, UseCaseError> {
+        self.read(hash_id, Some(revision), password, if_none_match).await
+    }
+}
+
+/// Check a payload against the versions named by `if_match`.
+///
+/// Returns the version the repository should still find the payload at, so
+/// the change fails if someone else changes the payload in the meantime.
+fn expected_version(
+    payload: &Payload,
+    if_match: Option<VersionMatch>,
+) -> Result<Option<u64>, UseCaseError> {
+    match if_match {
+        None | Some(VersionMatch::Any) => Ok(None),
+        Some(versions) if versions.matches(payload.version()) => Ok(Some(payload.version())),
+        Some(_) => Err(UseCaseError::PreconditionFailed),
+    }
 }
 
 /// Implementation of the delete payload use case.
@@ -179,6 +658,7 @@ pub struct DeletePayloadUseCaseImpl {
 }
 
 impl DeletePayloadUseCaseImpl {
+    /// Create a new instance of the use case implementation.
     pub fn new(repository: Arc<dyn Repository>) -> Self {
         Self { repository }
     }
@@ -186,32 +666,268 @@ impl DeletePayloadUseCaseImpl {
 
 #[async_trait]
 impl DeletePayloadUseCase for DeletePayloadUseCaseImpl {
-    async fn delete(&self, id: &str) -> Result<(), UseCaseError> {
-        let hash_id = HashId::from_string(id.to_string());
+    async fn delete(
+        &self,
+        hash_id: &HashId,
+        token: Option<&str>,
+        if_match: Option<VersionMatch>,
+    ) -> Result<(), UseCaseError> {
+        let token = token
+            .map(|token| ManagementToken::from_string(token.to_string()))
+            .ok_or(UseCaseError::Unauthorized)?;
+
+        let payload = self.repository
+            .get(hash_id)
+            .await
+            .map_err(UseCaseError::RepositoryError)?
+            .ok_or(UseCaseError::NotFound)?;
+        if !payload.is_managed_by(&token) {
+            return Err(UseCaseError::Unauthorized);
+        }
+        let version = expected_version(&payload, if_match)?;
         
-        // First check if the payload exists
-        match self.repository.get(&hash_id).await {
-            Ok(Some(_)) => {
-                // Payload exists, proceed with deletion
-                self.repository.delete(&hash_id).await
-                    .map_err(|e| UseCaseError::RepositoryError(e))
-            }
-            Ok(None) => {
-                // Payload not found
-                Err(UseCaseError::RepositoryError(anyhow::anyhow!("Payload not found")))
-            }
-            Err(e) => {
-                // Repository error
-                Err(UseCaseError::RepositoryError(e))
+        self.repository
+            .delete(hash_id, version)
+            .await
+            .map_err(|e| match e {
+                RepositoryError::NotFound => UseCaseError::NotFound,
+                e => UseCaseError::RepositoryError(e),
+            })
+    }
+}
+
+/// Implementation of the update payload use case.
+pub struct UpdatePayloadUseCaseImpl {
+    repository: Arc<dyn Repository>,
+    expiry_policy: ExpiryPolicy,
+}
+
+impl UpdatePayloadUseCaseImpl {
+    /// Create a new instance of the use case implementation.
+    ///
+    /// New expiries follow the default [`ExpiryPolicy`].
+    pub fn new(repository: Arc<dyn Repository>) -> Self {
+        Self {
+            repository,
+            expiry_policy: ExpiryPolicy::default(),
+        }
+    }
+
+    /// Use a different policy for new expiries.
+    pub fn with_expiry_policy(mut self, expiry_policy: ExpiryPolicy) -> Self {
+        self.expiry_policy = expiry_policy;
+        self
+    }
+}
+
+#[async_trait]
+impl UpdatePayloadUseCase for UpdatePayloadUseCaseImpl {
+    async fn update(
+        &self,
+        hash_id: &HashId,
+        token: Option<&str>,
+        if_match: Option<VersionMatch>,
+        request: UpdatePayloadRequest,
+    ) -> Result<UpdatePayloadResponse, UseCaseError> {
+        let token = token
+            .map(|token| ManagementToken::from_string(token.to_string()))
+            .ok_or(UseCaseError::Unauthorized)?;
+
+        let payload = self.repository
+            .get(hash_id)
+            .await
+            .map_err(UseCaseError::RepositoryError)?
+            .ok_or(UseCaseError::NotFound)?;
+        if !payload.is_managed_by(&token) {
+            return Err(UseCaseError::Unauthorized);
+        }
+        let version = expected_version(&payload, if_match)?;
+
+        // Work out the new expiry; revoking expires the payload right away
+        let now = Utc::now();
+        let expires_in = request.expires_in.as_deref().map(parse_duration).transpose()?;
+        let expiry_time = if request.revoke.unwrap_or(false) {
+            if request.expiry_time.is_some() || expires_in.is_some() {
+                return Err(ExpiryError::Conflicting.into());
             }
+            now
+        } else {
+            self.expiry_policy
+                .reschedule(request.expiry_time, expires_in, payload.created_at(), now)?
+        };
+
+        let payload = self.repository
+            .update_expiry(hash_id, expiry_time, version)
+            .await
+            .map_err(UseCaseError::RepositoryError)?
+            .ok_or(UseCaseError::NotFound)?;
+
+        // Keep the payload's other names resolving for exactly as long
+        for name in payload.names() {
+            self.repository
+                .update_alias_expiry(name, hash_id, expiry_time)
+                .await
+                .map_err(UseCaseError::RepositoryError)?;
+        }
+
+        Ok(UpdatePayloadResponse {
+            hash_id: payload.hash_id().as_string().to_string(),
+            version: payload.version(),
+            updated_at: payload.updated_at(),
+            expiry_time: payload.expiry_time(),
+        })
+    }
+}
+
+/// Implementation of the replace payload use case.
+pub struct ReplacePayloadUseCaseImpl {
+    repository: Arc<dyn Repository>,
+    mime_registry: Arc<MimeRegistry>,
+    max_revisions: usize,
+}
+
+impl ReplacePayloadUseCaseImpl {
+    /// Create a new instance of the use case implementation.
+    ///
+    /// The MIME types of the default [`MimeRegistry`] are accepted, and
+    /// [`DEFAULT_MAX_REVISIONS`] earlier revisions are kept per payload.
+    pub fn new(repository: Arc<dyn Repository>) -> Self {
+        Self {
+            repository,
+            mime_registry: Arc::new(MimeRegistry::default()),
+            max_revisions: DEFAULT_MAX_REVISIONS,
+        }
+    }
+
+    /// Keep a different number of earlier revisions per payload, dropping
+    /// the oldest as content is replaced.
+    pub fn with_max_revisions(mut self, max_revisions: usize) -> Self {
+        self.max_revisions = max_revisions;
+        self
+    }
+
+    /// Accept the MIME types of a different registry for new content.
+    pub fn with_mime_registry(mut self, mime_registry: Arc<MimeRegistry>) -> Self {
+        self.mime_registry = mime_registry;
+        self
+    }
+}
+
+#[async_trait]
+impl ReplacePayloadUseCase for ReplacePayloadUseCaseImpl {
+    async fn replace(
+        &self,
+        hash_id: &HashId,
+        token: Option<&str>,
+        if_match: Option<VersionMatch>,
+        request: ReplacePayloadRequest,
+    ) -> Result<ReplacePayloadResponse, UseCaseError> {
+        let token = token
+            .map(|token| ManagementToken::from_string(token.to_string()))
+            .ok_or(UseCaseError::Unauthorized)?;
+
+        let mut payload = self.repository
+            .get(hash_id)
+            .await
+            .map_err(UseCaseError::RepositoryError)?
+            .ok_or(UseCaseError::NotFound)?;
+        if !payload.is_managed_by(&token) {
+            return Err(UseCaseError::Unauthorized);
         }
+        expected_version(&payload, if_match)?;
+
+        request.validate().map_err(|e| UseCaseError::ValidationError(e.to_string()))?;
+        let content = request
+            .encoding
+            .unwrap_or_default()
+            .decode(&request.content)
+            .map_err(|e| UseCaseError::ValidationError(format!("Invalid base64 content: {}", e)))?;
+
+        // Content replaced without a MIME type must still match the old one
+        let declared = match request.mime_type.as_deref() {
+            Some(mime_type) => MimeType::parse_requested(mime_type).map_err(PayloadError::from)?,
+            None => Some(payload.mime_type().clone()),
+        };
+        let mime_type = self
+            .mime_registry
+            .resolve(declared, &content)
+            .map_err(PayloadError::from)?;
+
+        // The repository keeps the replaced content, unless another change
+        // got there first
+        let version = payload.version();
+        let previous = payload.replace_content(content, Some(mime_type.to_string()))?;
+        let payload = self.repository
+            .replace_content(&payload, &previous, version, self.max_revisions)
+            .await
+            .map_err(UseCaseError::RepositoryError)?
+            .ok_or(UseCaseError::NotFound)?;
+
+        Ok(ReplacePayloadResponse {
+            hash_id: payload.hash_id().as_string().to_string(),
+            revision: payload.revision(),
+            version: payload.version(),
+            mime_type: payload.mime_type().to_string(),
+            updated_at: payload.updated_at(),
+            expiry_time: payload.expiry_time(),
+        })
+    }
+}
+
+/// Implementation of the resolve share code use case.
+pub struct ResolveShareCodeUseCaseImpl {
+    repository: Arc<dyn Repository>,
+}
+
+impl ResolveShareCodeUseCaseImpl {
+    /// Create a new instance of the use case implementation.
+    pub fn new(repository: Arc<dyn Repository>) -> Self {
+        Self { repository }
+    }
+}
+
+#[async_trait]
+impl ResolveShareCodeUseCase for ResolveShareCodeUseCaseImpl {
+    async fn resolve(&self, code: &ShareCode) -> Result<HashId, UseCaseError> {
+        self.repository
+            .resolve_alias(code.as_str())
+            .await
+            .map_err(UseCaseError::RepositoryError)?
+            .ok_or(UseCaseError::NotFound)
+    }
+}
+
+/// Implementation of the resolve alias use case.
+pub struct ResolveAliasUseCaseImpl {
+    repository: Arc<dyn Repository>,
+}
+
+impl ResolveAliasUseCaseImpl {
+    /// Create a new instance of the use case implementation.
+    pub fn new(repository: Arc<dyn Repository>) -> Self {
+        Self { repository }
+    }
+}
+
+#[async_trait]
+impl ResolveAliasUseCase for ResolveAliasUseCaseImpl {
+    async fn resolve(&self, alias: &Alias) -> Result<HashId, UseCaseError> {
+        self.repository
+            .resolve_alias(alias.as_str())
+            .await
+            .map_err(UseCaseError::RepositoryError)?
+            .ok_or(UseCaseError::NotFound)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use chrono::{Duration, Utc};
+    use chrono::{DateTime, Duration};
+    use crate::domain::{
+        expiry::HumanDuration,
+        mime_type::{MimeTypeError, MismatchPolicy},
+    };
     use mockall::mock;
     use mockall::predicate::*;
 
@@ -219,29 +935,83 @@ mod tests {
         Repository {}
         #[async_trait]
         impl Repository for Repository {
-            async fn save(&self, payload: &Payload) -> Result<(), anyhow::Error>;
-            async fn get(&self, hash_id: &HashId) -> Result<Option<Payload>, anyhow::Error>;
-            async fn delete(&self, hash_id: &HashId) -> Result<(), anyhow::Error>;
+            async fn save(&self, payload: &Payload) -> Result<(), RepositoryError>;
+            async fn insert(&self, payload: &Payload) -> Result<(), RepositoryError>;
+            async fn get(&self, hash_id: &HashId) -> Result<Option<Payload>, RepositoryError>;
+            async fn view(&self, hash_id: &HashId, unlocked: bool) -> Result<Option<Payload>, RepositoryError>;
+            async fn try_unlock_attempt(
+                &self,
+                hash_id: &HashId,
+                at: DateTime<Utc>,
+            ) -> Result<Option<Payload>, RepositoryError>;
+            async fn release_unlock_attempt(
+                &self,
+                hash_id: &HashId,
+                at: DateTime<Utc>,
+                previous: Option<DateTime<Utc>>,
+            ) -> Result<(), RepositoryError>;
+            async fn update_expiry(
+                &self,
+                hash_id: &HashId,
+                expiry_time: DateTime<Utc>,
+                version: Option<u64>,
+            ) -> Result<Option<Payload>, RepositoryError>;
+            async fn replace_content(
+                &self,
+                payload: &Payload,
+                previous: &Revision,
+                version: u64,
+                max_revisions: usize,
+            ) -> Result<Option<Payload>, RepositoryError>;
+            async fn get_revision(
+                &self,
+                hash_id: &HashId,
+                number: u64,
+            ) -> Result<Option<Revision>, RepositoryError>;
+            async fn delete(&self, hash_id: &HashId, version: Option<u64>) -> Result<(), RepositoryError>;
+            async fn insert_alias(
+                &self,
+                alias: &str,
+                hash_id: &HashId,
+                expiry_time: DateTime<Utc>,
+            ) -> Result<(), RepositoryError>;
+            async fn update_alias_expiry(
+                &self,
+                alias: &str,
+                hash_id: &HashId,
+                expiry_time: DateTime<Utc>,
+            ) -> Result<(), RepositoryError>;
+            async fn resolve_alias(&self, alias: &str) -> Result<Option<HashId>, RepositoryError>;
         }
     }
 
     #[tokio::test]
     async fn test_create_payload_success() {
         let mut mock = MockRepository::new();
-        mock.expect_save()
+        let saved = Arc::new(std::sync::Mutex::new(None));
+        let saved_by_mock = saved.clone();
+        mock.expect_insert()
             .with(always())
             .times(1)
-            .returning(|_| Ok(()));
+            .returning(move |payload| {
+                *saved_by_mock.lock().unwrap() = Some(payload.clone());
+                Ok(())
+            });
 
         let use_case = CreatePayloadUseCaseImpl::new(Arc::new(mock));
         let request = CreatePayloadRequest {
             content: "test".to_string(),
             mime_type: Some("text/plain".to_string()),
             expiry_time: Some(Utc::now() + Duration::hours(1)),
+            ..Default::default()
         };
 
-        let result = use_case.execute(request).await;
-        assert!(result.is_ok());
+        let response = use_case.execute(request).await.unwrap();
+
+        // Only the creator learns the token; the payload keeps its hash
+        let saved = saved.lock().unwrap().take().unwrap();
+        let token = ManagementToken::from_string(response.management_token);
+        assert!(saved.is_managed_by(&token));
     }
 
     #[tokio::test]
@@ -250,8 +1020,7 @@ mod tests {
         let use_case = CreatePayloadUseCaseImpl::new(Arc::new(mock));
         let request = CreatePayloadRequest {
             content: "".to_string(), // Empty content should fail validation
-            mime_type: None,
-            expiry_time: None,
+            ..Default::default()
         };
 
         let result = use_case.execute(request).await;
@@ -259,42 +1028,1197 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_get_payload_success() {
-        let mut mock = MockRepository::new();
-        let payload = Payload::new(
-            "test".to_string(),
+    async fn test_create_payload_invalid_base64() {
+        let mock = MockRepository::new();
+        let use_case = CreatePayloadUseCaseImpl::new(Arc::new(mock));
+        let request = CreatePayloadRequest {
+            content: "not base64!".to_string(),
+            mime_type: Some("image/png".to_string()),
+            encoding: Some(ContentEncoding::Base64),
+            ..Default::default()
+        };
+
+        let result = use_case.execute(request).await;
+        assert!(matches!(result, Err(UseCaseError::ValidationError(_))));
+    }
+
+    #[tokio::test]
+    async fn test_create_payload_expires_in() {
+        let mut mock = MockRepository::new();
+        mock.expect_insert()
+            .withf(|payload| {
+                let lifetime = payload.expiry_time() - payload.created_at();
+                lifetime <= Duration::minutes(90) && lifetime > Duration::minutes(89)
+            })
+            .times(1)
+            .returning(|_| Ok(()));
+
+        let policy = ExpiryPolicy::new(Duration::hours(1), Duration::minutes(1), Duration::hours(2)).unwrap();
+        let use_case = CreatePayloadUseCaseImpl::new(Arc::new(mock)).with_expiry_policy(policy);
+        let request = CreatePayloadRequest {
+            content: "test".to_string(),
+            expires_in: Some("1h30m".to_string()),
+            ..Default::default()
+        };
+
+        use_case.execute(request).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_create_payload_rejects_expiry_outside_policy() {
+        // Nothing may be stored for a rejected expiry
+        let mock = MockRepository::new();
+        let policy = ExpiryPolicy::new(Duration::hours(1), Duration::minutes(1), Duration::hours(2)).unwrap();
+        let use_case = CreatePayloadUseCaseImpl::new(Arc::new(mock)).with_expiry_policy(policy);
+
+        let cases = [
+            (Some(Utc::now() - Duration::seconds(1)), None, ExpiryError::InPast),
+            (None, Some("3h"), ExpiryError::TooLong(HumanDuration(Duration::hours(2)))),
+            (None, Some("30s"), ExpiryError::TooShort(HumanDuration(Duration::minutes(1)))),
+            (None, Some("soon"), ExpiryError::InvalidDuration("soon".to_string())),
+            (Some(Utc::now() + Duration::hours(1)), Some("1h"), ExpiryError::Conflicting),
+        ];
+        for (expiry_time, expires_in, expected) in cases {
+            let request = CreatePayloadRequest {
+                content: "test".to_string(),
+                expiry_time,
+                expires_in: expires_in.map(ToString::to_string),
+                ..Default::default()
+            };
+
+            match use_case.execute(request).await {
+                Err(UseCaseError::InvalidExpiry(e)) => assert_eq!(e, expected),
+                other => panic!("Expected {:?}, got {:?}", expected, other),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_create_payload_idle_timeout() {
+        let mut mock = MockRepository::new();
+        mock.expect_insert()
+            .withf(|payload| {
+                payload.expiry_mode() == ExpiryMode::Idle { timeout_secs: 600 }
+                    && payload.expiry_time() <= Utc::now() + Duration::minutes(10)
+            })
+            .times(1)
+            .returning(|_| Ok(()));
+        // The alias must resolve for as long as views can keep the payload
+        mock.expect_insert_alias()
+            .withf(|alias, _, expiry_time| {
+                alias == "idle-notes" && *expiry_time > Utc::now() + Duration::minutes(59)
+            })
+            .times(1)
+            .returning(|_, _, _| Ok(()));
+        mock.expect_save().times(1).returning(|_| Ok(()));
+
+        let use_case = CreatePayloadUseCaseImpl::new(Arc::new(mock));
+        let request = CreatePayloadRequest {
+            content: "test".to_string(),
+            expires_in: Some("1h".to_string()),
+            idle_timeout: Some("10m".to_string()),
+            alias: Some("idle-notes".to_string()),
+            ..Default::default()
+        };
+
+        let response = use_case.execute(request).await.unwrap();
+        assert!(response.expiry_time <= Utc::now() + Duration::minutes(10));
+    }
+
+    #[tokio::test]
+    async fn test_create_payload_rejects_invalid_expiry_mode() {
+        let use_case = CreatePayloadUseCaseImpl::new(Arc::new(MockRepository::new()));
+
+        let cases = [
+            (Some("10m"), Some("10m"), ExpiryError::ConflictingModes),
+            (Some("30s"), None, ExpiryError::TooShort(HumanDuration(Duration::minutes(1)))),
+            (None, Some("31d"), ExpiryError::TooLong(HumanDuration(Duration::days(30)))),
+        ];
+        for (idle_timeout, expires_after_view, expected) in cases {
+            let request = CreatePayloadRequest {
+                content: "test".to_string(),
+                idle_timeout: idle_timeout.map(ToString::to_string),
+                expires_after_view: expires_after_view.map(ToString::to_string),
+                ..Default::default()
+            };
+
+            match use_case.execute(request).await {
+                Err(UseCaseError::InvalidExpiry(e)) => assert_eq!(e, expected),
+                other => panic!("Expected {:?}, got {:?}", expected, other),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_create_binary_payload_returns_base64() {
+        let mut mock = MockRepository::new();
+        mock.expect_insert()
+            .withf(|payload| payload.content() == b"\x89PNG\r\n\x1a\n\x00")
+            .times(1)
+            .returning(|_| Ok(()));
+
+        let use_case = CreatePayloadUseCaseImpl::new(Arc::new(mock));
+        let request = CreatePayloadRequest {
+            content: "iVBORw0KGgoA".to_string(),
+            mime_type: Some("image/png".to_string()),
+            encoding: Some(ContentEncoding::Base64),
+            ..Default::default()
+        };
+
+        let response = use_case.execute(request).await.unwrap();
+        assert_eq!(response.encoding, ContentEncoding::Base64);
+        assert_eq!(response.content, "iVBORw0KGgoA");
+    }
+
+    fn create_request(content: &str, mime_type: Option<&str>) -> CreatePayloadRequest {
+        CreatePayloadRequest {
+            content: content.to_string(),
+            mime_type: mime_type.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_create_payload_rejects_mismatched_content() {
+        let mut mock = MockRepository::new();
+        mock.expect_insert().never();
+
+        let use_case = CreatePayloadUseCaseImpl::new(Arc::new(mock));
+        for (content, mime_type) in [("not a png", "image/png"), ("{oops", "application/json")] {
+            let result = use_case.execute(create_request(content, Some(mime_type))).await;
+            assert!(matches!(
+                result,
+                Err(UseCaseError::DomainError(PayloadError::InvalidMimeType(MimeTypeError::Mismatch { .. })))
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_create_payload_detects_mime_type() {
+        let mut mock = MockRepository::new();
+        mock.expect_insert()
+            .withf(|payload| payload.mime_type().essence() == "application/json")
+            .times(2)
+            .returning(|_| Ok(()));
+
+        // Asked for explicitly, or by default when configured
+        let registry = MimeRegistry::default().with_default_type(None);
+        let use_case = CreatePayloadUseCaseImpl::new(Arc::new(mock))
+            .with_mime_registry(Arc::new(registry));
+        for mime_type in [Some("auto"), None] {
+            let response = use_case
+                .execute(create_request(r#"{"a": 1}"#, mime_type))
+                .await
+                .unwrap();
+            assert_eq!(response.mime_type, "application/json");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_create_payload_corrects_mismatched_content() {
+        let mut mock = MockRepository::new();
+        mock.expect_insert()
+            .withf(|payload| payload.mime_type().essence() == "text/plain")
+            .times(1)
+            .returning(|_| Ok(()));
+
+        let registry = MimeRegistry::default().with_mismatch_policy(MismatchPolicy::Correct);
+        let use_case = CreatePayloadUseCaseImpl::new(Arc::new(mock))
+            .with_mime_registry(Arc::new(registry));
+        let response = use_case
+            .execute(create_request("not a png", Some("image/png")))
+            .await
+            .unwrap();
+        assert_eq!(response.mime_type, "text/plain");
+    }
+
+    #[tokio::test]
+    async fn test_create_payload_with_metadata() {
+        let mut mock = MockRepository::new();
+        mock.expect_insert()
+            .withf(|payload| {
+                let metadata = payload.metadata();
+                metadata.filename() == Some("notes.txt")
+                    && metadata.title() == Some("Notes")
+                    && metadata.tags().get("team").map(String::as_str) == Some("payments")
+            })
+            .times(1)
+            .returning(|_| Ok(()));
+
+        let use_case = CreatePayloadUseCaseImpl::new(Arc::new(mock));
+        let mut request = create_request("hello", None);
+        request.filename = Some("notes.txt".to_string());
+        request.title = Some("Notes".to_string());
+        request.tags = Some([("team".to_string(), "payments".to_string())].into());
+        use_case.execute(request).await.unwrap();
+
+        // Filenames must not be paths
+        let mut request = create_request("hello", None);
+        request.filename = Some("../notes.txt".to_string());
+        let result = use_case.execute(request).await;
+        assert!(matches!(result, Err(UseCaseError::ValidationError(_))));
+    }
+
+    /// Generates the given IDs in order.
+    struct SequenceGenerator(std::sync::Mutex<Vec<&'static str>>);
+
+    impl HashIdGenerator for SequenceGenerator {
+        fn generate(&self) -> HashId {
+            HashId::from_string(self.0.lock().unwrap().remove(0).to_string())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_create_payload_retries_taken_hash_id() {
+        let mut mock = MockRepository::new();
+        mock.expect_insert()
+            .withf(|payload| payload.hash_id().as_string() == "taken")
+            .times(1)
+            .returning(|_| Err(RepositoryError::Conflict("taken".to_string())));
+        mock.expect_insert()
+            .withf(|payload| payload.hash_id().as_string() == "free")
+            .times(1)
+            .returning(|_| Ok(()));
+
+        let generator = SequenceGenerator(std::sync::Mutex::new(vec!["taken", "free"]));
+        let use_case = CreatePayloadUseCaseImpl::new(Arc::new(mock))
+            .with_id_generator(Arc::new(generator));
+        let request = CreatePayloadRequest {
+            content: "test".to_string(),
+            ..Default::default()
+        };
+
+        let response = use_case.execute(request).await.unwrap();
+        assert_eq!(response.hash_id, "free");
+    }
+
+    #[tokio::test]
+    async fn test_create_payload_gives_up_on_collisions() {
+        let mut mock = MockRepository::new();
+        mock.expect_insert()
+            .times(MAX_ID_ATTEMPTS)
+            .returning(|_| Err(RepositoryError::Conflict("taken".to_string())));
+
+        let use_case = CreatePayloadUseCaseImpl::new(Arc::new(mock));
+        let request = CreatePayloadRequest {
+            content: "test".to_string(),
+            ..Default::default()
+        };
+
+        let result = use_case.execute(request).await;
+        assert!(matches!(
+            result,
+            Err(UseCaseError::RepositoryError(RepositoryError::Conflict(_)))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_create_payload_with_share_code_retries_taken_codes() {
+        let mut mock = MockRepository::new();
+        let mut attempts = 0;
+        mock.expect_insert().times(1).returning(|_| Ok(()));
+        mock.expect_insert_alias()
+            .times(2)
+            .returning(move |_, _, _| {
+                attempts += 1;
+                if attempts == 1 {
+                    Err(RepositoryError::Conflict("taken".to_string()))
+                } else {
+                    Ok(())
+                }
+            });
+        mock.expect_save()
+            .withf(|payload| payload.share_code().is_some())
+            .times(1)
+            .returning(|_| Ok(()));
+
+        let use_case = CreatePayloadUseCaseImpl::new(Arc::new(mock));
+        let request = CreatePayloadRequest {
+            content: "test".to_string(),
+            share_code: Some(true),
+            ..Default::default()
+        };
+
+        let response = use_case.execute(request).await.unwrap();
+        let share_code = response.share_code.expect("share code should be returned");
+        assert!(ShareCode::parse(&share_code).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_create_payload_without_free_share_code_releases_alias() {
+        let mut mock = MockRepository::new();
+        let mut attempts = 0;
+        mock.expect_insert().times(1).returning(|_| Ok(()));
+        mock.expect_insert_alias()
+            .times(1 + MAX_SHARE_CODE_ATTEMPTS)
+            .returning(move |_, _, _| {
+                attempts += 1;
+                if attempts == 1 {
+                    Ok(())
+                } else {
+                    Err(RepositoryError::Conflict("taken".to_string()))
+                }
+            });
+        mock.expect_update_alias_expiry()
+            .withf(|alias, _, expiry_time| alias == "release-notes-42" && *expiry_time <= Utc::now())
+            .times(1)
+            .returning(|_, _, _| Ok(()));
+        mock.expect_save().never();
+        mock.expect_delete().times(1).returning(|_, _| Ok(()));
+
+        let use_case = CreatePayloadUseCaseImpl::new(Arc::new(mock));
+        let request = CreatePayloadRequest {
+            content: "test".to_string(),
+            share_code: Some(true),
+            alias: Some("release-notes-42".to_string()),
+            ..Default::default()
+        };
+
+        let result = use_case.execute(request).await;
+        assert!(matches!(
+            result,
+            Err(UseCaseError::RepositoryError(RepositoryError::Conflict(_)))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_create_payload_without_free_share_code_is_removed() {
+        let mut mock = MockRepository::new();
+        mock.expect_insert().times(1).returning(|_| Ok(()));
+        mock.expect_insert_alias()
+            .times(MAX_SHARE_CODE_ATTEMPTS)
+            .returning(|_, _, _| Err(RepositoryError::Conflict("taken".to_string())));
+        mock.expect_save().never();
+        mock.expect_delete().times(1).returning(|_, _| Ok(()));
+
+        let use_case = CreatePayloadUseCaseImpl::new(Arc::new(mock));
+        let request = CreatePayloadRequest {
+            content: "test".to_string(),
+            share_code: Some(true),
+            ..Default::default()
+        };
+
+        let result = use_case.execute(request).await;
+        assert!(matches!(
+            result,
+            Err(UseCaseError::RepositoryError(RepositoryError::Conflict(_)))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_create_payload_with_taken_alias_is_removed() {
+        let mut mock = MockRepository::new();
+        mock.expect_insert().times(1).returning(|_| Ok(()));
+        mock.expect_insert_alias()
+            .withf(|alias, _, _| alias == "release-notes")
+            .times(1)
+            .returning(|_, _, _| Err(RepositoryError::Conflict("taken".to_string())));
+        mock.expect_save().never();
+        mock.expect_delete().times(1).returning(|_, _| Ok(()));
+
+        let use_case = CreatePayloadUseCaseImpl::new(Arc::new(mock));
+        let request = CreatePayloadRequest {
+            content: "test".to_string(),
+            alias: Some("release-notes".to_string()),
+            ..Default::default()
+        };
+
+        let result = use_case.execute(request).await;
+        assert!(matches!(result, Err(UseCaseError::AliasTaken(alias)) if alias == "release-notes"));
+    }
+
+    #[tokio::test]
+    async fn test_create_payload_with_invalid_alias() {
+        let mut mock = MockRepository::new();
+        mock.expect_insert().never();
+
+        let use_case = CreatePayloadUseCaseImpl::new(Arc::new(mock));
+        let request = CreatePayloadRequest {
+            content: "test".to_string(),
+            alias: Some("Release Notes".to_string()),
+            ..Default::default()
+        };
+
+        let result = use_case.execute(request).await;
+        assert!(matches!(result, Err(UseCaseError::ValidationError(_))));
+    }
+
+    #[tokio::test]
+    async fn test_resolve_unknown_share_code() {
+        let mut mock = MockRepository::new();
+        mock.expect_resolve_alias()
+            .with(eq("7-crossover-clockwork"))
+            .times(1)
+            .returning(|_| Ok(None));
+
+        let use_case = ResolveShareCodeUseCaseImpl::new(Arc::new(mock));
+        let code = ShareCode::parse("7-crossover-clockwork").unwrap();
+        assert!(matches!(use_case.resolve(&code).await, Err(UseCaseError::NotFound)));
+    }
+
+    #[tokio::test]
+    async fn test_get_payload_success() {
+        let mut mock = MockRepository::new();
+        let payload = Payload::new(
+            b"test".to_vec(),
             Some("text/plain".to_string()),
             Some(Utc::now() + Duration::hours(1)),
         ).unwrap();
         let hash_id = payload.hash_id().clone();
 
+        let mut viewed = payload.clone();
+        viewed.mark_viewed();
+        // Without a password or version to check, the view does every check
+        mock.expect_get().never();
+        
+        // The view is recorded in the repository rather than by saving
+        mock.expect_view()
+            .with(eq(hash_id.clone()), eq(false))
+            .times(1)
+            .returning(move |_, _| Ok(Some(viewed.clone())));
+        mock.expect_save().never();
+
+        let use_case = GetPayloadUseCaseImpl::new(Arc::new(mock));
+        let result = use_case.execute(hash_id.clone(), None, None).await;
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_get_payload_expired() {
+        let mut mock = MockRepository::new();
+        let payload = Payload::new(
+            b"test".to_vec(),
+            None,
+            Some(Utc::now() - Duration::seconds(1)),
+        ).unwrap();
+        let hash_id = payload.hash_id().clone();
+
+        // The view itself drops expired payloads, leaving nothing to delete
+        mock.expect_get().never();
+        mock.expect_delete().never();
+        mock.expect_view()
+            .with(eq(hash_id.clone()), eq(false))
+            .times(1)
+            .returning(|_, _| Ok(None));
+
+        let use_case = GetPayloadUseCaseImpl::new(Arc::new(mock));
+        let result = use_case.execute(hash_id.clone(), None, None).await;
+        assert!(matches!(result, Err(UseCaseError::NotFound)));
+    }
+
+    #[tokio::test]
+    async fn test_get_one_time_payload_takes_it() {
+        let mut mock = MockRepository::new();
+        let payload = Payload::new(b"secret".to_vec(), None, None)
+            .unwrap()
+            .with_one_time(true);
+        let hash_id = payload.hash_id().clone();
+
+        let mut viewed = payload.clone();
+        viewed.mark_viewed();
+        mock.expect_view()
+            .with(eq(hash_id.clone()), eq(false))
+            .times(1)
+            .returning(move |_, _| Ok(Some(viewed.clone())));
+        mock.expect_save().never();
+
+        let use_case = GetPayloadUseCaseImpl::new(Arc::new(mock));
+        let response = use_case.execute(hash_id.clone(), None, None).await.unwrap();
+        assert_eq!(response.content, "secret");
+        assert!(response.one_time);
+        assert!(response.viewed_at.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_get_one_time_payload_lost_race() {
+        let mut mock = MockRepository::new();
+        let payload = Payload::new(b"secret".to_vec(), None, None)
+            .unwrap()
+            .with_one_time(true);
+        let hash_id = payload.hash_id().clone();
+
+        // Another reader took the payload between our get and view
         mock.expect_get()
-            .with(eq(hash_id.clone()))
             .times(1)
             .returning(move |_| Ok(Some(payload.clone())));
-        
-        // We also need to expect save since the use case marks the payload as viewed
-        mock.expect_save()
+        mock.expect_view()
             .times(1)
-            .returning(|_| Ok(()));
+            .returning(|_, _| Ok(None));
 
         let use_case = GetPayloadUseCaseImpl::new(Arc::new(mock));
-        let result = use_case.execute(hash_id.as_string().to_string()).await;
-        assert!(result.is_ok());
+        let if_none_match = Some(VersionMatch::Versions(vec![2]));
+        let result = use_case.execute(hash_id.clone(), None, if_none_match).await;
+        assert!(matches!(result, Err(UseCaseError::NotFound)));
     }
 
     #[tokio::test]
-    async fn test_get_payload_not_found() {
+    async fn test_get_payload_view_limit_reached() {
         let mut mock = MockRepository::new();
-        let hash_id = HashId::new();
+        let mut payload = Payload::new(b"test".to_vec(), None, None)
+            .unwrap()
+            .with_max_views(Some(1));
+        payload.mark_viewed();
+        let hash_id = payload.hash_id().clone();
+
+        mock.expect_get()
+            .times(1)
+            .returning(move |_| Ok(Some(payload.clone())));
+        mock.expect_view().never();
+
+        let use_case = GetPayloadUseCaseImpl::new(Arc::new(mock));
+        let if_none_match = Some(VersionMatch::Versions(vec![2]));
+        let result = use_case.execute(hash_id.clone(), None, if_none_match).await;
+        assert!(matches!(result, Err(UseCaseError::ViewLimitReached)));
+    }
+
+    #[tokio::test]
+    async fn test_get_payload_view_limit_used_up_concurrently() {
+        let mut mock = MockRepository::new();
+        let payload = Payload::new(b"test".to_vec(), None, None)
+            .unwrap()
+            .with_max_views(Some(1));
+        let hash_id = payload.hash_id().clone();
+
+        // Another reader recorded the last allowed view after our get
+        mock.expect_get()
+            .times(1)
+            .returning(move |_| Ok(Some(payload.clone())));
+        mock.expect_view()
+            .times(1)
+            .returning(|_, _| Err(RepositoryError::ViewLimitReached));
+
+        let use_case = GetPayloadUseCaseImpl::new(Arc::new(mock));
+        let if_none_match = Some(VersionMatch::Versions(vec![2]));
+        let result = use_case.execute(hash_id.clone(), None, if_none_match).await;
+        assert!(matches!(result, Err(UseCaseError::ViewLimitReached)));
+    }
+
+    fn protected_payload() -> Payload {
+        Payload::new(b"secret".to_vec(), None, None)
+            .unwrap()
+            .with_password("letmein")
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_get_protected_payload_with_password() {
+        let mut mock = MockRepository::new();
+        let payload = protected_payload();
+        let hash_id = payload.hash_id().clone();
+
+        let stored = payload.clone();
+        mock.expect_get()
+            .times(1)
+            .returning(move |_| Ok(Some(stored.clone())));
+        let stored = payload.clone();
+        mock.expect_try_unlock_attempt()
+            .with(eq(hash_id.clone()), always())
+            .times(1)
+            .returning(move |_, _| Ok(Some(stored.clone())));
+        // The right password hands its attempt back
+        mock.expect_release_unlock_attempt()
+            .with(eq(hash_id.clone()), always(), eq(None))
+            .times(1)
+            .returning(|_, _, _| Ok(()));
+        mock.expect_view()
+            .with(eq(hash_id.clone()), eq(true))
+            .times(1)
+            .returning(move |_, _| Ok(Some(payload.clone())));
+
+        let use_case = GetPayloadUseCaseImpl::new(Arc::new(mock));
+        let response = use_case
+            .execute(hash_id.clone(), Some("letmein".to_string()), None)
+            .await
+            .unwrap();
+        assert_eq!(response.content, "secret");
+        assert!(response.password_protected);
+    }
+
+    #[tokio::test]
+    async fn test_get_protected_payload_without_password() {
+        let mut mock = MockRepository::new();
+        let payload = protected_payload();
+        let hash_id = payload.hash_id().clone();
+
+        // The view refuses the locked payload without counting it
+        mock.expect_get().never();
+        mock.expect_view()
+            .with(eq(hash_id.clone()), eq(false))
+            .times(1)
+            .returning(|_, _| Err(RepositoryError::PasswordRequired));
+        // No guess was made, so nothing counts towards throttling
+        mock.expect_try_unlock_attempt().never();
 
+        let use_case = GetPayloadUseCaseImpl::new(Arc::new(mock));
+        let result = use_case.execute(hash_id.clone(), None, None).await;
+        assert!(matches!(result, Err(UseCaseError::Unauthorized)));
+    }
+
+    #[tokio::test]
+    async fn test_get_protected_payload_wrong_password_is_counted() {
+        let mut mock = MockRepository::new();
+        let payload = protected_payload();
+        let hash_id = payload.hash_id().clone();
+
+        let stored = payload.clone();
+        mock.expect_get()
+            .times(1)
+            .returning(move |_| Ok(Some(stored.clone())));
+        // The reserved attempt stays counted as a wrong password
+        mock.expect_try_unlock_attempt()
+            .with(eq(hash_id.clone()), always())
+            .times(1)
+            .returning(move |_, _| Ok(Some(payload.clone())));
+        mock.expect_release_unlock_attempt().never();
+        mock.expect_view().never();
+
+        let use_case = GetPayloadUseCaseImpl::new(Arc::new(mock));
+        let result = use_case
+            .execute(hash_id.clone(), Some("guess".to_string()), None)
+            .await;
+        assert!(matches!(result, Err(UseCaseError::Unauthorized)));
+    }
+
+    #[tokio::test]
+    async fn test_get_protected_payload_throttled() {
+        let mut mock = MockRepository::new();
+        let payload = protected_payload();
+        let hash_id = payload.hash_id().clone();
+
+        // Concurrent guesses throttled the payload after it was fetched, and
+        // even the right password is refused while throttled
+        mock.expect_get()
+            .times(1)
+            .returning(move |_| Ok(Some(payload.clone())));
+        mock.expect_try_unlock_attempt()
+            .times(1)
+            .returning(|_, _| Err(RepositoryError::UnlockThrottled));
+        mock.expect_release_unlock_attempt().never();
+        mock.expect_view().never();
+
+        let use_case = GetPayloadUseCaseImpl::new(Arc::new(mock));
+        let result = use_case
+            .execute(hash_id.clone(), Some("letmein".to_string()), None)
+            .await;
+        assert!(matches!(result, Err(UseCaseError::Unauthorized)));
+    }
+
+    #[tokio::test]
+    async fn test_get_missing_payload_with_password() {
+        let mut mock = MockRepository::new();
         mock.expect_get()
-            .with(eq(hash_id.clone()))
             .times(1)
             .returning(|_| Ok(None));
 
         let use_case = GetPayloadUseCaseImpl::new(Arc::new(mock));
-        let result = use_case.execute(hash_id.as_string().to_string()).await;
+        let result = use_case
+            .execute(HashId::from_string("missing".to_string()), Some("guess".to_string()), None)
+            .await;
+        assert!(matches!(result, Err(UseCaseError::Unauthorized)));
+    }
+
+    #[tokio::test]
+    async fn test_create_payload_repository_unavailable() {
+        let mut mock = MockRepository::new();
+        mock.expect_insert()
+            .times(1)
+            .returning(|_| Err(RepositoryError::Unavailable("connection refused".to_string())));
+
+        let use_case = CreatePayloadUseCaseImpl::new(Arc::new(mock));
+        let request = CreatePayloadRequest {
+            content: "test".to_string(),
+            ..Default::default()
+        };
+
+        let result = use_case.execute(request).await;
+        assert!(matches!(
+            result,
+            Err(UseCaseError::RepositoryError(RepositoryError::Unavailable(_)))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_delete_payload_not_found() {
+        let mut mock = MockRepository::new();
+        mock.expect_get()
+            .times(1)
+            .returning(|_| Ok(None));
+        mock.expect_delete().never();
+
+        let use_case = DeletePayloadUseCaseImpl::new(Arc::new(mock));
+        let result = use_case.delete(&HashId::new(), Some("token"), None).await;
+        assert!(matches!(result, Err(UseCaseError::NotFound)));
+    }
+
+    #[tokio::test]
+    async fn test_delete_payload_deleted_concurrently() {
+        let mut mock = MockRepository::new();
+        let token = ManagementToken::generate();
+        let payload = Payload::new(b"test".to_vec(), None, None)
+            .unwrap()
+            .with_management_token(&token);
+
+        mock.expect_get()
+            .times(1)
+            .returning(move |_| Ok(Some(payload.clone())));
+        mock.expect_delete()
+            .times(1)
+            .returning(|_, _| Err(RepositoryError::NotFound));
+
+        let use_case = DeletePayloadUseCaseImpl::new(Arc::new(mock));
+        let result = use_case.delete(&HashId::new(), Some(token.as_string()), None).await;
+        assert!(matches!(result, Err(UseCaseError::NotFound)));
+    }
+
+    #[tokio::test]
+    async fn test_delete_payload_requires_management_token() {
+        let token = ManagementToken::generate();
+        let payload = Payload::new(b"test".to_vec(), None, None)
+            .unwrap()
+            .with_management_token(&token);
+        let hash_id = payload.hash_id().clone();
+
+        for presented in [None, Some("not the token")] {
+            let mut mock = MockRepository::new();
+            let stored = payload.clone();
+            mock.expect_get()
+                .returning(move |_| Ok(Some(stored.clone())));
+            mock.expect_delete().never();
+
+            let use_case = DeletePayloadUseCaseImpl::new(Arc::new(mock));
+            let result = use_case.delete(&hash_id, presented, None).await;
+            assert!(matches!(result, Err(UseCaseError::Unauthorized)));
+        }
+
+        let mut mock = MockRepository::new();
+        mock.expect_get()
+            .returning(move |_| Ok(Some(payload.clone())));
+        mock.expect_delete()
+            .times(1)
+            .returning(|_, _| Ok(()));
+
+        let use_case = DeletePayloadUseCaseImpl::new(Arc::new(mock));
+        assert!(use_case.delete(&hash_id, Some(token.as_string()), None).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_update_payload_requires_management_token() {
+        let token = ManagementToken::generate();
+        let payload = Payload::new(b"test".to_vec(), None, None)
+            .unwrap()
+            .with_management_token(&token);
+        let hash_id = payload.hash_id().clone();
+
+        for presented in [None, Some("not the token")] {
+            let mut mock = MockRepository::new();
+            let stored = payload.clone();
+            mock.expect_get()
+                .returning(move |_| Ok(Some(stored.clone())));
+            mock.expect_update_expiry().never();
+
+            let use_case = UpdatePayloadUseCaseImpl::new(Arc::new(mock));
+            let request = UpdatePayloadRequest {
+                expires_in: Some("2h".to_string()),
+                ..Default::default()
+            };
+            let result = use_case.update(&hash_id, presented, None, request).await;
+            assert!(matches!(result, Err(UseCaseError::Unauthorized)));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_update_payload_extends_expiry_with_names() {
+        let token = ManagementToken::generate();
+        let payload = Payload::new(b"test".to_vec(), None, None)
+            .unwrap()
+            .with_management_token(&token)
+            .with_alias(Alias::parse("release-notes").unwrap())
+            .with_share_code(ShareCode::parse("7-crossover-clockwork").unwrap());
+        let hash_id = payload.hash_id().clone();
+
+        let mut mock = MockRepository::new();
+        let stored = payload.clone();
+        mock.expect_get()
+            .returning(move |_| Ok(Some(stored.clone())));
+        mock.expect_update_expiry()
+            .withf(|_, expiry_time, _| {
+                let expires_in = *expiry_time - Utc::now();
+                expires_in <= Duration::hours(30) && expires_in > Duration::hours(29)
+            })
+            .times(1)
+            .returning(move |_, expiry_time, _| {
+                let mut updated = payload.clone();
+                updated.set_expiry_time(expiry_time);
+                Ok(Some(updated))
+            });
+        mock.expect_update_alias_expiry()
+            .withf(|name, _, _| name == "release-notes" || name == "7-crossover-clockwork")
+            .times(2)
+            .returning(|_, _, _| Ok(()));
+
+        let use_case = UpdatePayloadUseCaseImpl::new(Arc::new(mock));
+        let request = UpdatePayloadRequest {
+            expires_in: Some("30h".to_string()),
+            ..Default::default()
+        };
+        let response = use_case.update(&hash_id, Some(token.as_string()), None, request).await.unwrap();
+        assert!(response.expiry_time > Utc::now() + Duration::hours(29));
+        assert_eq!(response.hash_id, hash_id.as_string());
+    }
+
+    #[tokio::test]
+    async fn test_update_payload_revoke() {
+        let token = ManagementToken::generate();
+        let payload = Payload::new(b"test".to_vec(), None, None)
+            .unwrap()
+            .with_management_token(&token);
+        let hash_id = payload.hash_id().clone();
+
+        let mut mock = MockRepository::new();
+        let stored = payload.clone();
+        mock.expect_get()
+            .returning(move |_| Ok(Some(stored.clone())));
+        mock.expect_update_expiry()
+            .withf(|_, expiry_time, _| *expiry_time <= Utc::now())
+            .times(1)
+            .returning(move |_, expiry_time, _| {
+                let mut updated = payload.clone();
+                updated.set_expiry_time(expiry_time);
+                Ok(Some(updated))
+            });
+
+        let use_case = UpdatePayloadUseCaseImpl::new(Arc::new(mock));
+        let request = UpdatePayloadRequest {
+            revoke: Some(true),
+            ..Default::default()
+        };
+        use_case.update(&hash_id, Some(token.as_string()), None, request).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_update_payload_rejects_expiry_outside_policy() {
+        let token = ManagementToken::generate();
+        let payload = Payload::new(b"test".to_vec(), None, None)
+            .unwrap()
+            .with_management_token(&token);
+        let hash_id = payload.hash_id().clone();
+
+        let requests = [
+            (UpdatePayloadRequest::default(), ExpiryError::Missing),
+            (
+                UpdatePayloadRequest {
+                    expiry_time: Some(Utc::now() - Duration::minutes(1)),
+                    ..Default::default()
+                },
+                ExpiryError::InPast,
+            ),
+            (
+                UpdatePayloadRequest {
+                    expires_in: Some("31d".to_string()),
+                    ..Default::default()
+                },
+                ExpiryError::TooLong(HumanDuration(Duration::days(30))),
+            ),
+            (
+                UpdatePayloadRequest {
+                    expires_in: Some("1h".to_string()),
+                    revoke: Some(true),
+                    ..Default::default()
+                },
+                ExpiryError::Conflicting,
+            ),
+        ];
+        for (request, expected) in requests {
+            let mut mock = MockRepository::new();
+            let stored = payload.clone();
+            mock.expect_get()
+                .returning(move |_| Ok(Some(stored.clone())));
+            mock.expect_update_expiry().never();
+
+            let use_case = UpdatePayloadUseCaseImpl::new(Arc::new(mock));
+            match use_case.update(&hash_id, Some(token.as_string()), None, request).await {
+                Err(UseCaseError::InvalidExpiry(e)) => assert_eq!(e, expected),
+                other => panic!("Expected {:?}, got {:?}", expected, other),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_update_payload_if_match() {
+        let token = ManagementToken::generate();
+        let payload = Payload::new(b"test".to_vec(), None, None)
+            .unwrap()
+            .with_management_token(&token);
+        let hash_id = payload.hash_id().clone();
+        let request = || UpdatePayloadRequest {
+            expires_in: Some("2h".to_string()),
+            ..Default::default()
+        };
+
+        // A client holding an outdated version is turned away
+        let mut mock = MockRepository::new();
+        let stored = payload.clone();
+        mock.expect_get()
+            .returning(move |_| Ok(Some(stored.clone())));
+        mock.expect_update_expiry().never();
+        let use_case = UpdatePayloadUseCaseImpl::new(Arc::new(mock));
+        let if_match = Some(VersionMatch::Versions(vec![2]));
+        let result = use_case.update(&hash_id, Some(token.as_string()), if_match, request()).await;
+        assert!(matches!(result, Err(UseCaseError::PreconditionFailed)));
+
+        // A matching version is checked again as the change is stored
+        let mut mock = MockRepository::new();
+        let stored = payload.clone();
+        mock.expect_get()
+            .returning(move |_| Ok(Some(stored.clone())));
+        mock.expect_update_expiry()
+            .withf(|_, _, version| *version == Some(1))
+            .times(1)
+            .returning(|_, _, _| Err(RepositoryError::VersionMismatch(1)));
+        let use_case = UpdatePayloadUseCaseImpl::new(Arc::new(mock));
+        let if_match = Some(VersionMatch::Versions(vec![1]));
+        let result = use_case.update(&hash_id, Some(token.as_string()), if_match, request()).await;
+        assert!(matches!(
+            result,
+            Err(UseCaseError::RepositoryError(RepositoryError::VersionMismatch(1)))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_get_payload_not_modified() {
+        let mut mock = MockRepository::new();
+        let payload = Payload::new(b"test".to_vec(), None, None)
+            .unwrap()
+            .with_one_time(true);
+        let hash_id = payload.hash_id().clone();
+
+        mock.expect_get()
+            .returning(move |_| Ok(Some(payload.clone())));
+        // The client keeps its copy, so the view is not counted
+        mock.expect_view().never();
+
+        let use_case = GetPayloadUseCaseImpl::new(Arc::new(mock));
+        let if_none_match = Some(VersionMatch::Versions(vec![3, 1]));
+        let result = use_case.execute(hash_id, None, if_none_match).await;
+        assert!(matches!(result, Err(UseCaseError::NotModified(1))));
+    }
+
+    fn replace_request(content: &str, mime_type: Option<&str>) -> ReplacePayloadRequest {
+        ReplacePayloadRequest {
+            content: content.to_string(),
+            mime_type: mime_type.map(str::to_string),
+            encoding: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_replace_payload_requires_management_token() {
+        let token = ManagementToken::generate();
+        let payload = Payload::new(b"test".to_vec(), None, None)
+            .unwrap()
+            .with_management_token(&token);
+        let hash_id = payload.hash_id().clone();
+
+        for presented in [None, Some("not the token")] {
+            let mut mock = MockRepository::new();
+            let stored = payload.clone();
+            mock.expect_get()
+                .returning(move |_| Ok(Some(stored.clone())));
+            mock.expect_replace_content().never();
+
+            let use_case = ReplacePayloadUseCaseImpl::new(Arc::new(mock));
+            let result = use_case
+                .replace(&hash_id, presented, None, replace_request("new", None))
+                .await;
+            assert!(matches!(result, Err(UseCaseError::Unauthorized)));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_replace_payload_keeps_previous_revision() {
+        let token = ManagementToken::generate();
+        let payload = Payload::new(b"old".to_vec(), Some("text/plain".to_string()), None)
+            .unwrap()
+            .with_management_token(&token);
+        let hash_id = payload.hash_id().clone();
+
+        let mut mock = MockRepository::new();
+        mock.expect_get()
+            .returning(move |_| Ok(Some(payload.clone())));
+        mock.expect_replace_content()
+            .withf(|payload, previous, version, max_revisions| {
+                payload.content() == b"{}"
+                    && payload.revision() == 2
+                    && previous.number() == 1
+                    && previous.content() == b"old"
+                    && *version == 1
+                    && *max_revisions == DEFAULT_MAX_REVISIONS
+            })
+            .times(1)
+            .returning(|payload, _, _, _| Ok(Some(payload.clone())));
+
+        let use_case = ReplacePayloadUseCaseImpl::new(Arc::new(mock));
+        let response = use_case
+            .replace(&hash_id, Some(token.as_string()), None, replace_request("{}", Some("application/json")))
+            .await
+            .unwrap();
+        assert_eq!(response.revision, 2);
+        assert_eq!(response.mime_type, "application/json");
+    }
+
+    #[tokio::test]
+    async fn test_replace_payload_validation_error() {
+        let token = ManagementToken::generate();
+        let payload = Payload::new(b"old".to_vec(), None, None)
+            .unwrap()
+            .with_management_token(&token);
+        let hash_id = payload.hash_id().clone();
+
+        let mut mock = MockRepository::new();
+        mock.expect_get()
+            .returning(move |_| Ok(Some(payload.clone())));
+        mock.expect_replace_content().never();
+
+        let use_case = ReplacePayloadUseCaseImpl::new(Arc::new(mock));
+        let result = use_case
+            .replace(&hash_id, Some(token.as_string()), None, replace_request("", None))
+            .await;
+        assert!(matches!(result, Err(UseCaseError::ValidationError(_))));
+
+        let result = use_case
+            .replace(&hash_id, Some(token.as_string()), None, replace_request("new", Some("not a type")))
+            .await;
+        assert!(matches!(
+            result,
+            Err(UseCaseError::DomainError(PayloadError::InvalidMimeType(MimeTypeError::Malformed(_))))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_replace_payload_checks_mime_registry() {
+        let token = ManagementToken::generate();
+        let payload = Payload::new(b"old".to_vec(), None, None)
+            .unwrap()
+            .with_management_token(&token);
+        let hash_id = payload.hash_id().clone();
+
+        let mut mock = MockRepository::new();
+        mock.expect_get()
+            .returning(move |_| Ok(Some(payload.clone())));
+        mock.expect_replace_content().never();
+
+        let registry = MimeRegistry::from_config("text/plain; max_size=8, application/json").unwrap();
+        let use_case = ReplacePayloadUseCaseImpl::new(Arc::new(mock))
+            .with_mime_registry(Arc::new(registry));
+
+        // The type must be enabled, even if it was accepted by default
+        let result = use_case
+            .replace(&hash_id, Some(token.as_string()), None, replace_request("<p>", Some("text/html")))
+            .await;
+        assert!(matches!(
+            result,
+            Err(UseCaseError::DomainError(PayloadError::InvalidMimeType(
+                MimeTypeError::UnsupportedMimeType(_)
+            )))
+        ));
+
+        // The current type's size limit applies when the type is kept
+        let result = use_case
+            .replace(&hash_id, Some(token.as_string()), None, replace_request("far too long", None))
+            .await;
+        assert!(matches!(
+            result,
+            Err(UseCaseError::DomainError(PayloadError::InvalidMimeType(
+                MimeTypeError::TooLarge { max_size: 8, .. }
+            )))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_replace_payload_inspects_content() {
+        let token = ManagementToken::generate();
+        let payload = Payload::new(b"GIF89a".to_vec(), Some("image/gif".to_string()), None)
+            .unwrap()
+            .with_management_token(&token);
+        let hash_id = payload.hash_id().clone();
+
+        let mut mock = MockRepository::new();
+        mock.expect_get()
+            .returning(move |_| Ok(Some(payload.clone())));
+        mock.expect_replace_content().never();
+
+        // Content replaced without a type must match the type it keeps
+        let use_case = ReplacePayloadUseCaseImpl::new(Arc::new(mock));
+        let result = use_case
+            .replace(&hash_id, Some(token.as_string()), None, replace_request("text", None))
+            .await;
+        assert!(matches!(
+            result,
+            Err(UseCaseError::DomainError(PayloadError::InvalidMimeType(MimeTypeError::Mismatch { .. })))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_get_payload_revision() {
+        let mut payload = Payload::new(b"old".to_vec(), None, None).unwrap();
+        let previous = payload.replace_content(b"new".to_vec(), None).unwrap();
+        let hash_id = payload.hash_id().clone();
+
+        let mut mock = MockRepository::new();
+        let stored = payload.clone();
+        mock.expect_get()
+            .returning(move |_| Ok(Some(stored.clone())));
+        mock.expect_get_revision()
+            .with(eq(hash_id.clone()), eq(1))
+            .times(1)
+            .returning(move |_, _| Ok(Some(previous.clone())));
+        mock.expect_view()
+            .times(1)
+            .returning(move |_, _| Ok(Some(payload.clone())));
+
+        let use_case = GetPayloadUseCaseImpl::new(Arc::new(mock));
+        let response = use_case.execute_revision(hash_id, 1, None, None).await.unwrap();
+        assert_eq!(response.revision, 1);
+        assert_eq!(response.content, "old");
+    }
+
+    #[tokio::test]
+    async fn test_get_payload_revision_replaced_concurrently() {
+        let payload = Payload::new(b"current".to_vec(), None, None).unwrap();
+        let current = payload.current_revision();
+        let mut replaced = payload.clone();
+        replaced.replace_content(b"newer".to_vec(), None).unwrap();
+        let hash_id = payload.hash_id().clone();
+
+        let mut mock = MockRepository::new();
+        mock.expect_get()
+            .returning(move |_| Ok(Some(payload.clone())));
+        // The content was replaced before the view was recorded
+        mock.expect_view()
+            .returning(move |_, _| Ok(Some(replaced.clone())));
+        mock.expect_get_revision()
+            .with(eq(hash_id.clone()), eq(1))
+            .times(1)
+            .returning(move |_, _| Ok(Some(current.clone())));
+
+        let use_case = GetPayloadUseCaseImpl::new(Arc::new(mock));
+        let response = use_case.execute_revision(hash_id, 1, None, None).await.unwrap();
+        assert_eq!(response.revision, 1);
+        assert_eq!(response.content, "current");
+    }
+
+    #[tokio::test]
+    async fn test_get_payload_not_found() {
+        let mut mock = MockRepository::new();
+        let hash_id = HashId::new();
+
+        mock.expect_view()
+            .with(eq(hash_id.clone()), eq(false))
+            .times(1)
+            .returning(|_, _| Ok(None));
+
+        let use_case = GetPayloadUseCaseImpl::new(Arc::new(mock));
+        let result = use_case.execute(hash_id.clone(), None, None).await;
         assert!(matches!(result, Err(UseCaseError::NotFound)));
     }
 }
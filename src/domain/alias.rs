@@ -0,0 +1,123 @@
+//! Alias module provides custom, human-chosen names for payloads.
+//!
+//! An alias such as `release-notes-42` is a vanity slug chosen by the
+//! creator of a payload. Like a [`ShareCode`](super::share_code::ShareCode),
+//! it is resolved by storage to the payload's
+//! [`HashId`](super::hash_id::HashId) until the payload expires. Aliases
+//! start with a letter and share codes with a digit, so the two never
+//! collide.
+//!
+//! Aliases are meant to be memorable, which also makes them guessable.
+//! Payloads that must stay private should not rely on an alias alone.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Minimum length of an alias
+pub const MIN_LENGTH: usize = 3;
+
+/// Maximum length of an alias
+pub const MAX_LENGTH: usize = 64;
+
+/// Errors that can occur when parsing an alias.
+#[derive(Debug, Error, PartialEq)]
+pub enum AliasError {
+    /// The alias is shorter than [`MIN_LENGTH`] or longer than [`MAX_LENGTH`]
+    #[error("Alias must be between {MIN_LENGTH} and {MAX_LENGTH} characters")]
+    InvalidLength,
+
+    /// The alias does not start with a letter
+    #[error("Alias must start with a letter")]
+    InvalidStart,
+
+    /// The alias contains a character other than a-z, 0-9 and '-'
+    #[error("Alias contains invalid character {0:?}, only a-z, 0-9 and '-' are allowed")]
+    InvalidCharacter(char),
+
+    /// The alias ends with a hyphen or contains two in a row
+    #[error("Alias cannot end with '-' or contain '--'")]
+    InvalidHyphen,
+}
+
+/// A custom name resolving to a payload.
+///
+/// # Examples
+///
+/// ```
+/// use jump::domain::alias::{Alias, AliasError};
+///
+/// let alias = Alias::parse("release-notes-42").unwrap();
+/// assert_eq!(alias.as_str(), "release-notes-42");
+///
+/// assert_eq!(Alias::parse("42-release-notes"), Err(AliasError::InvalidStart));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Alias(String);
+
+impl Alias {
+    /// Parses an alias requested by a client.
+    ///
+    /// Aliases are lowercase slugs: letters, digits and single hyphens,
+    /// starting with a letter.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `AliasError` describing the first problem found.
+    pub fn parse(val: &str) -> Result<Self, AliasError> {
+        if !(MIN_LENGTH..=MAX_LENGTH).contains(&val.len()) {
+            return Err(AliasError::InvalidLength);
+        }
+        if !val.starts_with(|c: char| c.is_ascii_lowercase()) {
+            return Err(AliasError::InvalidStart);
+        }
+        if let Some(c) = val
+            .chars()
+            .find(|c| !(c.is_ascii_lowercase() || c.is_ascii_digit() || *c == '-'))
+        {
+            return Err(AliasError::InvalidCharacter(c));
+        }
+        if val.ends_with('-') || val.contains("--") {
+            return Err(AliasError::InvalidHyphen);
+        }
+
+        Ok(Alias(val.to_string()))
+    }
+
+    /// Returns the string representation of the alias.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for Alias {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_accepts_slugs() {
+        for alias in ["release-notes-42", "abc", "incident-2024-03-14", &"a".repeat(MAX_LENGTH)] {
+            assert!(Alias::parse(alias).is_ok(), "{:?}", alias);
+        }
+    }
+
+    #[test]
+    fn test_parse_rejects_malformed_aliases() {
+        assert_eq!(Alias::parse("ab"), Err(AliasError::InvalidLength));
+        assert_eq!(Alias::parse(&"a".repeat(MAX_LENGTH + 1)), Err(AliasError::InvalidLength));
+        assert_eq!(Alias::parse("7-crossover-clockwork"), Err(AliasError::InvalidStart));
+        assert_eq!(Alias::parse("-notes"), Err(AliasError::InvalidStart));
+        assert_eq!(Alias::parse("Release-Notes"), Err(AliasError::InvalidStart));
+        assert_eq!(Alias::parse("release_notes"), Err(AliasError::InvalidCharacter('_')));
+        assert_eq!(Alias::parse("release/notes"), Err(AliasError::InvalidCharacter('/')));
+        assert_eq!(Alias::parse("release-"), Err(AliasError::InvalidHyphen));
+        assert_eq!(Alias::parse("release--notes"), Err(AliasError::InvalidHyphen));
+    }
+}
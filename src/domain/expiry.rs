@@ -0,0 +1,512 @@
+//! Expiry module decides when payloads expire.
+//!
+//! Clients either ask for an absolute expiry time or for a relative one such
+//! as `"2h"` from now. An [`ExpiryPolicy`] fills in the default when they ask
+//! for neither, and keeps every expiry between a minimum and a maximum
+//! lifetime. The minimum also guarantees that storage never sees a payload
+//! that has already expired.
+//!
+//! Besides a fixed expiry, an [`ExpiryMode`] can move a payload's expiry
+//! as it is read: an idle timeout pushes it forward on every view, while an
+//! expiry after the first view starts a countdown on the first read. Either
+//! way the payload never outlives the expiry time it was created with.
+
+use std::fmt;
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Lifetime of payloads created without an expiry
+pub const DEFAULT_LIFETIME_SECS: i64 = 24 * 60 * 60;
+
+/// Shortest lifetime allowed by the default policy
+pub const DEFAULT_MIN_LIFETIME_SECS: i64 = 60;
+
+/// Longest lifetime allowed by the default policy
+pub const DEFAULT_MAX_LIFETIME_SECS: i64 = 30 * 24 * 60 * 60;
+
+/// Duration units, from largest to smallest, with their length in seconds
+const UNITS: [(char, i64); 5] = [
+    ('w', 7 * 24 * 60 * 60),
+    ('d', 24 * 60 * 60),
+    ('h', 60 * 60),
+    ('m', 60),
+    ('s', 1),
+];
+
+/// Maximum length of input accepted as a duration
+const MAX_DURATION_LENGTH: usize = 32;
+
+/// Errors that can occur when working out an expiry time.
+#[derive(Debug, Error, PartialEq)]
+pub enum ExpiryError {
+    /// The duration is not a sequence like `90s`, `2h` or `1h30m`
+    #[error("Invalid duration {0:?}, expected a number followed by s, m, h, d or w, such as \"2h\"")]
+    InvalidDuration(String),
+
+    /// Neither an absolute nor a relative expiry was given where one is
+    /// required
+    #[error("One of expiry_time and expires_in is required")]
+    Missing,
+
+    /// Both an absolute and a relative expiry were given
+    #[error("Only one of expiry_time and expires_in can be given")]
+    Conflicting,
+
+    /// Both an idle timeout and an expiry after the first view were given
+    #[error("Only one of idle_timeout and expires_after_view can be given")]
+    ConflictingModes,
+
+    /// The expiry time has already passed
+    #[error("Expiry time is in the past")]
+    InPast,
+
+    /// The expiry is sooner than the policy allows
+    #[error("Payloads must live for at least {0}")]
+    TooShort(HumanDuration),
+
+    /// The expiry is later than the policy allows
+    #[error("Payloads cannot live longer than {0}")]
+    TooLong(HumanDuration),
+
+    /// The policy itself is inconsistent
+    #[error("Invalid expiry policy: {0}")]
+    InvalidPolicy(&'static str),
+}
+
+/// Parses a human duration such as `"90s"`, `"2h"`, `"7d"` or `"1h30m"`.
+///
+/// Units are `s`, `m`, `h`, `d` and `w`, and may be combined from largest
+/// to smallest.
+///
+/// # Errors
+///
+/// Returns `ExpiryError::InvalidDuration` if the input is not a positive
+/// duration in this format.
+///
+/// # Examples
+///
+/// ```
+/// use chrono::Duration;
+/// use jump::domain::expiry::parse_duration;
+///
+/// assert_eq!(parse_duration("90s"), Ok(Duration::seconds(90)));
+/// assert_eq!(parse_duration("1h30m"), Ok(Duration::minutes(90)));
+/// assert!(parse_duration("soon").is_err());
+/// ```
+pub fn parse_duration(val: &str) -> Result<Duration, ExpiryError> {
+    let invalid = || ExpiryError::InvalidDuration(val.chars().take(MAX_DURATION_LENGTH).collect());
+    if val.is_empty() || val.len() > MAX_DURATION_LENGTH {
+        return Err(invalid());
+    }
+
+    let mut total: i64 = 0;
+    let mut rest = val;
+    let mut units = UNITS.iter();
+    while !rest.is_empty() {
+        let digits = rest.find(|c: char| !c.is_ascii_digit()).ok_or_else(invalid)?;
+        let number: i64 = rest[..digits].parse().map_err(|_| invalid())?;
+        let unit = rest[digits..].chars().next().ok_or_else(invalid)?;
+        // Each unit may appear once, after any larger units
+        let (_, secs) = units.find(|(name, _)| *name == unit).ok_or_else(invalid)?;
+        total = number
+            .checked_mul(*secs)
+            .and_then(|secs| total.checked_add(secs))
+            .ok_or_else(invalid)?;
+        rest = &rest[digits + unit.len_utf8()..];
+    }
+
+    if total == 0 {
+        return Err(invalid());
+    }
+    Duration::try_seconds(total).ok_or_else(invalid)
+}
+
+/// A duration displayed the way [`parse_duration`] reads it, such as `1h30m`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HumanDuration(pub Duration);
+
+impl fmt::Display for HumanDuration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut secs = self.0.num_seconds();
+        if secs <= 0 {
+            return f.write_str("0s");
+        }
+        for (unit, length) in UNITS {
+            if secs >= length {
+                write!(f, "{}{}", secs / length, unit)?;
+                secs %= length;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// How a payload's expiry time moves once it is created.
+///
+/// Idle and after-first-view payloads keep a deadline that views push
+/// forward; they expire at the earlier of that deadline and their expiry
+/// time.
+///
+/// # Examples
+///
+/// ```
+/// use chrono::{Duration, Utc};
+/// use jump::domain::expiry::ExpiryMode;
+///
+/// let now = Utc::now();
+/// let idle = ExpiryMode::Idle { timeout_secs: 600 };
+///
+/// // Each view keeps an idle payload alive for another ten minutes
+/// assert_eq!(idle.deadline_after_view(now, 3), Some(now + Duration::minutes(10)));
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum ExpiryMode {
+    /// The payload expires at its expiry time, however often it is read
+    #[default]
+    Fixed,
+
+    /// The payload expires after going unread for `timeout_secs`
+    Idle {
+        /// Seconds a payload may go unread
+        timeout_secs: u64,
+    },
+
+    /// The payload expires `delay_secs` after it is first read
+    AfterFirstView {
+        /// Seconds a payload lives once read
+        delay_secs: u64,
+    },
+}
+
+impl ExpiryMode {
+    /// Picks the mode for a payload from the timeouts asked for, if any.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ExpiryError::ConflictingModes` if both are given.
+    pub fn from_timeouts(
+        idle_timeout: Option<Duration>,
+        expires_after_view: Option<Duration>,
+    ) -> Result<Self, ExpiryError> {
+        let secs = |timeout: Duration| timeout.num_seconds().max(0) as u64;
+        match (idle_timeout, expires_after_view) {
+            (Some(_), Some(_)) => Err(ExpiryError::ConflictingModes),
+            (Some(timeout), None) => Ok(Self::Idle { timeout_secs: secs(timeout) }),
+            (None, Some(delay)) => Ok(Self::AfterFirstView { delay_secs: secs(delay) }),
+            (None, None) => Ok(Self::Fixed),
+        }
+    }
+
+    /// The timeout of the mode, if it has one.
+    pub fn timeout(&self) -> Option<Duration> {
+        match self {
+            Self::Fixed => None,
+            Self::Idle { timeout_secs } => Some(Duration::seconds(*timeout_secs as i64)),
+            Self::AfterFirstView { delay_secs } => Some(Duration::seconds(*delay_secs as i64)),
+        }
+    }
+
+    /// Deadline of a payload created at `created_at`, before any views.
+    pub fn initial_deadline(&self, created_at: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self {
+            Self::Idle { .. } => self.deadline_after_view(created_at, 1),
+            Self::Fixed | Self::AfterFirstView { .. } => None,
+        }
+    }
+
+    /// New deadline of a payload after its `view_count`th view at
+    /// `viewed_at`, or `None` if the view leaves the deadline alone.
+    pub fn deadline_after_view(
+        &self,
+        viewed_at: DateTime<Utc>,
+        view_count: u64,
+    ) -> Option<DateTime<Utc>> {
+        match self {
+            Self::Idle { .. } => {}
+            Self::AfterFirstView { .. } if view_count == 1 => {}
+            _ => return None,
+        }
+        Some(viewed_at + self.timeout()?)
+    }
+}
+
+/// Server-side rules for payload lifetimes.
+///
+/// # Examples
+///
+/// ```
+/// use chrono::{Duration, Utc};
+/// use jump::domain::expiry::{ExpiryError, ExpiryPolicy};
+///
+/// let policy = ExpiryPolicy::default();
+/// let now = Utc::now();
+///
+/// assert_eq!(policy.resolve(None, None, now), Ok(now + Duration::hours(24)));
+/// assert_eq!(policy.resolve(None, Some(Duration::hours(2)), now), Ok(now + Duration::hours(2)));
+/// assert_eq!(
+///     policy.resolve(Some(now - Duration::hours(1)), None, now),
+///     Err(ExpiryError::InPast)
+/// );
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExpiryPolicy {
+    default: Duration,
+    min: Duration,
+    max: Duration,
+}
+
+impl ExpiryPolicy {
+    /// Creates a policy giving payloads `default` to live unless they ask
+    /// for a lifetime between `min` and `max`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ExpiryError::InvalidPolicy` unless
+    /// `1s <= min <= default <= max`.
+    pub fn new(default: Duration, min: Duration, max: Duration) -> Result<Self, ExpiryError> {
+        if min < Duration::seconds(1) {
+            return Err(ExpiryError::InvalidPolicy("minimum lifetime must be at least 1s"));
+        }
+        if !(min <= default && default <= max) {
+            return Err(ExpiryError::InvalidPolicy(
+                "default lifetime must be between the minimum and the maximum",
+            ));
+        }
+        Ok(Self { default, min, max })
+    }
+
+    /// Lifetime of payloads created without an expiry.
+    pub fn default_lifetime(&self) -> Duration {
+        self.default
+    }
+
+    /// Shortest lifetime a payload can ask for.
+    pub fn min_lifetime(&self) -> Duration {
+        self.min
+    }
+
+    /// Longest lifetime a payload can ask for.
+    pub fn max_lifetime(&self) -> Duration {
+        self.max
+    }
+
+    /// Works out when a payload created at `now` expires.
+    ///
+    /// Clients may give an absolute `expiry_time` or a relative
+    /// `expires_in`, but not both. Without either the default lifetime
+    /// applies.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `ExpiryError` if both are given, if the expiry has already
+    /// passed, or if the lifetime is outside the policy's limits.
+    pub fn resolve(
+        &self,
+        expiry_time: Option<DateTime<Utc>>,
+        expires_in: Option<Duration>,
+        now: DateTime<Utc>,
+    ) -> Result<DateTime<Utc>, ExpiryError> {
+        if expiry_time.is_none() && expires_in.is_none() {
+            return Ok(now + self.default);
+        }
+        self.reschedule(expiry_time, expires_in, now, now)
+    }
+
+    /// Works out a new expiry for a payload created at `created_at`.
+    ///
+    /// Like [`ExpiryPolicy::resolve`], except that one of `expiry_time` and
+    /// `expires_in` is required, and `expires_in` counts from `now`. The
+    /// maximum lifetime counts from creation, so a payload cannot be kept
+    /// alive for longer than the policy allows by extending it repeatedly.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `ExpiryError` if neither or both are given, if the expiry
+    /// has already passed, or if it is outside the policy's limits.
+    pub fn reschedule(
+        &self,
+        expiry_time: Option<DateTime<Utc>>,
+        expires_in: Option<Duration>,
+        created_at: DateTime<Utc>,
+        now: DateTime<Utc>,
+    ) -> Result<DateTime<Utc>, ExpiryError> {
+        let expiry_time = match (expiry_time, expires_in) {
+            (Some(_), Some(_)) => return Err(ExpiryError::Conflicting),
+            (Some(expiry_time), None) => expiry_time,
+            (None, Some(expires_in)) => now + expires_in,
+            (None, None) => return Err(ExpiryError::Missing),
+        };
+
+        if expiry_time <= now {
+            return Err(ExpiryError::InPast);
+        }
+        if expiry_time - now < self.min {
+            return Err(ExpiryError::TooShort(HumanDuration(self.min)));
+        }
+        if expiry_time - created_at > self.max {
+            return Err(ExpiryError::TooLong(HumanDuration(self.max)));
+        }
+        Ok(expiry_time)
+    }
+
+    /// Checks the timeout of an [`ExpiryMode`] against the policy.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `ExpiryError` if the timeout is shorter than the minimum
+    /// or longer than the maximum lifetime.
+    pub fn check_mode(&self, mode: ExpiryMode) -> Result<ExpiryMode, ExpiryError> {
+        match mode.timeout() {
+            Some(timeout) if timeout < self.min => Err(ExpiryError::TooShort(HumanDuration(self.min))),
+            Some(timeout) if timeout > self.max => Err(ExpiryError::TooLong(HumanDuration(self.max))),
+            _ => Ok(mode),
+        }
+    }
+}
+
+impl Default for ExpiryPolicy {
+    fn default() -> Self {
+        Self {
+            default: Duration::seconds(DEFAULT_LIFETIME_SECS),
+            min: Duration::seconds(DEFAULT_MIN_LIFETIME_SECS),
+            max: Duration::seconds(DEFAULT_MAX_LIFETIME_SECS),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("90s"), Ok(Duration::seconds(90)));
+        assert_eq!(parse_duration("15m"), Ok(Duration::minutes(15)));
+        assert_eq!(parse_duration("2h"), Ok(Duration::hours(2)));
+        assert_eq!(parse_duration("7d"), Ok(Duration::days(7)));
+        assert_eq!(parse_duration("2w"), Ok(Duration::weeks(2)));
+        assert_eq!(parse_duration("1d12h"), Ok(Duration::hours(36)));
+        assert_eq!(parse_duration("1h0m30s"), Ok(Duration::seconds(3630)));
+    }
+
+    #[test]
+    fn test_parse_duration_rejects_malformed_input() {
+        for val in [
+            "", "2", "h", "2x", "-2h", "2 h", "2H", "1.5h", "0s", "0h0m", "30m1h", "1h1h",
+            "99999999999999999999s", "9999999999999w", "2h ",
+        ] {
+            assert!(
+                matches!(parse_duration(val), Err(ExpiryError::InvalidDuration(_))),
+                "{:?}",
+                val
+            );
+        }
+    }
+
+    #[test]
+    fn test_human_duration_round_trips() {
+        for val in ["90s", "2h", "7d", "1h30m", "1w2d3h4m5s"] {
+            let duration = parse_duration(val).unwrap();
+            assert_eq!(parse_duration(&HumanDuration(duration).to_string()), Ok(duration));
+        }
+        assert_eq!(HumanDuration(Duration::seconds(90)).to_string(), "1m30s");
+    }
+
+    #[test]
+    fn test_policy_enforces_limits() {
+        let policy = ExpiryPolicy::new(Duration::hours(1), Duration::minutes(5), Duration::days(1)).unwrap();
+        let now = Utc::now();
+
+        assert_eq!(policy.resolve(None, None, now), Ok(now + Duration::hours(1)));
+        assert_eq!(
+            policy.resolve(Some(now + Duration::hours(2)), None, now),
+            Ok(now + Duration::hours(2))
+        );
+        assert_eq!(policy.resolve(None, Some(Duration::days(1)), now), Ok(now + Duration::days(1)));
+        assert_eq!(
+            policy.resolve(None, Some(Duration::minutes(1)), now),
+            Err(ExpiryError::TooShort(HumanDuration(Duration::minutes(5))))
+        );
+        assert_eq!(
+            policy.resolve(Some(now + Duration::days(2)), None, now),
+            Err(ExpiryError::TooLong(HumanDuration(Duration::days(1))))
+        );
+        assert_eq!(policy.resolve(Some(now), None, now), Err(ExpiryError::InPast));
+        assert_eq!(
+            policy.resolve(Some(now + Duration::hours(2)), Some(Duration::hours(2)), now),
+            Err(ExpiryError::Conflicting)
+        );
+    }
+
+    #[test]
+    fn test_reschedule_counts_maximum_from_creation() {
+        let policy = ExpiryPolicy::new(Duration::hours(1), Duration::minutes(5), Duration::days(1)).unwrap();
+        let now = Utc::now();
+        let created_at = now - Duration::hours(20);
+
+        assert_eq!(
+            policy.reschedule(None, Some(Duration::hours(3)), created_at, now),
+            Ok(now + Duration::hours(3))
+        );
+        assert_eq!(
+            policy.reschedule(None, Some(Duration::hours(5)), created_at, now),
+            Err(ExpiryError::TooLong(HumanDuration(Duration::days(1))))
+        );
+        assert_eq!(
+            policy.reschedule(Some(now + Duration::minutes(1)), None, created_at, now),
+            Err(ExpiryError::TooShort(HumanDuration(Duration::minutes(5))))
+        );
+        assert_eq!(
+            policy.reschedule(Some(now - Duration::minutes(1)), None, created_at, now),
+            Err(ExpiryError::InPast)
+        );
+        assert_eq!(policy.reschedule(None, None, created_at, now), Err(ExpiryError::Missing));
+    }
+
+    #[test]
+    fn test_expiry_modes() {
+        let now = Utc::now();
+
+        let fixed = ExpiryMode::Fixed;
+        assert_eq!(fixed.initial_deadline(now), None);
+        assert_eq!(fixed.deadline_after_view(now, 1), None);
+
+        let idle = ExpiryMode::Idle { timeout_secs: 600 };
+        assert_eq!(idle.initial_deadline(now), Some(now + Duration::minutes(10)));
+        assert_eq!(idle.deadline_after_view(now, 5), Some(now + Duration::minutes(10)));
+
+        let after_view = ExpiryMode::AfterFirstView { delay_secs: 300 };
+        assert_eq!(after_view.initial_deadline(now), None);
+        assert_eq!(after_view.deadline_after_view(now, 1), Some(now + Duration::minutes(5)));
+        assert_eq!(after_view.deadline_after_view(now, 2), None);
+    }
+
+    #[test]
+    fn test_expiry_mode_from_timeouts() {
+        let minute = Some(Duration::minutes(1));
+        assert_eq!(ExpiryMode::from_timeouts(None, None), Ok(ExpiryMode::Fixed));
+        assert_eq!(ExpiryMode::from_timeouts(minute, None), Ok(ExpiryMode::Idle { timeout_secs: 60 }));
+        assert_eq!(
+            ExpiryMode::from_timeouts(None, minute),
+            Ok(ExpiryMode::AfterFirstView { delay_secs: 60 })
+        );
+        assert_eq!(ExpiryMode::from_timeouts(minute, minute), Err(ExpiryError::ConflictingModes));
+
+        let policy = ExpiryPolicy::default();
+        assert!(policy.check_mode(ExpiryMode::Idle { timeout_secs: 60 }).is_ok());
+        assert!(policy.check_mode(ExpiryMode::Idle { timeout_secs: 59 }).is_err());
+        assert!(policy.check_mode(ExpiryMode::AfterFirstView { delay_secs: 31 * 86400 }).is_err());
+    }
+
+    #[test]
+    fn test_policy_rejects_inconsistent_limits() {
+        let hour = Duration::hours(1);
+        assert!(ExpiryPolicy::new(hour, hour, hour).is_ok());
+        assert!(ExpiryPolicy::new(hour, Duration::zero(), hour).is_err());
+        assert!(ExpiryPolicy::new(hour, hour * 2, hour * 3).is_err());
+        assert!(ExpiryPolicy::new(hour * 4, hour, hour * 3).is_err());
+    }
+}
@@ -1,14 +1,82 @@
-//! HashId module provides a type-safe wrapper around UUID-based identifiers for payloads.
+//! HashId module provides a type-safe wrapper around identifiers for payloads.
 //! 
 //! This module ensures that:
 //! - All HashIds are valid and properly formatted
 //! - HashIds are URL-safe
 //! - HashIds are unique with extremely high probability
 //! - HashIds can be serialized/deserialized safely
+//!
+//! New IDs come from a pluggable [`HashIdGenerator`]. The default generator
+//! produces short base62 strings; [`UuidGenerator`] produces the 32-character
+//! hex IDs used before, which remain valid.
 
+use std::collections::HashSet;
+
+use rand::{distributions::Uniform, rngs::OsRng, Rng};
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
 use uuid::Uuid;
 
+/// Characters of the default base62 alphabet
+pub const BASE62_ALPHABET: &str = "0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+/// Default length of generated IDs (about 71 bits of entropy in base62)
+pub const DEFAULT_LENGTH: usize = 12;
+
+/// Maximum length of a HashId
+pub const MAX_LENGTH: usize = 64;
+
+/// Minimum entropy of generated IDs in bits.
+///
+/// IDs double as capabilities for reading payloads, so they must be too
+/// many to guess even at high request rates.
+pub const MIN_ENTROPY_BITS: f64 = 64.0;
+
+/// Errors that can occur when parsing a HashId.
+#[derive(Debug, Error, PartialEq)]
+pub enum HashIdError {
+    /// The ID is empty
+    #[error("Hash ID cannot be empty")]
+    Empty,
+
+    /// The ID is longer than any issued ID
+    #[error("Hash ID cannot be longer than {MAX_LENGTH} characters")]
+    TooLong,
+
+    /// The ID contains a character no generator produces
+    #[error("Hash ID contains invalid character {0:?}")]
+    InvalidCharacter(char),
+}
+
+/// Errors in the configuration of a HashId generator.
+#[derive(Debug, Error, PartialEq)]
+pub enum HashIdGeneratorError {
+    /// The alphabet has fewer than two characters
+    #[error("Alphabet must contain at least 2 characters")]
+    AlphabetTooSmall,
+
+    /// The alphabet contains a character more than once
+    #[error("Alphabet contains {0:?} more than once")]
+    DuplicateCharacter(char),
+
+    /// The alphabet contains a character that is not URL-safe
+    #[error("Alphabet character {0:?} is not one of A-Z, a-z, 0-9, '-' or '_'")]
+    UnsafeCharacter(char),
+
+    /// The IDs would be too long to parse
+    #[error("IDs cannot be longer than {MAX_LENGTH} characters")]
+    TooLong,
+
+    /// The generated IDs would be too easy to guess
+    #[error("IDs of this length and alphabet carry {bits:.1} bits of entropy, below the minimum of {min}")]
+    InsufficientEntropy {
+        /// Entropy of the configured IDs in bits
+        bits: f64,
+        /// Required entropy in bits
+        min: f64,
+    },
+}
+
 /// A unique identifier for payloads in the system.
 /// 
 /// HashId is implemented as a wrapper around a UUID v4, stored in a URL-safe format.
@@ -49,10 +117,45 @@ impl HashId {
         HashId(Uuid::new_v4().simple().to_string())
     }
 
-    /// Creates a HashId from an existing string.
+    /// Parses a HashId received from a client.
+    ///
+    /// Accepts IDs from every generator: non-empty strings of at most
+    /// [`MAX_LENGTH`] ASCII letters, digits, `-` and `_`. This includes the
+    /// 32-character hex IDs issued by [`HashId::new`].
+    ///
+    /// # Errors
+    ///
+    /// Returns a `HashIdError` describing the first problem found.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jump::domain::hash_id::{HashId, HashIdError};
+    ///
+    /// assert!(HashId::parse("Xk3f9Qa2LmZ7").is_ok());
+    /// assert!(HashId::parse("feb61cd1b3d34efebab6d6a8490071b2").is_ok());
+    /// assert_eq!(HashId::parse("a:b"), Err(HashIdError::InvalidCharacter(':')));
+    /// ```
+    pub fn parse(val: &str) -> Result<Self, HashIdError> {
+        if val.is_empty() {
+            return Err(HashIdError::Empty);
+        }
+        if val.len() > MAX_LENGTH {
+            return Err(HashIdError::TooLong);
+        }
+        if let Some(c) = val
+            .chars()
+            .find(|c| !(c.is_ascii_alphanumeric() || *c == '-' || *c == '_'))
+        {
+            return Err(HashIdError::InvalidCharacter(c));
+        }
+        Ok(HashId(val.to_string()))
+    }
+
+    /// Creates a HashId from an existing string without validating it.
     /// 
-    /// This is particularly useful when receiving IDs from API requests
-    /// or loading from storage.
+    /// Only use this for trusted values, such as IDs loaded from storage;
+    /// input from clients must go through [`HashId::parse`].
     /// 
     /// # Arguments
     /// 
@@ -97,6 +200,115 @@ impl Default for HashId {
     }
 }
 
+/// A source of new HashIds.
+///
+/// Generators only need to make collisions unlikely; callers still check
+/// each new ID against the repository before using it.
+pub trait HashIdGenerator: Send + Sync {
+    /// Generates a new random HashId.
+    fn generate(&self) -> HashId;
+}
+
+/// Generates 32-character hex IDs from UUID v4, as [`HashId::new`] does.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UuidGenerator;
+
+impl HashIdGenerator for UuidGenerator {
+    fn generate(&self) -> HashId {
+        HashId::new()
+    }
+}
+
+/// Generates fixed-length IDs from a configurable alphabet.
+///
+/// # Examples
+///
+/// ```
+/// use jump::domain::hash_id::{AlphabetGenerator, HashIdGenerator};
+///
+/// // Base62 IDs of the default length
+/// let generator = AlphabetGenerator::default();
+/// assert_eq!(generator.generate().as_string().len(), 12);
+///
+/// // Lowercase-only IDs, which need more characters for the same entropy
+/// let generator = AlphabetGenerator::new("abcdefghijklmnopqrstuvwxyz", 14).unwrap();
+/// assert!(generator.generate().as_string().chars().all(|c| c.is_ascii_lowercase()));
+///
+/// // Too short to be safe
+/// assert!(AlphabetGenerator::new("abcdefghijklmnopqrstuvwxyz", 6).is_err());
+/// ```
+#[derive(Debug, Clone)]
+pub struct AlphabetGenerator {
+    alphabet: Vec<char>,
+    length: usize,
+}
+
+impl AlphabetGenerator {
+    /// Creates a generator for IDs of `length` characters drawn from
+    /// `alphabet`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the alphabet has fewer than two characters,
+    /// repeats a character or contains a character other than ASCII letters,
+    /// digits, `-` and `_`, or if the resulting IDs would have less than
+    /// [`MIN_ENTROPY_BITS`] of entropy.
+    pub fn new(alphabet: &str, length: usize) -> Result<Self, HashIdGeneratorError> {
+        let mut seen = HashSet::new();
+        for c in alphabet.chars() {
+            if !(c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+                return Err(HashIdGeneratorError::UnsafeCharacter(c));
+            }
+            if !seen.insert(c) {
+                return Err(HashIdGeneratorError::DuplicateCharacter(c));
+            }
+        }
+        if seen.len() < 2 {
+            return Err(HashIdGeneratorError::AlphabetTooSmall);
+        }
+        if length > MAX_LENGTH {
+            return Err(HashIdGeneratorError::TooLong);
+        }
+
+        let generator = Self {
+            alphabet: alphabet.chars().collect(),
+            length,
+        };
+        let bits = generator.entropy_bits();
+        if bits < MIN_ENTROPY_BITS {
+            return Err(HashIdGeneratorError::InsufficientEntropy {
+                bits,
+                min: MIN_ENTROPY_BITS,
+            });
+        }
+        Ok(generator)
+    }
+
+    /// Returns the entropy of each generated ID in bits.
+    pub fn entropy_bits(&self) -> f64 {
+        self.length as f64 * (self.alphabet.len() as f64).log2()
+    }
+}
+
+impl Default for AlphabetGenerator {
+    /// Creates a base62 generator for IDs of [`DEFAULT_LENGTH`] characters.
+    fn default() -> Self {
+        Self::new(BASE62_ALPHABET, DEFAULT_LENGTH).expect("default ID configuration is valid")
+    }
+}
+
+impl HashIdGenerator for AlphabetGenerator {
+    fn generate(&self) -> HashId {
+        let index = Uniform::from(0..self.alphabet.len());
+        let id = OsRng
+            .sample_iter(index)
+            .take(self.length)
+            .map(|i| self.alphabet[i])
+            .collect();
+        HashId::from_string(id)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -114,6 +326,27 @@ mod tests {
         assert_eq!(id.as_string(), original);
     }
 
+    #[test]
+    fn test_parse_accepts_generated_ids() {
+        let legacy = HashId::new();
+        assert_eq!(HashId::parse(legacy.as_string()), Ok(legacy));
+
+        let short = AlphabetGenerator::default().generate();
+        assert_eq!(HashId::parse(short.as_string()), Ok(short));
+
+        assert!(HashId::parse("release-notes_42").is_ok());
+    }
+
+    #[test]
+    fn test_parse_rejects_malformed_ids() {
+        assert_eq!(HashId::parse(""), Err(HashIdError::Empty));
+        assert_eq!(HashId::parse(&"a".repeat(MAX_LENGTH + 1)), Err(HashIdError::TooLong));
+        assert_eq!(HashId::parse("../etc/passwd"), Err(HashIdError::InvalidCharacter('.')));
+        assert_eq!(HashId::parse("abc def"), Err(HashIdError::InvalidCharacter(' ')));
+        assert_eq!(HashId::parse("payload:*"), Err(HashIdError::InvalidCharacter(':')));
+        assert_eq!(HashId::parse("caf\u{e9}"), Err(HashIdError::InvalidCharacter('\u{e9}')));
+    }
+
     #[test]
     fn test_default_creates_valid_id() {
         let id: HashId = Default::default();
@@ -126,4 +359,48 @@ mod tests {
         let id2 = HashId::new();
         assert_ne!(id1, id2);
     }
+
+    #[test]
+    fn test_alphabet_generator_uses_alphabet_and_length() {
+        let generator = AlphabetGenerator::new("01", 64).unwrap();
+        let id = generator.generate();
+        assert_eq!(id.as_string().len(), 64);
+        assert!(id.as_string().chars().all(|c| c == '0' || c == '1'));
+    }
+
+    #[test]
+    fn test_default_generator_is_short_and_unique() {
+        let generator = AlphabetGenerator::default();
+        let ids: HashSet<HashId> = (0..1000).map(|_| generator.generate()).collect();
+        assert_eq!(ids.len(), 1000);
+        assert!(ids.iter().all(|id| id.as_string().len() == DEFAULT_LENGTH));
+        assert!(ids
+            .iter()
+            .all(|id| id.as_string().chars().all(|c| BASE62_ALPHABET.contains(c))));
+    }
+
+    #[test]
+    fn test_generator_config_is_validated() {
+        assert_eq!(
+            AlphabetGenerator::new("a", 100).unwrap_err(),
+            HashIdGeneratorError::AlphabetTooSmall
+        );
+        assert_eq!(
+            AlphabetGenerator::new("abca", 100).unwrap_err(),
+            HashIdGeneratorError::DuplicateCharacter('a')
+        );
+        assert_eq!(
+            AlphabetGenerator::new("ab/", 100).unwrap_err(),
+            HashIdGeneratorError::UnsafeCharacter('/')
+        );
+        assert!(matches!(
+            AlphabetGenerator::new(BASE62_ALPHABET, 10),
+            Err(HashIdGeneratorError::InsufficientEntropy { .. })
+        ));
+        assert_eq!(
+            AlphabetGenerator::new("01", MAX_LENGTH + 1).unwrap_err(),
+            HashIdGeneratorError::TooLong
+        );
+        assert!(AlphabetGenerator::new(BASE62_ALPHABET, 11).is_ok());
+    }
 }
@@ -0,0 +1,207 @@
+//! Inspection module checks payload content against its MIME type.
+//!
+//! Clients declare the MIME type of the content they send, and nothing
+//! stops them from labelling arbitrary bytes `image/png`. This module
+//! verifies declared types where the content can tell:
+//! - Images and PDF documents must start with their magic numbers
+//! - JSON must be well-formed
+//! - Text must be valid UTF-8, unless its charset says otherwise
+//!
+//! It can also detect the MIME type of content whose type was not declared.
+
+use serde::de::IgnoredAny;
+
+use super::mime_type::{MimeType, MimeTypeError};
+
+/// Leading bytes identifying content of a MIME type.
+const SIGNATURES: &[(&str, &[&[u8]])] = &[
+    ("image/png", &[b"\x89PNG\r\n\x1a\n"]),
+    ("image/jpeg", &[b"\xff\xd8\xff"]),
+    ("image/gif", &[b"GIF87a", b"GIF89a"]),
+    ("application/pdf", &[b"%PDF-"]),
+];
+
+/// Returns true if `content` starts with one of `prefixes`, ignoring case
+/// and leading whitespace.
+fn starts_with_markup(content: &str, prefixes: &[&str]) -> bool {
+    let start = content.trim_start();
+    prefixes.iter().any(|prefix| {
+        start
+            .get(..prefix.len())
+            .is_some_and(|head| head.eq_ignore_ascii_case(prefix))
+    })
+}
+
+/// Returns true if `content` is a well-formed JSON document.
+fn is_json(content: &[u8]) -> bool {
+    serde_json::from_slice::<IgnoredAny>(content).is_ok()
+}
+
+/// Checks that `content` matches its declared MIME type.
+///
+/// Types the content cannot tell anything about are accepted as they are.
+///
+/// # Errors
+///
+/// Returns `MimeTypeError::Mismatch` if an image or PDF document lacks its
+/// magic number, JSON is malformed, or text is not valid UTF-8.
+///
+/// # Examples
+///
+/// ```
+/// use jump::domain::{inspection::verify, mime_type::MimeType};
+///
+/// let png = MimeType::try_from("image/png").unwrap();
+/// assert!(verify(&png, b"\x89PNG\r\n\x1a\n....").is_ok());
+/// assert!(verify(&png, b"not an image").is_err());
+///
+/// let json = MimeType::try_from("application/json").unwrap();
+/// assert!(verify(&json, br#"{"valid": true}"#).is_ok());
+/// assert!(verify(&json, b"{oops").is_err());
+/// ```
+pub fn verify(mime_type: &MimeType, content: &[u8]) -> Result<(), MimeTypeError> {
+    let mismatch = |reason: &'static str| MimeTypeError::Mismatch {
+        mime_type: mime_type.essence().to_string(),
+        reason,
+    };
+
+    if let Some((_, signatures)) = SIGNATURES
+        .iter()
+        .find(|(essence, _)| *essence == mime_type.essence())
+    {
+        if !signatures
+            .iter()
+            .any(|signature| content.starts_with(signature))
+        {
+            return Err(mismatch("content does not start with its magic number"));
+        }
+        return Ok(());
+    }
+
+    let subtype = mime_type
+        .essence()
+        .split_once('/')
+        .map_or("", |(_, subtype)| subtype);
+    if (subtype == "json" || subtype.ends_with("+json")) && !is_json(content) {
+        return Err(mismatch("content is not well-formed JSON"));
+    }
+    if mime_type.essence().starts_with("text/")
+        && mime_type.is_text()
+        && std::str::from_utf8(content).is_err()
+    {
+        return Err(mismatch("content is not valid UTF-8"));
+    }
+    Ok(())
+}
+
+/// Works out the MIME type of content from what it looks like.
+///
+/// Images and PDF documents are recognized by their magic numbers. Other
+/// content that is valid UTF-8 is JSON if it is a well-formed object or
+/// array, HTML or SVG if it starts like either, and plain text otherwise.
+/// Anything else is `application/octet-stream`.
+///
+/// # Examples
+///
+/// ```
+/// use jump::domain::inspection::detect;
+///
+/// assert_eq!(detect(b"GIF89a...").essence(), "image/gif");
+/// assert_eq!(detect(br#"[1, 2, 3]"#).essence(), "application/json");
+/// assert_eq!(detect(b"<!DOCTYPE html><p>Hi</p>").essence(), "text/html");
+/// assert_eq!(detect(b"Hello").essence(), "text/plain");
+/// assert_eq!(detect(&[0x00, 0xff]).essence(), "application/octet-stream");
+/// ```
+pub fn detect(content: &[u8]) -> MimeType {
+    let essence = SIGNATURES
+        .iter()
+        .find(|(_, signatures)| {
+            signatures
+                .iter()
+                .any(|signature| content.starts_with(signature))
+        })
+        .map(|(essence, _)| *essence)
+        .unwrap_or_else(|| match std::str::from_utf8(content) {
+            Ok(text) if starts_with_markup(text, &["{", "["]) && is_json(content) => {
+                "application/json"
+            }
+            Ok(text) if starts_with_markup(text, &["<!doctype html", "<html"]) => "text/html",
+            Ok(text) if starts_with_markup(text, &["<svg", "<?xml"]) && text.contains("<svg") => {
+                "image/svg+xml"
+            }
+            Ok(_) => "text/plain",
+            Err(_) => "application/octet-stream",
+        });
+    essence
+        .parse()
+        .expect("detected MIME types are well-formed")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mime(s: &str) -> MimeType {
+        MimeType::try_from(s).unwrap()
+    }
+
+    #[test]
+    fn test_verify_magic_numbers() {
+        assert!(verify(&mime("image/png"), b"\x89PNG\r\n\x1a\nrest").is_ok());
+        assert!(verify(&mime("image/jpeg"), b"\xff\xd8\xff\xe0rest").is_ok());
+        assert!(verify(&mime("image/gif"), b"GIF87arest").is_ok());
+        assert!(verify(&mime("application/pdf"), b"%PDF-1.7").is_ok());
+
+        assert!(verify(&mime("image/png"), b"GIF89a").is_err());
+        assert!(verify(&mime("image/jpeg"), b"").is_err());
+        assert!(verify(&mime("application/pdf"), b"PDF").is_err());
+    }
+
+    #[test]
+    fn test_verify_json() {
+        assert!(verify(&mime("application/json"), b"42").is_ok());
+        assert!(verify(&mime("application/ld+json"), br#"{"@id": "x"}"#).is_ok());
+        assert!(verify(&mime("application/json"), b"{\"a\": }").is_err());
+        assert!(verify(&mime("application/json"), b"").is_err());
+    }
+
+    #[test]
+    fn test_verify_text() {
+        assert!(verify(&mime("text/plain"), "héllo".as_bytes()).is_ok());
+        assert!(verify(&mime("text/csv; charset=utf-8"), b"a,b").is_ok());
+        assert!(verify(&mime("text/plain"), &[0xff, 0xfe]).is_err());
+        // The charset says the content is not UTF-8
+        assert!(verify(&mime("text/plain; charset=iso-8859-1"), &[0xe9]).is_ok());
+    }
+
+    #[test]
+    fn test_verify_accepts_types_it_cannot_check() {
+        assert!(verify(&mime("application/octet-stream"), &[0x00, 0xff]).is_ok());
+        assert!(verify(&mime("application/x-custom"), b"anything").is_ok());
+    }
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(detect(b"\x89PNG\r\n\x1a\n").essence(), "image/png");
+        assert_eq!(detect(b"\xff\xd8\xff\xdb").essence(), "image/jpeg");
+        assert_eq!(detect(b"%PDF-1.4").essence(), "application/pdf");
+        assert_eq!(detect(b"  {\"a\": 1}").essence(), "application/json");
+        assert_eq!(detect(b"<HTML><body></body></HTML>").essence(), "text/html");
+        assert_eq!(
+            detect(b"<?xml version=\"1.0\"?><svg></svg>").essence(),
+            "image/svg+xml"
+        );
+        assert_eq!(
+            detect(b"<?xml version=\"1.0\"?><feed/>").essence(),
+            "text/plain"
+        );
+    }
+
+    #[test]
+    fn test_detect_only_takes_documents_for_json() {
+        // Bare JSON values are more likely text that happens to parse
+        assert_eq!(detect(b"42").essence(), "text/plain");
+        assert_eq!(detect(b"true").essence(), "text/plain");
+        assert_eq!(detect(b"{not json").essence(), "text/plain");
+    }
+}
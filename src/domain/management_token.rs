@@ -0,0 +1,121 @@
+//! ManagementToken module provides the secret that authorizes changes to a payload.
+//!
+//! A token is generated when a payload is created and returned only to its
+//! creator. The payload keeps just a SHA-256 hash of the token, which is
+//! enough because tokens are long and random rather than chosen by people.
+//! Whoever holds the token can manage the payload; whoever only holds the
+//! link can merely read it.
+
+use std::fmt;
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use rand::{rngs::OsRng, RngCore};
+use sha2::{Digest, Sha256};
+use subtle::ConstantTimeEq;
+
+/// Number of random bytes in a generated token
+const TOKEN_BYTES: usize = 32;
+
+/// A secret token authorizing the management of a payload.
+///
+/// The `Debug` output never includes the token itself, so it cannot leak
+/// through logs.
+///
+/// # Examples
+///
+/// ```
+/// use jump::domain::management_token::ManagementToken;
+///
+/// let token = ManagementToken::generate();
+/// let presented = ManagementToken::from_string(token.as_string().to_string());
+/// assert_eq!(presented.hash(), token.hash());
+/// ```
+#[derive(Clone, PartialEq, Eq)]
+pub struct ManagementToken(String);
+
+impl ManagementToken {
+    /// Generates a new random token.
+    ///
+    /// Tokens hold 256 bits from the operating system's secure random number
+    /// generator, encoded as URL-safe base64.
+    pub fn generate() -> Self {
+        let mut bytes = [0u8; TOKEN_BYTES];
+        OsRng.fill_bytes(&mut bytes);
+        ManagementToken(URL_SAFE_NO_PAD.encode(bytes))
+    }
+
+    /// Creates a token from a string presented by a client.
+    pub fn from_string(val: String) -> Self {
+        ManagementToken(val)
+    }
+
+    /// Returns the string representation of the token.
+    pub fn as_string(&self) -> &str {
+        &self.0
+    }
+
+    /// Returns the hex-encoded SHA-256 hash of the token, as stored with
+    /// the payload.
+    pub fn hash(&self) -> String {
+        Sha256::digest(self.0.as_bytes())
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+
+    /// Returns true if `hash` is the hash of this token.
+    ///
+    /// Compares in constant time, so response timings reveal nothing about
+    /// how much of a presented token's hash matches.
+    pub fn matches_hash(&self, hash: &str) -> bool {
+        self.hash().as_bytes().ct_eq(hash.as_bytes()).into()
+    }
+}
+
+impl fmt::Debug for ManagementToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ManagementToken(..)")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generated_tokens_are_unique_and_url_safe() {
+        let token1 = ManagementToken::generate();
+        let token2 = ManagementToken::generate();
+        assert_ne!(token1, token2);
+        assert_eq!(token1.as_string().len(), 43);
+        assert!(token1
+            .as_string()
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'));
+    }
+
+    #[test]
+    fn test_hash_is_stable_and_hides_token() {
+        let token = ManagementToken::from_string("token".to_string());
+        assert_eq!(
+            token.hash(),
+            "3c469e9d6c5875d37a43f353d4f88e61fcf812c66eee3457465a40b0da4153e0"
+        );
+        assert_ne!(token.hash(), ManagementToken::generate().hash());
+    }
+
+    #[test]
+    fn test_matches_hash() {
+        let token = ManagementToken::generate();
+        assert!(token.matches_hash(&token.hash()));
+        assert!(!token.matches_hash(&ManagementToken::generate().hash()));
+        assert!(!token.matches_hash(&token.hash()[..32]));
+        assert!(!token.matches_hash(""));
+    }
+
+    #[test]
+    fn test_debug_does_not_reveal_token() {
+        let token = ManagementToken::from_string("secret".to_string());
+        assert!(!format!("{:?}", token).contains("secret"));
+    }
+}
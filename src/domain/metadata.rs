@@ -0,0 +1,138 @@
+//! Metadata module describes what a payload is for.
+//!
+//! Creators may label a payload with a filename, a title, a description and
+//! free-form tags, so that people and tooling receiving a link can tell what
+//! it is without reading the content. The filename is also suggested to
+//! browsers downloading the raw content, so it must be a plain name rather
+//! than a path.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Errors that can occur when describing a payload.
+#[derive(Debug, Error, PartialEq)]
+pub enum MetadataError {
+    /// The filename is empty, a path, or contains control characters
+    #[error("Invalid filename {0:?}: must be a plain name without path separators or control characters")]
+    InvalidFilename(String),
+}
+
+/// Optional description of a payload.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::BTreeMap;
+/// use jump::domain::metadata::Metadata;
+///
+/// let metadata = Metadata::default()
+///     .with_filename("incident-4711.log")
+///     .unwrap()
+///     .with_title(Some("Checkout outage logs".to_string()))
+///     .with_tags(BTreeMap::from([("team".to_string(), "payments".to_string())]));
+/// assert_eq!(metadata.filename(), Some("incident-4711.log"));
+/// assert_eq!(metadata.tags()["team"], "payments");
+///
+/// assert!(Metadata::default().with_filename("../etc/passwd").is_err());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Metadata {
+    /// Filename suggested when the content is downloaded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    filename: Option<String>,
+
+    /// Short human-readable title
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+
+    /// Longer human-readable description
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+
+    /// Free-form labels, by name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    tags: BTreeMap<String, String>,
+}
+
+impl Metadata {
+    /// Sets the filename suggested when the content is downloaded.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MetadataError::InvalidFilename` if the filename is empty,
+    /// `.` or `..`, or contains a path separator or control character.
+    pub fn with_filename(mut self, filename: &str) -> Result<Self, MetadataError> {
+        let is_plain = !matches!(filename, "" | "." | "..")
+            && !filename.chars().any(|c| c == '/' || c == '\\' || c.is_control());
+        if !is_plain {
+            return Err(MetadataError::InvalidFilename(filename.to_string()));
+        }
+        self.filename = Some(filename.to_string());
+        Ok(self)
+    }
+
+    /// Sets the title.
+    pub fn with_title(mut self, title: Option<String>) -> Self {
+        self.title = title;
+        self
+    }
+
+    /// Sets the description.
+    pub fn with_description(mut self, description: Option<String>) -> Self {
+        self.description = description;
+        self
+    }
+
+    /// Sets the tags, replacing any given before.
+    pub fn with_tags(mut self, tags: BTreeMap<String, String>) -> Self {
+        self.tags = tags;
+        self
+    }
+
+    /// Returns the filename suggested for downloads, if any.
+    pub fn filename(&self) -> Option<&str> {
+        self.filename.as_deref()
+    }
+
+    /// Returns the title, if any.
+    pub fn title(&self) -> Option<&str> {
+        self.title.as_deref()
+    }
+
+    /// Returns the description, if any.
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    /// Returns the tags, ordered by name.
+    pub fn tags(&self) -> &BTreeMap<String, String> {
+        &self.tags
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filenames_must_be_plain_names() {
+        for filename in ["report.pdf", "notes", ".env", "Résumé 2024.txt", "a\"b.txt"] {
+            assert!(Metadata::default().with_filename(filename).is_ok(), "{}", filename);
+        }
+        for filename in ["", ".", "..", "dir/file", "C:\\file", "line\nbreak", "nul\0"] {
+            assert_eq!(
+                Metadata::default().with_filename(filename),
+                Err(MetadataError::InvalidFilename(filename.to_string()))
+            );
+        }
+    }
+
+    #[test]
+    fn test_empty_metadata_serializes_to_nothing() {
+        assert_eq!(serde_json::to_string(&Metadata::default()).unwrap(), "{}");
+        let metadata: Metadata = serde_json::from_str("{}").unwrap();
+        assert_eq!(metadata, Metadata::default());
+    }
+}
@@ -1,146 +1,603 @@
-//! MimeType module provides a type-safe way to handle MIME types for payloads.
+//! MimeType module models the MIME types of payloads and which of them the
+//! server accepts.
 //!
 //! This module ensures that:
-//! - Only supported MIME types are allowed
-//! - MIME types are properly formatted
-//! - MIME types are validated before use
+//! - MIME types are properly formatted, with parameters such as `charset`
+//!   normalized
+//! - Only MIME types enabled in a [`MimeRegistry`] are accepted
+//! - Each enabled type follows its own [`MimePolicy`]: a maximum content
+//!   size, whether it may be served inline, and its file extension
+//! - Content matches its declared type, as far as
+//!   [`inspection`](super::inspection) can tell, or the type is corrected
+//!   according to the registry's [`MismatchPolicy`]
 
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fmt;
+use std::str::FromStr;
 use thiserror::Error;
 
+use super::inspection;
+
 /// Errors that can occur when working with MIME types.
 #[derive(Debug, Error)]
 pub enum MimeTypeError {
-    /// The MIME type is not supported by the system.
+    /// The MIME type is not of the form `type/subtype; name=value`.
+    #[error("Malformed MIME type: {0}")]
+    Malformed(String),
+
+    /// The MIME type is not enabled in the registry.
     #[error("Unsupported MIME type: {0}")]
     UnsupportedMimeType(String),
+
+    /// The content is larger than its MIME type allows.
+    #[error("Content of type {mime_type} cannot exceed {max_size} bytes")]
+    TooLarge {
+        /// The MIME type of the content
+        mime_type: String,
+        /// The largest content allowed for the type, in bytes
+        max_size: usize,
+    },
+
+    /// The content does not match its declared MIME type.
+    #[error("Content does not match MIME type {mime_type}: {reason}")]
+    Mismatch {
+        /// The declared MIME type of the content
+        mime_type: String,
+        /// Why the content does not match
+        reason: &'static str,
+    },
+
+    /// The registry configuration is malformed.
+    #[error("Invalid MIME type configuration: {0}")]
+    InvalidConfig(String),
 }
 
-/// Represents a supported MIME type for payloads.
-/// 
-/// This enum ensures that only supported MIME types can be used in the system.
-/// The supported types are based on the requirements specified in the PRD.
-/// 
+/// MIME type clients give to have the type detected from the content.
+pub const AUTO: &str = "auto";
+
+/// Charsets whose content is also valid UTF-8.
+const UTF8_CHARSETS: &[&str] = &["utf-8", "us-ascii"];
+
+/// Returns true if `s` is an RFC 2045 token.
+fn is_token(s: &str) -> bool {
+    !s.is_empty()
+        && s.chars().all(|c| c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c))
+}
+
+/// A parsed MIME type, such as `text/plain; charset=utf-8`.
+///
+/// Type, subtype and parameter names are case-insensitive and kept in
+/// lowercase, as is the value of `charset`. Other parameter values are
+/// kept as given.
+///
 /// # Examples
-/// 
+///
 /// ```
 /// use jump::domain::mime_type::MimeType;
-/// 
-/// let mime = MimeType::TextPlain;
-/// assert_eq!(mime.to_string(), "text/plain");
-/// 
-/// let from_str = MimeType::try_from("text/plain").unwrap();
-/// assert_eq!(from_str, MimeType::TextPlain);
+///
+/// let mime: MimeType = "Text/Plain; Charset=\"UTF-8\"".parse().unwrap();
+/// assert_eq!(mime.essence(), "text/plain");
+/// assert_eq!(mime.charset(), Some("utf-8"));
+/// assert_eq!(mime.to_string(), "text/plain; charset=utf-8");
+///
+/// assert!(MimeType::try_from("not a type").is_err());
 /// ```
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-#[serde(rename_all = "lowercase")]
-pub enum MimeType {
-    /// Plain text content (text/plain)
-    TextPlain,
-    /// HTML content (text/html)
-    TextHtml,
-    /// JSON content (application/json)
-    ApplicationJson,
-    /// JPEG image (image/jpeg)
-    ImageJpeg,
-    /// PNG image (image/png)
-    ImagePng,
-    /// GIF image (image/gif)
-    ImageGif,
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct MimeType {
+    /// Type and subtype, such as `text/plain`
+    essence: String,
+
+    /// Parameter names and values, in the order given
+    params: Vec<(String, String)>,
 }
 
 impl MimeType {
-    /// Returns all supported MIME types.
-    /// 
-    /// This is useful for validation and documentation purposes.
-    /// 
+    /// Returns `text/plain`, the MIME type of payloads created without one.
+    pub fn text_plain() -> Self {
+        Self {
+            essence: "text/plain".to_string(),
+            params: Vec::new(),
+        }
+    }
+
+    /// Parses a MIME type asked for by a client, returning `None` for
+    /// [`AUTO`] to have the type detected from the content.
+    ///
     /// # Examples
-    /// 
+    ///
     /// ```
     /// use jump::domain::mime_type::MimeType;
-    /// 
-    /// let supported = MimeType::supported_types();
-    /// assert!(supported.contains(&"text/plain"));
+    ///
+    /// assert_eq!(MimeType::parse_requested("auto").unwrap(), None);
+    /// assert_eq!(MimeType::parse_requested("text/plain").unwrap(), Some(MimeType::text_plain()));
     /// ```
-    pub fn supported_types() -> Vec<&'static str> {
-        vec![
-            "text/plain",
-            "text/html",
-            "application/json",
-            "image/jpeg",
-            "image/png",
-            "image/gif",
-        ]
-    }
-
-    /// Checks if a MIME type string is supported.
-    /// 
-    /// # Arguments
-    /// 
-    /// * `mime_type` - The MIME type string to check
-    /// 
+    pub fn parse_requested(s: &str) -> Result<Option<Self>, MimeTypeError> {
+        if s.trim().eq_ignore_ascii_case(AUTO) {
+            return Ok(None);
+        }
+        s.parse().map(Some)
+    }
+
+    /// Returns the type and subtype without parameters, such as
+    /// `text/plain`.
+    pub fn essence(&self) -> &str {
+        &self.essence
+    }
+
+    /// Returns the value of the named parameter, if given.
+    pub fn param(&self, name: &str) -> Option<&str> {
+        let name = name.to_ascii_lowercase();
+        self.params
+            .iter()
+            .find(|(param, _)| *param == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Returns the `charset` parameter, if given.
+    pub fn charset(&self) -> Option<&str> {
+        self.param("charset")
+    }
+
+    /// Returns true if content of this type is textual.
+    ///
+    /// Textual content is expected to be UTF-8 and can be transported as a
+    /// plain JSON string; everything else is treated as binary. Types are
+    /// textual if they are `text/*`, JSON, XML or YAML, unless their
+    /// charset is not compatible with UTF-8.
+    ///
     /// # Examples
-    /// 
+    ///
     /// ```
     /// use jump::domain::mime_type::MimeType;
-    /// 
-    /// assert!(MimeType::is_supported("text/plain"));
-    /// assert!(!MimeType::is_supported("application/pdf"));
+    ///
+    /// assert!(MimeType::try_from("application/json").unwrap().is_text());
+    /// assert!(MimeType::try_from("image/svg+xml").unwrap().is_text());
+    /// assert!(!MimeType::try_from("image/gif").unwrap().is_text());
+    /// assert!(!MimeType::try_from("text/plain; charset=utf-16").unwrap().is_text());
     /// ```
-    pub fn is_supported(mime_type: &str) -> bool {
-        Self::supported_types().contains(&mime_type)
+    pub fn is_text(&self) -> bool {
+        if self.charset().is_some_and(|charset| !UTF8_CHARSETS.contains(&charset)) {
+            return false;
+        }
+        let (top_level, subtype) = self.essence.split_once('/').unwrap_or_default();
+        top_level == "text"
+            || matches!(subtype, "json" | "xml" | "yaml" | "x-yaml")
+            || subtype.ends_with("+json")
+            || subtype.ends_with("+xml")
+    }
+}
+
+impl Default for MimeType {
+    fn default() -> Self {
+        Self::text_plain()
+    }
+}
+
+impl FromStr for MimeType {
+    type Err = MimeTypeError;
+
+    /// Parses a MIME type with optional parameters.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MimeTypeError::Malformed` unless the string is of the form
+    /// `type/subtype`, followed by `; name=value` parameters whose values
+    /// are tokens or quoted strings.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let malformed = || MimeTypeError::Malformed(s.to_string());
+        let mut parts = s.split(';');
+
+        let essence = parts.next().unwrap_or_default().trim().to_ascii_lowercase();
+        match essence.split_once('/') {
+            Some((top_level, subtype)) if is_token(top_level) && is_token(subtype) => {}
+            _ => return Err(malformed()),
+        }
+
+        let mut params: Vec<(String, String)> = Vec::new();
+        for part in parts {
+            let part = part.trim();
+            // Tolerate a trailing semicolon
+            if part.is_empty() {
+                continue;
+            }
+            let (name, value) = part.split_once('=').ok_or_else(malformed)?;
+            let name = name.trim().to_ascii_lowercase();
+            let value = value.trim();
+            let value = match value.strip_prefix('"').and_then(|value| value.strip_suffix('"')) {
+                Some(quoted) if !quoted.contains('"') => quoted.to_string(),
+                Some(_) => return Err(malformed()),
+                None if is_token(value) => value.to_string(),
+                None => return Err(malformed()),
+            };
+            if !is_token(&name) || params.iter().any(|(param, _)| *param == name) {
+                return Err(malformed());
+            }
+            let value = if name == "charset" { value.to_ascii_lowercase() } else { value };
+            params.push((name, value));
+        }
+
+        Ok(Self { essence, params })
     }
 }
 
 impl TryFrom<&str> for MimeType {
     type Error = MimeTypeError;
 
-    /// Tries to create a MimeType from a string.
-    /// 
-    /// # Arguments
-    /// 
-    /// * `s` - The MIME type string
-    /// 
-    /// # Errors
-    /// 
-    /// Returns `MimeTypeError::UnsupportedMimeType` if the MIME type is not supported.
-    /// 
-    /// # Examples
-    /// 
-    /// ```
-    /// use jump::domain::mime_type::MimeType;
-    /// 
-    /// let mime = MimeType::try_from("text/plain").unwrap();
-    /// assert_eq!(mime, MimeType::TextPlain);
-    /// 
-    /// let error = MimeType::try_from("application/pdf").unwrap_err();
-    /// assert!(error.to_string().contains("Unsupported MIME type"));
-    /// ```
+    /// Tries to create a MimeType from a string, like [`str::parse`].
     fn try_from(s: &str) -> Result<Self, Self::Error> {
-        match s {
-            "text/plain" => Ok(Self::TextPlain),
-            "text/html" => Ok(Self::TextHtml),
-            "application/json" => Ok(Self::ApplicationJson),
-            "image/jpeg" => Ok(Self::ImageJpeg),
-            "image/png" => Ok(Self::ImagePng),
-            "image/gif" => Ok(Self::ImageGif),
-            _ => Err(MimeTypeError::UnsupportedMimeType(s.to_string())),
-        }
+        s.parse()
+    }
+}
+
+impl TryFrom<String> for MimeType {
+    type Error = MimeTypeError;
+
+    /// Tries to create a MimeType from a string, like [`str::parse`].
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<MimeType> for String {
+    fn from(mime_type: MimeType) -> Self {
+        mime_type.to_string()
     }
 }
 
 impl fmt::Display for MimeType {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let s = match self {
-            Self::TextPlain => "text/plain",
-            Self::TextHtml => "text/html",
-            Self::ApplicationJson => "application/json",
-            Self::ImageJpeg => "image/jpeg",
-            Self::ImagePng => "image/png",
-            Self::ImageGif => "image/gif",
+        write!(f, "{}", self.essence)?;
+        for (name, value) in &self.params {
+            if is_token(value) {
+                write!(f, "; {}={}", name, value)?;
+            } else {
+                write!(f, "; {}=\"{}\"", name, value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// How content of one MIME type is treated.
+///
+/// # Examples
+///
+/// ```
+/// use jump::domain::mime_type::MimePolicy;
+///
+/// let policy = MimePolicy::new("pdf").with_max_size(Some(1024)).with_inline(false);
+/// assert_eq!(policy.extension(), "pdf");
+/// assert_eq!(policy.max_size(), Some(1024));
+/// assert!(!policy.is_inline());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MimePolicy {
+    extension: String,
+    max_size: Option<usize>,
+    inline: bool,
+}
+
+impl MimePolicy {
+    /// Creates a policy for content saved with `extension`, served inline
+    /// and limited in size only by the server's overall limit.
+    pub fn new(extension: impl Into<String>) -> Self {
+        Self {
+            extension: extension.into(),
+            max_size: None,
+            inline: true,
+        }
+    }
+
+    /// Limits the size of the content in bytes.
+    pub fn with_max_size(mut self, max_size: Option<usize>) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    /// Sets whether the content may be displayed by browsers, rather than
+    /// only downloaded.
+    pub fn with_inline(mut self, inline: bool) -> Self {
+        self.inline = inline;
+        self
+    }
+
+    /// Returns the file extension suggested for downloaded content.
+    pub fn extension(&self) -> &str {
+        &self.extension
+    }
+
+    /// Returns the largest content allowed in bytes, if limited.
+    pub fn max_size(&self) -> Option<usize> {
+        self.max_size
+    }
+
+    /// Returns true if the content may be displayed by browsers.
+    pub fn is_inline(&self) -> bool {
+        self.inline
+    }
+}
+
+/// What to do with content that does not match its declared MIME type.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MismatchPolicy {
+    /// The content is refused (the default)
+    #[default]
+    Reject,
+    /// The content is stored under the MIME type detected from it
+    Correct,
+}
+
+impl FromStr for MismatchPolicy {
+    type Err = MimeTypeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "reject" => Ok(Self::Reject),
+            "correct" => Ok(Self::Correct),
+            other => Err(MimeTypeError::InvalidConfig(format!("unknown mismatch policy {}", other))),
+        }
+    }
+}
+
+/// MIME types the server knows how to treat: type, file extension, and
+/// whether it is served inline by default.
+const KNOWN_TYPES: &[(&str, &str, bool)] = &[
+    ("text/plain", "txt", true),
+    ("text/html", "html", true),
+    ("application/json", "json", true),
+    ("image/jpeg", "jpg", true),
+    ("image/png", "png", true),
+    ("image/gif", "gif", true),
+    ("application/pdf", "pdf", true),
+    ("text/markdown", "md", true),
+    ("text/csv", "csv", true),
+    // SVG can carry scripts, so it is only downloaded unless configured
+    ("image/svg+xml", "svg", false),
+    ("application/octet-stream", "bin", false),
+    ("application/x-yaml", "yaml", true),
+];
+
+/// MIME types enabled unless configured otherwise.
+const DEFAULT_TYPES: &[&str] = &[
+    "text/plain",
+    "text/html",
+    "application/json",
+    "image/jpeg",
+    "image/png",
+    "image/gif",
+];
+
+/// The MIME types payloads may use, each with its policy.
+///
+/// Types are looked up by their essence, so parameters such as `charset`
+/// do not need to be enabled separately. The registry also decides the type
+/// of payloads created without one, and what happens to content that does
+/// not match its declared type.
+///
+/// # Examples
+///
+/// ```
+/// use jump::domain::mime_type::{MimeRegistry, MimeType};
+///
+/// let registry = MimeRegistry::from_config("text/plain, application/pdf; max_size=1024").unwrap();
+///
+/// let pdf = MimeType::try_from("application/pdf").unwrap();
+/// assert!(registry.check(&pdf, 1024).is_ok());
+/// assert!(registry.check(&pdf, 1025).is_err());
+///
+/// let html = MimeType::try_from("text/html").unwrap();
+/// assert!(registry.check(&html, 10).is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MimeRegistry {
+    types: BTreeMap<String, MimePolicy>,
+    default_type: Option<MimeType>,
+    mismatch_policy: MismatchPolicy,
+}
+
+impl MimeRegistry {
+    /// Creates a registry with no types enabled.
+    pub fn empty() -> Self {
+        Self {
+            types: BTreeMap::new(),
+            default_type: Some(MimeType::text_plain()),
+            mismatch_policy: MismatchPolicy::default(),
+        }
+    }
+
+    /// Sets the MIME type of payloads created without one, or `None` to
+    /// detect it from their content.
+    pub fn with_default_type(mut self, default_type: Option<MimeType>) -> Self {
+        self.default_type = default_type;
+        self
+    }
+
+    /// Sets what happens to content that does not match its declared type.
+    pub fn with_mismatch_policy(mut self, mismatch_policy: MismatchPolicy) -> Self {
+        self.mismatch_policy = mismatch_policy;
+        self
+    }
+
+    /// Returns the MIME type of payloads created without one, or `None` if
+    /// it is detected from their content.
+    pub fn default_type(&self) -> Option<&MimeType> {
+        self.default_type.as_ref()
+    }
+
+    /// Returns what happens to content that does not match its declared
+    /// type.
+    pub fn mismatch_policy(&self) -> MismatchPolicy {
+        self.mismatch_policy
+    }
+
+    /// Returns the default policy for a MIME type the server knows, given
+    /// by its essence.
+    pub fn known_policy(essence: &str) -> Option<MimePolicy> {
+        KNOWN_TYPES
+            .iter()
+            .find(|(known, _, _)| *known == essence)
+            .map(|(_, extension, inline)| MimePolicy::new(*extension).with_inline(*inline))
+    }
+
+    /// Returns the usual policy of a MIME type if the server knows it, and
+    /// otherwise one offering the content only for download as `.bin`.
+    fn default_policy(essence: &str) -> MimePolicy {
+        Self::known_policy(essence).unwrap_or_else(|| MimePolicy::new("bin").with_inline(false))
+    }
+
+    /// Creates a registry from a comma-separated list of MIME types.
+    ///
+    /// Each type may be followed by policy attributes written like MIME
+    /// parameters: `max_size` in bytes, `inline` as `true` or `false`, and
+    /// `extension`. Types the server knows default to their usual policy;
+    /// others are downloaded as `.bin` unless configured otherwise.
+    ///
+    /// ```text
+    /// text/plain, text/markdown; max_size=65536, application/pdf; inline=false
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns `MimeTypeError::InvalidConfig` if an entry is malformed or
+    /// has an unknown or invalid attribute, or if no type is enabled.
+    pub fn from_config(config: &str) -> Result<Self, MimeTypeError> {
+        let invalid = |msg: String| MimeTypeError::InvalidConfig(msg);
+        let mut registry = Self::empty();
+
+        for entry in config.split(',').map(str::trim).filter(|entry| !entry.is_empty()) {
+            let mime_type: MimeType = entry
+                .parse()
+                .map_err(|_| invalid(format!("malformed entry {:?}", entry)))?;
+            let mut policy = Self::default_policy(mime_type.essence());
+
+            for (name, value) in &mime_type.params {
+                policy = match name.as_str() {
+                    "max_size" => policy.with_max_size(Some(value.parse().map_err(|_| {
+                        invalid(format!("invalid max_size {:?} for {}", value, mime_type.essence()))
+                    })?)),
+                    "inline" => policy.with_inline(value.parse().map_err(|_| {
+                        invalid(format!("invalid inline {:?} for {}", value, mime_type.essence()))
+                    })?),
+                    "extension" => MimePolicy {
+                        extension: value.clone(),
+                        ..policy
+                    },
+                    _ => {
+                        return Err(invalid(format!(
+                            "unknown attribute {} for {}",
+                            name,
+                            mime_type.essence()
+                        )))
+                    }
+                };
+            }
+            registry.enable(mime_type.essence(), policy);
+        }
+
+        if registry.types.is_empty() {
+            return Err(invalid("no MIME types enabled".to_string()));
+        }
+        Ok(registry)
+    }
+
+    /// Enables a MIME type, given by its essence, with a policy.
+    ///
+    /// Replaces the policy if the type is already enabled.
+    pub fn enable(&mut self, essence: &str, policy: MimePolicy) {
+        self.types.insert(essence.to_ascii_lowercase(), policy);
+    }
+
+    /// Returns the policy of a MIME type, if it is enabled.
+    pub fn policy(&self, mime_type: &MimeType) -> Option<&MimePolicy> {
+        self.types.get(mime_type.essence())
+    }
+
+    /// Returns the policy for serving content stored as `mime_type`.
+    ///
+    /// Content stored before its type was disabled is still served, under
+    /// the type's usual policy if the server knows it, and otherwise only
+    /// for download as `.bin`.
+    pub fn serving_policy(&self, mime_type: &MimeType) -> MimePolicy {
+        self.policy(mime_type)
+            .cloned()
+            .unwrap_or_else(|| Self::default_policy(mime_type.essence()))
+    }
+
+    /// Returns the essences of the enabled MIME types, in order.
+    pub fn supported_types(&self) -> impl Iterator<Item = &str> {
+        self.types.keys().map(String::as_str)
+    }
+
+    /// Checks that content of `size` bytes may be stored as `mime_type`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MimeTypeError::UnsupportedMimeType` if the type is not
+    /// enabled, or `MimeTypeError::TooLarge` if the content is larger than
+    /// its policy allows.
+    pub fn check(&self, mime_type: &MimeType, size: usize) -> Result<&MimePolicy, MimeTypeError> {
+        let policy = self
+            .policy(mime_type)
+            .ok_or_else(|| MimeTypeError::UnsupportedMimeType(mime_type.to_string()))?;
+        match policy.max_size() {
+            Some(max_size) if size > max_size => Err(MimeTypeError::TooLarge {
+                mime_type: mime_type.essence().to_string(),
+                max_size,
+            }),
+            _ => Ok(policy),
+        }
+    }
+
+    /// Works out the MIME type `content` is stored as.
+    ///
+    /// The declared type is inspected against the content, or detected from
+    /// it if `None`. A mismatch is refused or corrected according to the
+    /// registry's [`MismatchPolicy`], and the resulting type must then pass
+    /// [`check`](Self::check).
+    ///
+    /// # Errors
+    ///
+    /// Returns `MimeTypeError::Mismatch` if the content does not match its
+    /// type and mismatches are refused, or any error of `check`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jump::domain::mime_type::{MimeRegistry, MimeType, MismatchPolicy};
+    ///
+    /// let png = MimeType::try_from("image/png").unwrap();
+    /// let registry = MimeRegistry::default();
+    /// assert!(registry.resolve(Some(png.clone()), b"GIF89a...").is_err());
+    /// assert_eq!(registry.resolve(None, b"GIF89a...").unwrap().essence(), "image/gif");
+    ///
+    /// let registry = registry.with_mismatch_policy(MismatchPolicy::Correct);
+    /// assert_eq!(registry.resolve(Some(png), b"GIF89a...").unwrap().essence(), "image/gif");
+    /// ```
+    pub fn resolve(&self, declared: Option<MimeType>, content: &[u8]) -> Result<MimeType, MimeTypeError> {
+        let mime_type = match declared {
+            None => inspection::detect(content),
+            Some(declared) => match inspection::verify(&declared, content) {
+                Ok(()) => declared,
+                Err(_) if self.mismatch_policy == MismatchPolicy::Correct => inspection::detect(content),
+                Err(e) => return Err(e),
+            },
         };
-        write!(f, "{}", s)
+        self.check(&mime_type, content.len())?;
+        Ok(mime_type)
+    }
+}
+
+impl Default for MimeRegistry {
+    /// Enables the MIME types accepted before types were configurable.
+    fn default() -> Self {
+        let mut registry = Self::empty();
+        for essence in DEFAULT_TYPES {
+            let policy = Self::known_policy(essence).expect("default MIME types are known");
+            registry.enable(essence, policy);
+        }
+        registry
     }
 }
 
@@ -148,43 +605,183 @@ impl fmt::Display for MimeType {
 mod tests {
     use super::*;
 
+    fn mime(s: &str) -> MimeType {
+        MimeType::try_from(s).unwrap()
+    }
+
     #[test]
-    fn test_supported_types_contains_required_types() {
-        let types = MimeType::supported_types();
+    fn test_default_registry_contains_required_types() {
+        let registry = MimeRegistry::default();
+        let types: Vec<&str> = registry.supported_types().collect();
         assert!(types.contains(&"text/plain"));
         assert!(types.contains(&"text/html"));
         assert!(types.contains(&"application/json"));
         assert!(types.contains(&"image/jpeg"));
         assert!(types.contains(&"image/png"));
         assert!(types.contains(&"image/gif"));
+        assert!(!types.contains(&"application/pdf"));
     }
 
     #[test]
     fn test_valid_mime_type_conversion() {
-        assert_eq!(MimeType::try_from("text/plain").unwrap(), MimeType::TextPlain);
-        assert_eq!(MimeType::try_from("text/html").unwrap(), MimeType::TextHtml);
-        assert_eq!(
-            MimeType::try_from("application/json").unwrap(),
-            MimeType::ApplicationJson
-        );
+        assert_eq!(mime("text/plain").essence(), "text/plain");
+        assert_eq!(mime("TEXT/HTML").essence(), "text/html");
+        assert_eq!(mime(" application/json ").to_string(), "application/json");
+        assert_eq!(mime("image/svg+xml").essence(), "image/svg+xml");
     }
 
     #[test]
     fn test_invalid_mime_type_conversion() {
-        assert!(MimeType::try_from("application/pdf").is_err());
         assert!(MimeType::try_from("invalid").is_err());
+        assert!(MimeType::try_from("text/").is_err());
+        assert!(MimeType::try_from("text plain/html").is_err());
+        assert!(MimeType::try_from("text/plain; charset").is_err());
+        assert!(MimeType::try_from("text/plain; charset=utf-8; charset=ascii").is_err());
+        assert!(MimeType::try_from("text/plain; name=\"a\"b\"").is_err());
+    }
+
+    #[test]
+    fn test_parameters() {
+        let mime = mime("text/plain; Charset=UTF-8; format=Flowed;");
+        assert_eq!(mime.charset(), Some("utf-8"));
+        assert_eq!(mime.param("FORMAT"), Some("Flowed"));
+        assert_eq!(mime.to_string(), "text/plain; charset=utf-8; format=Flowed");
+
+        let quoted = MimeType::try_from("text/plain; title=\"two words\"").unwrap();
+        assert_eq!(quoted.param("title"), Some("two words"));
+        assert_eq!(quoted.to_string(), "text/plain; title=\"two words\"");
     }
 
     #[test]
     fn test_mime_type_display() {
-        assert_eq!(MimeType::TextPlain.to_string(), "text/plain");
-        assert_eq!(MimeType::ApplicationJson.to_string(), "application/json");
+        assert_eq!(MimeType::text_plain().to_string(), "text/plain");
+        assert_eq!(mime("application/json").to_string(), "application/json");
+    }
+
+    #[test]
+    fn test_serde_round_trip() {
+        let mime = mime("text/plain; charset=utf-8");
+        let json = serde_json::to_string(&mime).unwrap();
+        assert_eq!(json, "\"text/plain; charset=utf-8\"");
+        assert_eq!(serde_json::from_str::<MimeType>(&json).unwrap(), mime);
+        assert!(serde_json::from_str::<MimeType>("\"nonsense\"").is_err());
+    }
+
+    #[test]
+    fn test_is_text() {
+        assert!(mime("text/plain").is_text());
+        assert!(mime("text/html").is_text());
+        assert!(mime("text/csv; charset=us-ascii").is_text());
+        assert!(mime("application/ld+json").is_text());
+        assert!(mime("application/x-yaml").is_text());
+        assert!(!mime("image/png").is_text());
+        assert!(!mime("application/pdf").is_text());
+        assert!(!mime("text/plain; charset=iso-8859-1").is_text());
+    }
+
+    #[test]
+    fn test_known_policies() {
+        assert_eq!(MimeRegistry::known_policy("text/html").unwrap().extension(), "html");
+        assert_eq!(MimeRegistry::known_policy("image/jpeg").unwrap().extension(), "jpg");
+        assert_eq!(MimeRegistry::known_policy("text/markdown").unwrap().extension(), "md");
+        assert!(!MimeRegistry::known_policy("image/svg+xml").unwrap().is_inline());
+        assert!(MimeRegistry::known_policy("application/x-unknown").is_none());
+    }
+
+    #[test]
+    fn test_check() {
+        let registry = MimeRegistry::default();
+        assert!(registry.check(&mime("text/plain; charset=utf-8"), 10).is_ok());
+        assert!(matches!(
+            registry.check(&mime("application/pdf"), 10),
+            Err(MimeTypeError::UnsupportedMimeType(_))
+        ));
+    }
+
+    #[test]
+    fn test_from_config() {
+        let registry = MimeRegistry::from_config(
+            "text/plain, text/markdown; max_size=100, application/pdf; inline=false, \
+             application/x-custom; extension=cst",
+        )
+        .unwrap();
+
+        assert_eq!(registry.supported_types().count(), 4);
+        assert!(matches!(
+            registry.check(&mime("text/markdown"), 101),
+            Err(MimeTypeError::TooLarge { max_size: 100, .. })
+        ));
+        assert!(!registry.policy(&mime("application/pdf")).unwrap().is_inline());
+        let custom = registry.policy(&mime("application/x-custom")).unwrap();
+        assert_eq!(custom.extension(), "cst");
+        assert!(!custom.is_inline());
+        assert!(registry.check(&mime("text/html"), 1).is_err());
+    }
+
+    #[test]
+    fn test_serving_policy() {
+        let registry = MimeRegistry::from_config("application/pdf; inline=false").unwrap();
+        assert!(!registry.serving_policy(&mime("application/pdf")).is_inline());
+        // Types disabled since the content was stored keep being served
+        assert_eq!(registry.serving_policy(&mime("image/png")).extension(), "png");
+        let unknown = registry.serving_policy(&mime("application/x-unknown"));
+        assert_eq!(unknown.extension(), "bin");
+        assert!(!unknown.is_inline());
+    }
+
+    #[test]
+    fn test_parse_requested() {
+        assert_eq!(MimeType::parse_requested("AUTO").unwrap(), None);
+        assert_eq!(MimeType::parse_requested("image/png").unwrap(), Some(mime("image/png")));
+        assert!(MimeType::parse_requested("automatic").is_err());
+    }
+
+    #[test]
+    fn test_mismatch_policy_from_str() {
+        assert_eq!("Reject".parse::<MismatchPolicy>().unwrap(), MismatchPolicy::Reject);
+        assert_eq!("correct".parse::<MismatchPolicy>().unwrap(), MismatchPolicy::Correct);
+        assert!("ignore".parse::<MismatchPolicy>().is_err());
+    }
+
+    #[test]
+    fn test_resolve_rejects_mismatches() {
+        let registry = MimeRegistry::default();
+        assert_eq!(registry.resolve(Some(mime("text/plain")), b"hello").unwrap(), mime("text/plain"));
+        assert!(matches!(
+            registry.resolve(Some(mime("image/png")), b"hello"),
+            Err(MimeTypeError::Mismatch { .. })
+        ));
+        assert!(matches!(
+            registry.resolve(Some(mime("application/json")), b"{"),
+            Err(MimeTypeError::Mismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_resolve_corrects_mismatches() {
+        let registry = MimeRegistry::default().with_mismatch_policy(MismatchPolicy::Correct);
+        assert_eq!(registry.resolve(Some(mime("image/png")), b"hello").unwrap(), mime("text/plain"));
+        // The corrected type must still be enabled
+        assert!(matches!(
+            registry.resolve(Some(mime("image/png")), &[0x00, 0xff]),
+            Err(MimeTypeError::UnsupportedMimeType(_))
+        ));
+    }
+
+    #[test]
+    fn test_resolve_detects_undeclared_types() {
+        let registry = MimeRegistry::default();
+        assert_eq!(registry.resolve(None, br#"{"a": 1}"#).unwrap(), mime("application/json"));
+        assert_eq!(registry.resolve(None, b"\xff\xd8\xff\xe0").unwrap(), mime("image/jpeg"));
+        assert!(registry.resolve(None, b"%PDF-1.7").is_err());
     }
 
     #[test]
-    fn test_is_supported() {
-        assert!(MimeType::is_supported("text/plain"));
-        assert!(MimeType::is_supported("application/json"));
-        assert!(!MimeType::is_supported("application/pdf"));
+    fn test_from_config_rejects_invalid_entries() {
+        assert!(MimeRegistry::from_config("").is_err());
+        assert!(MimeRegistry::from_config("text").is_err());
+        assert!(MimeRegistry::from_config("text/plain; max_size=lots").is_err());
+        assert!(MimeRegistry::from_config("text/plain; inline=maybe").is_err());
+        assert!(MimeRegistry::from_config("text/plain; colour=blue").is_err());
     }
 }
@@ -1,3 +1,10 @@
 pub mod payload;
 pub mod hash_id;
 pub mod mime_type;
+pub mod inspection;
+pub mod management_token;
+pub mod share_code;
+pub mod alias;
+pub mod expiry;
+pub mod revision;
+pub mod metadata;
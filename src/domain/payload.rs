@@ -3,30 +3,59 @@
 //! A Payload represents a piece of content that can be shared temporarily, with features like:
 //! - Unique identification through HashId
 //! - Content type validation through MimeType
-//! - Automatic expiration through expiry_time
+//! - Automatic expiration through expiry_time, optionally moved by views
+//!   through an idle timeout or an expiry after the first view
+//! - Optional burn-after-reading through one_time
+//! - Optional view limits through max_views
+//! - Optional password protection, with throttling of wrong guesses
+//! - Owner management through a hashed management token
 //! - Tracking of creation, update, and view times
+//! - Content replacement, keeping earlier content as revisions
+//! - A version number counting changes by the owner, for optimistic
+//!   concurrency control
+//! - Optional metadata describing what the payload is for
 
+use argon2::{
+    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Argon2,
+};
 use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 use super::hash_id::HashId;
+use super::management_token::ManagementToken;
+use super::alias::Alias;
+use super::share_code::ShareCode;
+use super::expiry::{ExpiryMode, DEFAULT_LIFETIME_SECS};
+use super::metadata::Metadata;
 use super::mime_type::{MimeType, MimeTypeError};
+use super::revision::Revision;
+
+/// Wrong passwords accepted before further attempts are throttled
+const FREE_UNLOCK_ATTEMPTS: u64 = 5;
+
+/// Wait imposed after the first throttled failure, doubling with each
+/// further failure
+const UNLOCK_BACKOFF_SECONDS: i64 = 2;
+
+/// Longest wait imposed between throttled attempts
+const MAX_UNLOCK_BACKOFF_SECONDS: i64 = 3600;
 
 /// Errors that can occur when working with Payloads.
 #[derive(Debug, Error)]
 pub enum PayloadError {
-    /// The payload has expired and is no longer accessible
-    #[error("Payload has expired")]
-    Expired,
-
-    /// The MIME type provided is not supported
+    /// The MIME type provided is malformed or not supported
     #[error("Invalid MIME type: {0}")]
     InvalidMimeType(#[from] MimeTypeError),
 
     /// The content is empty
     #[error("Content cannot be empty")]
     EmptyContent,
+
+    /// The password could not be hashed
+    #[error("Failed to hash password: {0}")]
+    PasswordHash(String),
 }
 
 /// Represents a shareable payload in the system.
@@ -43,12 +72,12 @@ pub enum PayloadError {
 /// 
 /// let content = "Hello, World!";
 /// let payload = Payload::new(
-///     content.to_string(),
+///     content.as_bytes().to_vec(),
 ///     None, // Default to text/plain
 ///     None, // Default expiry time
 /// ).unwrap();
 /// 
-/// assert_eq!(payload.content(), content);
+/// assert_eq!(payload.content(), content.as_bytes());
 /// assert!(!payload.is_expired());
 /// ```
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -56,11 +85,24 @@ pub struct Payload {
     /// Unique identifier for the payload
     hash_id: HashId,
     
-    /// The actual content being shared
-    content: String,
+    /// The actual content being shared, as raw bytes.
+    ///
+    /// Content is not part of the serialized form: repositories store it
+    /// separately so binary data never has to be escaped.
+    #[serde(skip)]
+    content: Vec<u8>,
     
     /// The MIME type of the content
     mime_type: MimeType,
+
+    /// Number of the current content revision, starting at 1
+    #[serde(default = "first_revision")]
+    revision: u64,
+
+    /// Number of the current version, starting at 1 and incremented by
+    /// every change the owner makes
+    #[serde(default = "first_revision")]
+    version: u64,
     
     /// When the payload was created
     created_at: DateTime<Utc>,
@@ -73,6 +115,58 @@ pub struct Payload {
     
     /// When the payload will expire
     expiry_time: DateTime<Utc>,
+
+    /// How views move the expiry time
+    #[serde(default)]
+    expiry_mode: ExpiryMode,
+
+    /// Latest expiry time allowed, for modes that move the expiry
+    #[serde(default)]
+    expiry_limit: Option<DateTime<Utc>>,
+
+    /// Expiry time set by the mode, before the limit is applied
+    #[serde(default)]
+    expiry_deadline: Option<DateTime<Utc>>,
+
+    /// Whether the payload is deleted on its first successful read
+    #[serde(default)]
+    one_time: bool,
+
+    /// How many times the payload has been viewed
+    #[serde(default)]
+    view_count: u64,
+
+    /// Maximum number of views allowed, if limited
+    #[serde(default)]
+    max_views: Option<u64>,
+
+    /// Argon2 hash of the password required to read the payload, if any
+    #[serde(default)]
+    password_hash: Option<String>,
+
+    /// Number of wrong passwords given for the payload
+    #[serde(default)]
+    failed_unlocks: u64,
+
+    /// When the last wrong password was given
+    #[serde(default)]
+    failed_unlock_at: Option<DateTime<Utc>>,
+
+    /// SHA-256 hash of the token authorizing management of the payload
+    #[serde(default)]
+    management_token_hash: Option<String>,
+
+    /// Human-readable code resolving to the payload, if one was requested
+    #[serde(default)]
+    share_code: Option<ShareCode>,
+
+    /// Custom name resolving to the payload, if one was requested
+    #[serde(default)]
+    alias: Option<Alias>,
+
+    /// Description of what the payload is for
+    #[serde(default)]
+    metadata: Metadata,
 }
 
 impl Payload {
@@ -80,14 +174,14 @@ impl Payload {
     /// 
     /// # Arguments
     /// 
-    /// * `content` - The content to be shared
+    /// * `content` - The raw bytes to be shared
     /// * `mime_type` - Optional MIME type (defaults to text/plain)
     /// * `expiry_time` - Optional expiry time (defaults to 24 hours from creation)
     /// 
     /// # Errors
     /// 
     /// Returns `PayloadError::EmptyContent` if the content is empty
-    /// Returns `PayloadError::InvalidMimeType` if the MIME type is not supported
+    /// Returns `PayloadError::InvalidMimeType` if the MIME type is malformed
     /// 
     /// # Examples
     /// 
@@ -97,20 +191,20 @@ impl Payload {
     /// 
     /// // Create with defaults
     /// let payload = Payload::new(
-    ///     "Hello".to_string(),
+    ///     b"Hello".to_vec(),
     ///     None,
     ///     None,
     /// ).unwrap();
     /// 
     /// // Create with specific MIME type and expiry
     /// let payload = Payload::new(
-    ///     "Hello".to_string(),
+    ///     b"Hello".to_vec(),
     ///     Some("text/html".to_string()),
     ///     Some(Utc::now() + Duration::hours(1)),
     /// ).unwrap();
     /// ```
     pub fn new(
-        content: String,
+        content: Vec<u8>,
         mime_type: Option<String>,
         expiry_time: Option<DateTime<Utc>>,
     ) -> Result<Self, PayloadError> {
@@ -120,31 +214,345 @@ impl Payload {
 
         let now = Utc::now();
         let mime_type = match mime_type {
-            Some(mime_str) => MimeType::try_from(mime_str.as_str())?,
-            None => MimeType::TextPlain,
+            Some(mime_str) => mime_str.parse()?,
+            None => MimeType::text_plain(),
         };
 
         Ok(Self {
             hash_id: HashId::new(),
             content,
             mime_type,
+            revision: 1,
+            version: 1,
             created_at: now,
             updated_at: now,
             viewed_at: None,
-            expiry_time: expiry_time.unwrap_or_else(|| now + Duration::hours(24)),
+            expiry_time: expiry_time.unwrap_or_else(|| now + Duration::seconds(DEFAULT_LIFETIME_SECS)),
+            expiry_mode: ExpiryMode::Fixed,
+            expiry_limit: None,
+            expiry_deadline: None,
+            one_time: false,
+            view_count: 0,
+            max_views: None,
+            password_hash: None,
+            failed_unlocks: 0,
+            failed_unlock_at: None,
+            management_token_hash: None,
+            share_code: None,
+            alias: None,
+            metadata: Metadata::default(),
         })
     }
 
+    /// Replaces the randomly generated hash ID.
+    ///
+    /// Used when IDs come from a configured
+    /// [`HashIdGenerator`](super::hash_id::HashIdGenerator) rather than the
+    /// UUID default.
+    pub fn with_hash_id(mut self, hash_id: HashId) -> Self {
+        self.hash_id = hash_id;
+        self
+    }
+
+    /// Makes the payload burn after reading.
+    ///
+    /// A one-time payload is deleted on its first successful read, so its
+    /// content can be retrieved exactly once.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jump::domain::payload::Payload;
+    ///
+    /// let payload = Payload::new(b"secret".to_vec(), None, None)
+    ///     .unwrap()
+    ///     .with_one_time(true);
+    /// assert!(payload.is_one_time());
+    /// ```
+    pub fn with_one_time(mut self, one_time: bool) -> Self {
+        self.one_time = one_time;
+        self
+    }
+
+    /// Lets views move the expiry time.
+    ///
+    /// The expiry time given at creation becomes a limit the payload never
+    /// outlives, however often it is read.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chrono::{Duration, Utc};
+    /// use jump::domain::{expiry::ExpiryMode, payload::Payload};
+    ///
+    /// let limit = Utc::now() + Duration::hours(1);
+    /// let payload = Payload::new(b"secret".to_vec(), None, Some(limit))
+    ///     .unwrap()
+    ///     .with_expiry_mode(ExpiryMode::Idle { timeout_secs: 300 });
+    /// assert!(payload.expiry_time() <= Utc::now() + Duration::minutes(5));
+    /// assert_eq!(payload.expiry_limit(), limit);
+    /// ```
+    pub fn with_expiry_mode(mut self, expiry_mode: ExpiryMode) -> Self {
+        let limit = self.expiry_limit();
+        self.expiry_mode = expiry_mode;
+        self.expiry_limit = (expiry_mode != ExpiryMode::Fixed).then_some(limit);
+        self.apply_deadline(expiry_mode.initial_deadline(self.created_at), limit);
+        self
+    }
+
+    /// Limits how many times the payload can be viewed.
+    ///
+    /// Once `view_count` reaches the limit the payload is no longer served.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jump::domain::payload::Payload;
+    ///
+    /// let mut payload = Payload::new(b"secret".to_vec(), None, None)
+    ///     .unwrap()
+    ///     .with_max_views(Some(1));
+    /// assert!(!payload.view_limit_reached());
+    ///
+    /// payload.mark_viewed();
+    /// assert!(payload.view_limit_reached());
+    /// ```
+    pub fn with_max_views(mut self, max_views: Option<u64>) -> Self {
+        self.max_views = max_views;
+        self
+    }
+
+    /// Protects the payload with a password.
+    ///
+    /// Only an Argon2id hash of the password is kept. Hashing is deliberately
+    /// slow, so async callers should run this on a blocking thread.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PayloadError::PasswordHash` if the password cannot be hashed
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jump::domain::payload::Payload;
+    ///
+    /// let payload = Payload::new(b"secret".to_vec(), None, None)
+    ///     .unwrap()
+    ///     .with_password("hunter2")
+    ///     .unwrap();
+    /// assert!(payload.password_matches(Some("hunter2")));
+    /// assert!(!payload.password_matches(Some("hunter3")));
+    /// assert!(!payload.password_matches(None));
+    /// ```
+    pub fn with_password(mut self, password: &str) -> Result<Self, PayloadError> {
+        let salt = SaltString::generate(&mut OsRng);
+        let hash = Argon2::default()
+            .hash_password(password.as_bytes(), &salt)
+            .map_err(|e| PayloadError::PasswordHash(e.to_string()))?;
+        self.password_hash = Some(hash.to_string());
+        Ok(self)
+    }
+
+    /// Sets the token that authorizes management of the payload.
+    ///
+    /// Only a hash of the token is kept.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use jump::domain::{management_token::ManagementToken, payload::Payload};
+    ///
+    /// let token = ManagementToken::generate();
+    /// let payload = Payload::new(b"mine".to_vec(), None, None)
+    ///     .unwrap()
+    ///     .with_management_token(&token);
+    /// assert!(payload.is_managed_by(&token));
+    /// assert!(!payload.is_managed_by(&ManagementToken::generate()));
+    /// ```
+    pub fn with_management_token(mut self, token: &ManagementToken) -> Self {
+        self.management_token_hash = Some(token.hash());
+        self
+    }
+
+    /// Records the share code reserved for the payload.
+    ///
+    /// The code itself is resolved by the repository; the payload only
+    /// remembers it so it can be shown to readers.
+    pub fn with_share_code(mut self, share_code: ShareCode) -> Self {
+        self.share_code = Some(share_code);
+        self
+    }
+
+    /// Records the alias reserved for the payload.
+    ///
+    /// Like the share code, the alias is resolved by the repository.
+    pub fn with_alias(mut self, alias: Alias) -> Self {
+        self.alias = Some(alias);
+        self
+    }
+
+    /// Describes what the payload is for.
+    pub fn with_metadata(mut self, metadata: Metadata) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
     /// Returns true if the payload has expired.
     /// 
-    /// A payload is considered expired if the current time is past its expiry_time.
+    /// A payload is considered expired if the current time is past its
+    /// expiry_time, which already accounts for its expiry mode.
     pub fn is_expired(&self) -> bool {
         Utc::now() > self.expiry_time
     }
 
-    /// Records that the payload was viewed at the current time.
+    /// Returns true if the payload has been viewed as often as it may be.
+    pub fn view_limit_reached(&self) -> bool {
+        self.max_views.is_some_and(|max_views| self.view_count >= max_views)
+    }
+
+    /// Records a view at the current time and increments the view count.
+    ///
+    /// Depending on the expiry mode, the view also pushes the expiry time
+    /// forward, up to the expiry limit.
     pub fn mark_viewed(&mut self) {
-        self.viewed_at = Some(Utc::now());
+        let now = Utc::now();
+        self.viewed_at = Some(now);
+        self.view_count += 1;
+        if let Some(deadline) = self.expiry_mode.deadline_after_view(now, self.view_count) {
+            self.apply_deadline(Some(deadline), self.expiry_limit());
+        }
+    }
+
+    /// Changes when the payload expires, recording the change as an update
+    /// and a new version.
+    ///
+    /// For payloads whose expiry moves with views this changes the limit,
+    /// so a running idle or after-view countdown still applies.
+    /// An expiry time that has already passed expires the payload at once.
+    pub fn set_expiry_time(&mut self, expiry_time: DateTime<Utc>) {
+        if self.expiry_mode == ExpiryMode::Fixed {
+            self.expiry_time = expiry_time;
+        } else {
+            self.expiry_limit = Some(expiry_time);
+            self.apply_deadline(self.expiry_deadline, expiry_time);
+        }
+        self.updated_at = Utc::now();
+        self.version += 1;
+    }
+
+    /// Sets the mode's deadline and expires the payload at the earlier of
+    /// it and the limit
+    fn apply_deadline(&mut self, deadline: Option<DateTime<Utc>>, limit: DateTime<Utc>) {
+        self.expiry_deadline = deadline;
+        self.expiry_time = deadline.map_or(limit, |deadline| deadline.min(limit));
+    }
+
+    /// Replaces the content, and the MIME type if one is given, recording
+    /// the change as an update and a new version.
+    ///
+    /// Returns the content that was replaced as a revision, to be kept by
+    /// the repository.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PayloadError::EmptyContent` if the content is empty
+    /// Returns `PayloadError::InvalidMimeType` if the MIME type is malformed
+    pub fn replace_content(
+        &mut self,
+        content: Vec<u8>,
+        mime_type: Option<String>,
+    ) -> Result<Revision, PayloadError> {
+        if content.is_empty() {
+            return Err(PayloadError::EmptyContent);
+        }
+        let mime_type = match mime_type {
+            Some(mime_str) => mime_str.parse()?,
+            None => self.mime_type.clone(),
+        };
+
+        let previous = self.current_revision();
+        self.content = content;
+        self.mime_type = mime_type;
+        self.revision += 1;
+        self.version += 1;
+        self.updated_at = Utc::now();
+        Ok(previous)
+    }
+
+    /// Returns the current content as a revision.
+    pub fn current_revision(&self) -> Revision {
+        Revision::new(self.revision, self.content.clone(), self.mime_type.clone(), self.updated_at)
+    }
+
+    /// Returns true if the given password unlocks the payload.
+    ///
+    /// Payloads without a password are unlocked by anything, including no
+    /// password at all. Like hashing, checking a password is deliberately
+    /// slow, so async callers should run this on a blocking thread.
+    pub fn password_matches(&self, password: Option<&str>) -> bool {
+        let Some(hash) = &self.password_hash else {
+            return true;
+        };
+        let (Some(password), Ok(hash)) = (password, PasswordHash::new(hash)) else {
+            return false;
+        };
+        Argon2::default()
+            .verify_password(password.as_bytes(), &hash)
+            .is_ok()
+    }
+
+    /// Returns true if the token authorizes management of the payload.
+    ///
+    /// Payloads created without a token cannot be managed at all.
+    pub fn is_managed_by(&self, token: &ManagementToken) -> bool {
+        self.management_token_hash
+            .as_deref()
+            .is_some_and(|hash| token.matches_hash(hash))
+    }
+
+    /// Records a wrong password at the current time.
+    pub fn record_failed_unlock(&mut self) {
+        self.failed_unlocks += 1;
+        self.failed_unlock_at = Some(Utc::now());
+    }
+
+    /// Reserves a password attempt made at `at`, counting it as a wrong
+    /// password until it is released, so attempts made while it is checked
+    /// are throttled as if it failed. Returns false without reserving
+    /// anything if attempts are currently throttled.
+    pub fn reserve_unlock_attempt(&mut self, at: DateTime<Utc>) -> bool {
+        if self.unlock_retry_after().is_some() {
+            return false;
+        }
+        self.failed_unlocks += 1;
+        self.failed_unlock_at = Some(at);
+        true
+    }
+
+    /// Releases an attempt reserved at `at` that turned out to be right.
+    ///
+    /// Restores the time of the failed unlock before it, `previous`, unless
+    /// another attempt has been recorded since.
+    pub fn release_unlock_attempt(&mut self, at: DateTime<Utc>, previous: Option<DateTime<Utc>>) {
+        self.failed_unlocks = self.failed_unlocks.saturating_sub(1);
+        if self.failed_unlock_at == Some(at) {
+            self.failed_unlock_at = previous;
+        }
+    }
+
+    /// Returns when the next password attempt will be accepted, if attempts
+    /// are currently throttled.
+    ///
+    /// The first few wrong passwords are free; after that, each failure
+    /// doubles the wait before the next attempt, up to one hour.
+    pub fn unlock_retry_after(&self) -> Option<DateTime<Utc>> {
+        let throttled = self.failed_unlocks.checked_sub(FREE_UNLOCK_ATTEMPTS)?;
+        let backoff = UNLOCK_BACKOFF_SECONDS
+            .checked_shl(throttled.min(32) as u32)
+            .unwrap_or(MAX_UNLOCK_BACKOFF_SECONDS)
+            .min(MAX_UNLOCK_BACKOFF_SECONDS);
+        let retry_after = self.failed_unlock_at? + Duration::seconds(backoff);
+        (retry_after > Utc::now()).then_some(retry_after)
     }
 
     /// Returns the unique identifier of the payload.
@@ -152,16 +560,90 @@ impl Payload {
         &self.hash_id
     }
 
-    /// Returns the content of the payload.
-    pub fn content(&self) -> &str {
+    /// Returns the raw content of the payload.
+    pub fn content(&self) -> &[u8] {
         &self.content
     }
 
+    /// Restores content loaded from storage.
+    ///
+    /// Repositories keep content apart from the serialized metadata, so after
+    /// deserializing a payload they put its bytes back with this method.
+    pub(crate) fn restore_content(&mut self, content: Vec<u8>) {
+        self.content = content;
+    }
+
+    /// Restores view tracking kept apart from the serialized metadata.
+    ///
+    /// Repositories that update view counts in place, without rewriting the
+    /// whole payload, use this to apply the stored values after loading.
+    pub(crate) fn restore_views(&mut self, view_count: u64, viewed_at: Option<DateTime<Utc>>) {
+        self.view_count = view_count;
+        self.viewed_at = viewed_at;
+    }
+
+    /// Restores failed unlock tracking kept apart from the serialized
+    /// metadata, like [`Payload::restore_views`].
+    pub(crate) fn restore_failed_unlocks(
+        &mut self,
+        failed_unlocks: u64,
+        failed_unlock_at: Option<DateTime<Utc>>,
+    ) {
+        self.failed_unlocks = failed_unlocks;
+        self.failed_unlock_at = failed_unlock_at;
+    }
+
+    /// Restores an expiry change kept apart from the serialized metadata,
+    /// like [`Payload::restore_views`].
+    pub(crate) fn restore_expiry(
+        &mut self,
+        expiry_time: DateTime<Utc>,
+        expiry_limit: Option<DateTime<Utc>>,
+        expiry_deadline: Option<DateTime<Utc>>,
+        updated_at: DateTime<Utc>,
+    ) {
+        self.expiry_time = expiry_time;
+        self.expiry_limit = expiry_limit;
+        self.expiry_deadline = expiry_deadline;
+        self.updated_at = updated_at;
+    }
+
+    /// Restores a content replacement made on another copy of the payload,
+    /// like [`Payload::restore_views`].
+    ///
+    /// Repositories use this to apply a replacement to the stored payload
+    /// without losing views recorded in the meantime.
+    pub(crate) fn restore_revision(&mut self, replaced: &Payload) {
+        self.content = replaced.content.clone();
+        self.mime_type = replaced.mime_type.clone();
+        self.revision = replaced.revision;
+        self.version = replaced.version;
+        self.updated_at = replaced.updated_at;
+    }
+
+    /// Restores the version kept apart from the serialized metadata, like
+    /// [`Payload::restore_views`].
+    pub(crate) fn restore_version(&mut self, version: u64) {
+        self.version = version;
+    }
+
     /// Returns the MIME type of the payload.
     pub fn mime_type(&self) -> &MimeType {
         &self.mime_type
     }
 
+    /// Returns the number of the current content revision.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Returns the number of the current version.
+    ///
+    /// Views do not change the version; changes by the owner do.
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
     /// Returns when the payload was created.
     pub fn created_at(&self) -> DateTime<Utc> {
         self.created_at
@@ -181,6 +663,81 @@ impl Payload {
     pub fn expiry_time(&self) -> DateTime<Utc> {
         self.expiry_time
     }
+
+    /// Returns how views move the expiry time.
+    pub fn expiry_mode(&self) -> ExpiryMode {
+        self.expiry_mode
+    }
+
+    /// Returns the latest time the payload can expire, however it is read.
+    ///
+    /// This is the expiry time itself unless the expiry moves with views.
+    pub fn expiry_limit(&self) -> DateTime<Utc> {
+        self.expiry_limit.unwrap_or(self.expiry_time)
+    }
+
+    /// Returns the expiry time set by the expiry mode, if it has set one.
+    pub fn expiry_deadline(&self) -> Option<DateTime<Utc>> {
+        self.expiry_deadline
+    }
+
+    /// Returns true if the payload is deleted on its first successful read.
+    pub fn is_one_time(&self) -> bool {
+        self.one_time
+    }
+
+    /// Returns how many times the payload has been viewed.
+    pub fn view_count(&self) -> u64 {
+        self.view_count
+    }
+
+    /// Returns the maximum number of views allowed, if limited.
+    pub fn max_views(&self) -> Option<u64> {
+        self.max_views
+    }
+
+    /// Returns true if a password is required to read the payload.
+    pub fn is_password_protected(&self) -> bool {
+        self.password_hash.is_some()
+    }
+
+    /// Returns how many wrong passwords have been given for the payload.
+    pub fn failed_unlocks(&self) -> u64 {
+        self.failed_unlocks
+    }
+
+    /// Returns when the last wrong password was given, if ever.
+    pub fn failed_unlock_at(&self) -> Option<DateTime<Utc>> {
+        self.failed_unlock_at
+    }
+
+    /// Returns the share code resolving to the payload, if any.
+    pub fn share_code(&self) -> Option<&ShareCode> {
+        self.share_code.as_ref()
+    }
+
+    /// Returns the alias resolving to the payload, if any.
+    pub fn alias(&self) -> Option<&Alias> {
+        self.alias.as_ref()
+    }
+
+    /// Returns the names resolving to the payload: its alias and share code.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.alias
+            .iter()
+            .map(Alias::as_str)
+            .chain(self.share_code.iter().map(ShareCode::as_str))
+    }
+
+    /// Returns the description of what the payload is for.
+    pub fn metadata(&self) -> &Metadata {
+        &self.metadata
+    }
+}
+
+/// Revision and version of payloads stored before either was tracked
+fn first_revision() -> u64 {
+    1
 }
 
 #[cfg(test)]
@@ -189,11 +746,11 @@ mod tests {
 
     #[test]
     fn test_create_payload_with_defaults() {
-        let content = "Test content".to_string();
+        let content = b"Test content".to_vec();
         let payload = Payload::new(content.clone(), None, None).unwrap();
 
         assert_eq!(payload.content(), content);
-        assert!(matches!(payload.mime_type(), MimeType::TextPlain));
+        assert_eq!(payload.mime_type(), &MimeType::text_plain());
         assert!(payload.viewed_at().is_none());
         assert!(!payload.is_expired());
     }
@@ -201,20 +758,20 @@ mod tests {
     #[test]
     fn test_create_payload_with_custom_mime_type() {
         let payload = Payload::new(
-            "Test content".to_string(),
+            b"Test content".to_vec(),
             Some("text/html".to_string()),
             None,
         )
         .unwrap();
 
-        assert!(matches!(payload.mime_type(), MimeType::TextHtml));
+        assert_eq!(payload.mime_type().essence(), "text/html");
     }
 
     #[test]
     fn test_create_payload_with_invalid_mime_type() {
         let result = Payload::new(
-            "Test content".to_string(),
-            Some("invalid/type".to_string()),
+            b"Test content".to_vec(),
+            Some("invalid".to_string()),
             None,
         );
 
@@ -223,23 +780,268 @@ mod tests {
 
     #[test]
     fn test_create_payload_with_empty_content() {
-        let result = Payload::new("".to_string(), None, None);
+        let result = Payload::new(Vec::new(), None, None);
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_create_payload_with_binary_content() {
+        let content = vec![0x89, b'P', b'N', b'G', 0x00, 0xff, 0xfe];
+        let payload = Payload::new(
+            content.clone(),
+            Some("image/png".to_string()),
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(payload.content(), content.as_slice());
+    }
+
+    #[test]
+    fn test_payloads_are_not_one_time_by_default() {
+        let payload = Payload::new(b"Test content".to_vec(), None, None).unwrap();
+        assert!(!payload.is_one_time());
+        assert!(payload.with_one_time(true).is_one_time());
+    }
+
     #[test]
     fn test_mark_viewed_updates_viewed_at() {
-        let mut payload = Payload::new("Test content".to_string(), None, None).unwrap();
+        let mut payload = Payload::new(b"Test content".to_vec(), None, None).unwrap();
         assert!(payload.viewed_at().is_none());
 
         payload.mark_viewed();
         assert!(payload.viewed_at().is_some());
     }
 
+    #[test]
+    fn test_mark_viewed_counts_views() {
+        let mut payload = Payload::new(b"Test content".to_vec(), None, None).unwrap();
+        assert_eq!(payload.view_count(), 0);
+
+        payload.mark_viewed();
+        payload.mark_viewed();
+        assert_eq!(payload.view_count(), 2);
+    }
+
+    #[test]
+    fn test_view_limit() {
+        let mut payload = Payload::new(b"Test content".to_vec(), None, None)
+            .unwrap()
+            .with_max_views(Some(2));
+
+        payload.mark_viewed();
+        assert!(!payload.view_limit_reached());
+        payload.mark_viewed();
+        assert!(payload.view_limit_reached());
+
+        let mut unlimited = Payload::new(b"Test content".to_vec(), None, None).unwrap();
+        for _ in 0..10 {
+            unlimited.mark_viewed();
+        }
+        assert!(!unlimited.view_limit_reached());
+    }
+
+    #[test]
+    fn test_password_is_stored_hashed() {
+        let payload = Payload::new(b"Test content".to_vec(), None, None)
+            .unwrap()
+            .with_password("correct horse")
+            .unwrap();
+
+        assert!(payload.is_password_protected());
+        let serialized = serde_json::to_string(&payload).unwrap();
+        assert!(!serialized.contains("correct horse"));
+        assert!(serialized.contains("$argon2id$"));
+        assert!(payload.password_matches(Some("correct horse")));
+        assert!(!payload.password_matches(Some("battery staple")));
+    }
+
+    #[test]
+    fn test_metadata_is_serialized() {
+        let metadata = Metadata::default()
+            .with_filename("notes.txt")
+            .unwrap()
+            .with_title(Some("Notes".to_string()));
+        let payload = Payload::new(b"Test content".to_vec(), None, None)
+            .unwrap()
+            .with_metadata(metadata.clone());
+
+        let serialized = serde_json::to_value(&payload).unwrap();
+        let deserialized: Payload = serde_json::from_value(serialized).unwrap();
+        assert_eq!(deserialized.metadata(), &metadata);
+    }
+
+    #[test]
+    fn test_unlock_throttling() {
+        let mut payload = Payload::new(b"Test content".to_vec(), None, None).unwrap();
+
+        for _ in 0..FREE_UNLOCK_ATTEMPTS {
+            assert!(payload.unlock_retry_after().is_none());
+            payload.record_failed_unlock();
+        }
+        let retry_after = payload.unlock_retry_after().unwrap();
+        assert!(retry_after > Utc::now());
+        assert!(retry_after <= Utc::now() + Duration::seconds(UNLOCK_BACKOFF_SECONDS));
+
+        // Each further failure doubles the wait
+        payload.record_failed_unlock();
+        let retry_after = payload.unlock_retry_after().unwrap();
+        assert!(retry_after > Utc::now() + Duration::seconds(UNLOCK_BACKOFF_SECONDS));
+        assert!(retry_after <= Utc::now() + Duration::seconds(2 * UNLOCK_BACKOFF_SECONDS));
+
+        // Long runs of failures are capped
+        payload.restore_failed_unlocks(1000, Some(Utc::now()));
+        let retry_after = payload.unlock_retry_after().unwrap();
+        assert!(retry_after <= Utc::now() + Duration::seconds(MAX_UNLOCK_BACKOFF_SECONDS));
+    }
+
+    #[test]
+    fn test_unlock_attempts_are_reserved_and_released() {
+        let mut payload = Payload::new(b"Test content".to_vec(), None, None).unwrap();
+        let first = Utc::now();
+        assert!(payload.reserve_unlock_attempt(first));
+
+        // A later attempt released first leaves the earlier one in place
+        let second = first + Duration::milliseconds(1);
+        assert!(payload.reserve_unlock_attempt(second));
+        payload.release_unlock_attempt(second, Some(first));
+        assert_eq!(payload.failed_unlocks(), 1);
+        assert_eq!(payload.failed_unlock_at(), Some(first));
+
+        // Releasing an attempt another has been recorded after keeps the time
+        assert!(payload.reserve_unlock_attempt(second));
+        payload.release_unlock_attempt(first, None);
+        assert_eq!(payload.failed_unlocks(), 1);
+        assert_eq!(payload.failed_unlock_at(), Some(second));
+
+        // Throttled attempts are not reserved
+        for _ in 0..FREE_UNLOCK_ATTEMPTS {
+            payload.record_failed_unlock();
+        }
+        assert!(!payload.reserve_unlock_attempt(Utc::now()));
+        assert_eq!(payload.failed_unlocks(), FREE_UNLOCK_ATTEMPTS + 1);
+    }
+
+    #[test]
+    fn test_management_token_is_stored_hashed() {
+        let token = ManagementToken::generate();
+        let payload = Payload::new(b"Test content".to_vec(), None, None).unwrap();
+        assert!(!payload.is_managed_by(&token));
+
+        let payload = payload.with_management_token(&token);
+        assert!(payload.is_managed_by(&token));
+        let serialized = serde_json::to_string(&payload).unwrap();
+        assert!(!serialized.contains(token.as_string()));
+    }
+
+    #[test]
+    fn test_idle_timeout_moves_expiry() {
+        let limit = Utc::now() + Duration::hours(1);
+        let mut payload = Payload::new(b"Test content".to_vec(), None, Some(limit))
+            .unwrap()
+            .with_expiry_mode(ExpiryMode::Idle { timeout_secs: 600 });
+        let created_expiry = payload.expiry_time();
+        assert!(created_expiry <= payload.created_at() + Duration::minutes(10));
+
+        payload.mark_viewed();
+        assert!(payload.expiry_time() > created_expiry);
+        assert_eq!(payload.expiry_time(), payload.viewed_at().unwrap() + Duration::minutes(10));
+        assert_eq!(payload.expiry_limit(), limit);
+
+        // Views never push the expiry past the limit
+        payload.set_expiry_time(Utc::now() + Duration::minutes(5));
+        payload.mark_viewed();
+        assert_eq!(payload.expiry_time(), payload.expiry_limit());
+
+        // Going unread for the timeout expires the payload
+        payload.restore_expiry(
+            Utc::now() - Duration::seconds(1),
+            Some(limit),
+            Some(Utc::now() - Duration::seconds(1)),
+            payload.updated_at(),
+        );
+        assert!(payload.is_expired());
+    }
+
+    #[test]
+    fn test_expiry_after_first_view() {
+        let limit = Utc::now() + Duration::hours(1);
+        let mut payload = Payload::new(b"Test content".to_vec(), None, Some(limit))
+            .unwrap()
+            .with_expiry_mode(ExpiryMode::AfterFirstView { delay_secs: 300 });
+        assert_eq!(payload.expiry_time(), limit);
+
+        payload.mark_viewed();
+        let countdown = payload.expiry_time();
+        assert_eq!(countdown, payload.viewed_at().unwrap() + Duration::minutes(5));
+
+        // Later views leave the countdown alone
+        payload.mark_viewed();
+        assert_eq!(payload.expiry_time(), countdown);
+
+        // Extending the limit does not stop a running countdown
+        payload.set_expiry_time(limit + Duration::hours(1));
+        assert_eq!(payload.expiry_time(), countdown);
+        assert_eq!(payload.expiry_limit(), limit + Duration::hours(1));
+    }
+
+    #[test]
+    fn test_fixed_expiry_ignores_views() {
+        let limit = Utc::now() + Duration::hours(1);
+        let mut payload = Payload::new(b"Test content".to_vec(), None, Some(limit)).unwrap();
+
+        payload.mark_viewed();
+        assert_eq!(payload.expiry_time(), limit);
+        assert_eq!(payload.expiry_limit(), limit);
+        assert!(payload.expiry_deadline().is_none());
+    }
+
+    #[test]
+    fn test_replace_content_keeps_revision() {
+        let mut payload = Payload::new(b"v1".to_vec(), None, None).unwrap();
+        let created_at = payload.updated_at();
+        assert_eq!(payload.revision(), 1);
+
+        let previous = payload
+            .replace_content(b"<p>v2</p>".to_vec(), Some("text/html".to_string()))
+            .unwrap();
+        assert_eq!(previous.number(), 1);
+        assert_eq!(previous.content(), b"v1");
+        assert_eq!(previous.mime_type(), &MimeType::text_plain());
+        assert_eq!(previous.created_at(), created_at);
+        assert_eq!(payload.revision(), 2);
+        assert_eq!(payload.content(), b"<p>v2</p>");
+        assert_eq!(payload.mime_type(), &MimeType::try_from("text/html").unwrap());
+        assert!(payload.updated_at() > created_at);
+
+        // Without a MIME type the current one is kept
+        payload.replace_content(b"<p>v3</p>".to_vec(), None).unwrap();
+        assert_eq!(payload.mime_type(), &MimeType::try_from("text/html").unwrap());
+
+        assert!(payload.replace_content(Vec::new(), None).is_err());
+        assert_eq!(payload.revision(), 3);
+    }
+
+    #[test]
+    fn test_version_counts_owner_changes() {
+        let mut payload = Payload::new(b"v1".to_vec(), None, None).unwrap();
+        assert_eq!(payload.version(), 1);
+
+        payload.mark_viewed();
+        payload.record_failed_unlock();
+        assert_eq!(payload.version(), 1);
+
+        payload.replace_content(b"v2".to_vec(), None).unwrap();
+        assert_eq!(payload.version(), 2);
+        payload.set_expiry_time(Utc::now() + Duration::hours(2));
+        assert_eq!(payload.version(), 3);
+        assert_eq!(payload.revision(), 2);
+    }
+
     #[test]
     fn test_payload_expires() {
         let payload = Payload::new(
-            "Test content".to_string(),
+            b"Test content".to_vec(),
             None,
             Some(Utc::now() - Duration::hours(1)),
         )
@@ -0,0 +1,85 @@
+//! Revision module represents earlier content of a payload.
+//!
+//! Replacing the content of a payload keeps what it replaces as a numbered
+//! revision, so a link to the payload can keep pointing at the latest
+//! content while older content stays retrievable. Revisions are numbered
+//! from 1, the content the payload was created with, and expire together
+//! with their payload.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::mime_type::MimeType;
+
+/// Number of earlier revisions kept for each payload by default
+pub const DEFAULT_MAX_REVISIONS: usize = 20;
+
+/// A numbered version of a payload's content.
+///
+/// # Examples
+///
+/// ```
+/// use jump::domain::payload::Payload;
+///
+/// let mut payload = Payload::new(b"v1".to_vec(), None, None).unwrap();
+/// let previous = payload.replace_content(b"v2".to_vec(), None).unwrap();
+///
+/// assert_eq!(previous.number(), 1);
+/// assert_eq!(previous.content(), b"v1");
+/// assert_eq!(payload.revision(), 2);
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Revision {
+    /// Number of the revision, starting at 1
+    number: u64,
+
+    /// The content of the revision, as raw bytes.
+    ///
+    /// Like payload content, it is stored apart from the serialized form.
+    #[serde(skip)]
+    content: Vec<u8>,
+
+    /// The MIME type of the content
+    mime_type: MimeType,
+
+    /// When the content was stored
+    created_at: DateTime<Utc>,
+}
+
+impl Revision {
+    /// Creates a revision from content that was stored at `created_at`.
+    pub fn new(number: u64, content: Vec<u8>, mime_type: MimeType, created_at: DateTime<Utc>) -> Self {
+        Self {
+            number,
+            content,
+            mime_type,
+            created_at,
+        }
+    }
+
+    /// Restores content loaded from storage, which keeps it apart from the
+    /// serialized metadata as it does for payloads.
+    pub(crate) fn restore_content(&mut self, content: Vec<u8>) {
+        self.content = content;
+    }
+
+    /// Returns the number of the revision.
+    pub fn number(&self) -> u64 {
+        self.number
+    }
+
+    /// Returns the raw content of the revision.
+    pub fn content(&self) -> &[u8] {
+        &self.content
+    }
+
+    /// Returns the MIME type of the revision.
+    pub fn mime_type(&self) -> &MimeType {
+        &self.mime_type
+    }
+
+    /// Returns when the content of the revision was stored.
+    pub fn created_at(&self) -> DateTime<Utc> {
+        self.created_at
+    }
+}
@@ -0,0 +1,188 @@
+//! ShareCode module provides short, human-readable codes for payloads.
+//!
+//! A share code such as `7-crossover-clockwork` is a number followed by words
+//! from a bundled wordlist, so it can be read out loud over a call and typed
+//! in without mistakes. Codes are aliases: storage maps each code to the
+//! [`HashId`](super::hash_id::HashId) of its payload until the payload
+//! expires.
+//!
+//! With about 23 bits of entropy, codes are far easier to guess than hash
+//! IDs. They are only safe to hand out because lookups by code are
+//! throttled much harder than other requests.
+
+use std::fmt;
+
+use lazy_static::lazy_static;
+use rand::{rngs::OsRng, Rng};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Largest number at the start of a code
+pub const MAX_NUMBER: u32 = 99;
+
+/// Number of words in a code
+pub const WORD_COUNT: usize = 2;
+
+/// Maximum length of input accepted as a code
+pub const MAX_LENGTH: usize = 64;
+
+lazy_static! {
+    /// The bundled wordlist, sorted so words can be looked up by binary search
+    static ref WORDLIST: Vec<&'static str> = include_str!("wordlist.txt").lines().collect();
+}
+
+/// Errors that can occur when parsing a share code.
+#[derive(Debug, Error, PartialEq)]
+pub enum ShareCodeError {
+    /// The input is longer than any code
+    #[error("Share code cannot be longer than {MAX_LENGTH} characters")]
+    TooLong,
+
+    /// The input does not have the shape `number-word-word`
+    #[error("Share code must be a number followed by {WORD_COUNT} words")]
+    Malformed,
+
+    /// The number is not between 1 and [`MAX_NUMBER`]
+    #[error("Share code must start with a number from 1 to {MAX_NUMBER}")]
+    InvalidNumber,
+
+    /// A word is not on the wordlist
+    #[error("Share code contains a word that is not on the wordlist")]
+    UnknownWord,
+}
+
+/// A human-readable code resolving to a payload.
+///
+/// # Examples
+///
+/// ```
+/// use jump::domain::share_code::ShareCode;
+///
+/// let code = ShareCode::generate();
+/// assert_eq!(ShareCode::parse(code.as_str()), Ok(code));
+///
+/// // Codes read out loud may be typed with spaces and capitals
+/// let typed = ShareCode::parse(" 7 Crossover clockwork ").unwrap();
+/// assert_eq!(typed.as_str(), "7-crossover-clockwork");
+/// ```
+#[derive(Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ShareCode(String);
+
+impl ShareCode {
+    /// Generates a new random code.
+    pub fn generate() -> Self {
+        let mut rng = OsRng;
+        let number = rng.gen_range(1..=MAX_NUMBER);
+        let words: Vec<&str> = (0..WORD_COUNT)
+            .map(|_| WORDLIST[rng.gen_range(0..WORDLIST.len())])
+            .collect();
+        ShareCode(format!("{}-{}", number, words.join("-")))
+    }
+
+    /// Parses a code typed in by a person.
+    ///
+    /// Case is ignored, and words may be separated by hyphens or
+    /// whitespace. The code is returned in its canonical form.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `ShareCodeError` describing the first problem found.
+    pub fn parse(val: &str) -> Result<Self, ShareCodeError> {
+        if val.len() > MAX_LENGTH {
+            return Err(ShareCodeError::TooLong);
+        }
+
+        let lowercase = val.to_ascii_lowercase();
+        let parts: Vec<&str> = lowercase
+            .split(|c: char| c == '-' || c.is_whitespace())
+            .filter(|part| !part.is_empty())
+            .collect();
+        let [number, words @ ..] = parts.as_slice() else {
+            return Err(ShareCodeError::Malformed);
+        };
+        if words.len() != WORD_COUNT {
+            return Err(ShareCodeError::Malformed);
+        }
+
+        let number: u32 = number
+            .parse()
+            .ok()
+            .filter(|number| (1..=MAX_NUMBER).contains(number))
+            .ok_or(ShareCodeError::InvalidNumber)?;
+        if words.iter().any(|word| WORDLIST.binary_search(word).is_err()) {
+            return Err(ShareCodeError::UnknownWord);
+        }
+
+        Ok(ShareCode(format!("{}-{}", number, words.join("-"))))
+    }
+
+    /// Returns the canonical string representation of the code.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Entropy of generated codes in bits.
+    pub fn entropy_bits() -> f64 {
+        (MAX_NUMBER as f64).log2() + WORD_COUNT as f64 * (WORDLIST.len() as f64).log2()
+    }
+}
+
+impl fmt::Display for ShareCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl fmt::Debug for ShareCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ShareCode(..)")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wordlist_is_sorted_and_unique() {
+        assert_eq!(WORDLIST.len(), 256);
+        assert!(WORDLIST.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(WORDLIST
+            .iter()
+            .all(|word| !word.is_empty() && word.chars().all(|c| c.is_ascii_lowercase())));
+    }
+
+    #[test]
+    fn test_generated_codes_parse() {
+        for _ in 0..100 {
+            let code = ShareCode::generate();
+            assert_eq!(ShareCode::parse(code.as_str()), Ok(code));
+        }
+    }
+
+    #[test]
+    fn test_parse_normalizes_input() {
+        let canonical = ShareCode::parse("7-crossover-clockwork").unwrap();
+        for typed in ["7 crossover clockwork", "07-CROSSOVER-Clockwork", "  7 - crossover  clockwork\n"] {
+            assert_eq!(ShareCode::parse(typed), Ok(canonical.clone()), "{:?}", typed);
+        }
+    }
+
+    #[test]
+    fn test_parse_rejects_malformed_codes() {
+        assert_eq!(ShareCode::parse(""), Err(ShareCodeError::Malformed));
+        assert_eq!(ShareCode::parse("7-crossover"), Err(ShareCodeError::Malformed));
+        assert_eq!(ShareCode::parse("7-crossover-clockwork-zebra"), Err(ShareCodeError::Malformed));
+        assert_eq!(ShareCode::parse("0-crossover-clockwork"), Err(ShareCodeError::InvalidNumber));
+        assert_eq!(ShareCode::parse("100-crossover-clockwork"), Err(ShareCodeError::InvalidNumber));
+        assert_eq!(ShareCode::parse("crossover-7-clockwork"), Err(ShareCodeError::InvalidNumber));
+        assert_eq!(ShareCode::parse("7-crossover-xylophone"), Err(ShareCodeError::UnknownWord));
+        assert_eq!(ShareCode::parse(&"7-".repeat(40)), Err(ShareCodeError::TooLong));
+    }
+
+    #[test]
+    fn test_entropy_is_low_enough_to_need_throttling() {
+        let bits = ShareCode::entropy_bits();
+        assert!(bits > 22.0 && bits < 23.0, "{}", bits);
+    }
+}
@@ -0,0 +1,994 @@
+//! File system repository implementation.
+//!
+//! This module provides a `Repository` that keeps payloads on local disk, for
+//! deployments that cannot run Redis. Payloads survive restarts and expired
+//! entries are removed by a background sweeper task.
+//!
+//! Each payload is a single file `payloads/{hash_id}.payload` under the data
+//! directory. The file starts with the payload metadata on one line, in a
+//! versioned [`schema`](super::schema) envelope, followed by the raw content
+//! bytes. Files are written to a temporary name, synced and then renamed into
+//! place, so a crash never leaves a partially written payload behind.
+//!
+//! Earlier revisions of a payload's content are kept in files
+//! `revisions/{hash_id}.{n}.revision` of the same layout. They are removed
+//! together with their payload, or by the sweeper once it is gone.
+//!
+//! Aliases are small JSON files `aliases/{alias}.alias` holding the hash ID
+//! they resolve to and their expiry time. They are created with hard links,
+//! so reserving an alias is atomic as well.
+//!
+//! Read-modify-write updates such as recording a view are serialized by a
+//! lock shared between clones, so a data directory must only be used by one
+//! process at a time.
+
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tokio::fs;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
+
+use crate::{
+    application::repository::{
+        check_version, oldest_kept_revision, reserve_unlock_attempt, Repository, RepositoryError,
+    },
+    domain::{hash_id::HashId, payload::Payload, revision::Revision},
+    infrastructure::schema::{SchemaError, PAYLOADS, REVISIONS},
+};
+
+/// Extension of committed payload files
+const PAYLOAD_EXTENSION: &str = "payload";
+
+/// Extension of committed alias files
+const ALIAS_EXTENSION: &str = "alias";
+
+/// Extension of committed revision files
+const REVISION_EXTENSION: &str = "revision";
+
+/// Extension of files that are still being written
+const TEMP_EXTENSION: &str = "tmp";
+
+/// File repository configuration
+#[derive(Clone, Debug)]
+pub struct FileConfig {
+    /// Directory under which payloads are stored
+    pub data_dir: PathBuf,
+    /// Interval between expiry sweeps in seconds
+    pub sweep_interval: u64,
+}
+
+impl Default for FileConfig {
+    fn default() -> Self {
+        Self {
+            data_dir: PathBuf::from("./data"),
+            sweep_interval: 60,
+        }
+    }
+}
+
+/// File repository errors
+#[derive(Error, Debug)]
+pub enum FileError {
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("JSON serialization error: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Storage schema error: {0}")]
+    Schema(#[from] SchemaError),
+    #[error("Corrupt payload file: {0}")]
+    Corrupt(String),
+    #[error("Hash ID cannot be used as a file name: {0}")]
+    InvalidHashId(String),
+    #[error("Alias cannot be used as a file name: {0}")]
+    InvalidAlias(String),
+}
+
+impl From<FileError> for RepositoryError {
+    fn from(err: FileError) -> Self {
+        match err {
+            FileError::Io(e) if e.kind() == std::io::ErrorKind::StorageFull => {
+                RepositoryError::QuotaExceeded(e.to_string())
+            }
+            FileError::Io(e) => RepositoryError::Unavailable(e.to_string()),
+            FileError::Json(e) => RepositoryError::Serialization(e.to_string()),
+            FileError::Schema(e) => e.into(),
+            FileError::Corrupt(msg) => RepositoryError::Serialization(msg),
+            FileError::InvalidHashId(_) | FileError::InvalidAlias(_) => {
+                RepositoryError::Internal(err.to_string())
+            }
+        }
+    }
+}
+
+/// Contents of an alias file
+#[derive(Serialize, Deserialize)]
+struct AliasRecord {
+    hash_id: HashId,
+    expiry_time: DateTime<Utc>,
+}
+
+/// Returns true if `name` can safely be used as a file name stem
+fn is_safe_file_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 128
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// File system repository for storing and retrieving payloads
+///
+/// Clones share the same data directory.
+#[derive(Clone)]
+pub struct FileRepository {
+    payload_dir: Arc<PathBuf>,
+    revision_dir: Arc<PathBuf>,
+    alias_dir: Arc<PathBuf>,
+    sweep_interval: u64,
+    /// Held while updating, taking or deleting payload files
+    update_lock: Arc<Mutex<()>>,
+}
+
+impl FileRepository {
+    /// Open a file repository, creating the data directory if needed.
+    ///
+    /// Temporary files left over from writes interrupted by a crash are
+    /// removed.
+    pub fn new(config: FileConfig) -> Result<Self, FileError> {
+        let payload_dir = config.data_dir.join("payloads");
+        let revision_dir = config.data_dir.join("revisions");
+        let alias_dir = config.data_dir.join("aliases");
+
+        for dir in [&payload_dir, &revision_dir, &alias_dir] {
+            std::fs::create_dir_all(dir)?;
+            for entry in std::fs::read_dir(dir)? {
+                let path = entry?.path();
+                if path.extension().is_some_and(|ext| ext == TEMP_EXTENSION) {
+                    warn!(path = %path.display(), "Removing incomplete write");
+                    std::fs::remove_file(&path)?;
+                }
+            }
+        }
+
+        Ok(Self {
+            payload_dir: Arc::new(payload_dir),
+            revision_dir: Arc::new(revision_dir),
+            alias_dir: Arc::new(alias_dir),
+            sweep_interval: config.sweep_interval,
+            update_lock: Arc::new(Mutex::new(())),
+        })
+    }
+
+    /// Spawn the background task that periodically removes expired payloads.
+    ///
+    /// The task stops on its own once every clone of the repository has been
+    /// dropped. Must be called from within a Tokio runtime.
+    pub fn start_sweeper(&self) -> JoinHandle<()> {
+        let (payload_dir, revision_dir, alias_dir) = (
+            self.payload_dir.clone(),
+            self.revision_dir.clone(),
+            self.alias_dir.clone(),
+        );
+        let sweep_interval = self.sweep_interval;
+        // Clones share the update lock, so it outlives them only while one is held
+        let update_lock: Weak<Mutex<()>> = Arc::downgrade(&self.update_lock);
+        let period = Duration::from_secs(self.sweep_interval.max(1));
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            loop {
+                interval.tick().await;
+                let Some(update_lock) = update_lock.upgrade() else {
+                    debug!("File repository dropped, stopping expiry sweeper");
+                    break;
+                };
+                let repo = Self {
+                    payload_dir: payload_dir.clone(),
+                    revision_dir: revision_dir.clone(),
+                    alias_dir: alias_dir.clone(),
+                    sweep_interval,
+                    update_lock,
+                };
+                match repo.sweep_expired().await {
+                    Ok(0) => {}
+                    Ok(removed) => info!(removed, "Swept expired payloads from disk"),
+                    Err(e) => error!(error = %e, "Failed to sweep expired payloads"),
+                }
+            }
+        })
+    }
+
+    /// Remove all expired payloads now, returning how many were removed
+    pub async fn sweep_expired(&self) -> Result<usize, FileError> {
+        let now = Utc::now();
+        let mut removed = 0;
+        let mut entries = fs::read_dir(self.payload_dir.as_ref()).await?;
+
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path.extension().is_none_or(|ext| ext != PAYLOAD_EXTENSION) {
+                continue;
+            }
+
+            let expired = match Self::read_metadata(&path).await {
+                Ok(Some(payload)) => payload.expiry_time() <= now,
+                // Removed concurrently
+                Ok(None) => false,
+                Err(e) => {
+                    warn!(path = %path.display(), error = %e, "Removing unreadable payload file");
+                    true
+                }
+            };
+
+            if expired && Self::remove_file(&path).await? {
+                removed += 1;
+            }
+        }
+
+        // Revisions of payloads that are gone are removed as well
+        let mut entries = fs::read_dir(self.revision_dir.as_ref()).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path.extension().is_none_or(|ext| ext != REVISION_EXTENSION) {
+                continue;
+            }
+
+            let hash_id = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.split('.').next())
+                .map(|id| HashId::from_string(id.to_string()));
+            let orphaned = match hash_id.map(|hash_id| self.payload_path(&hash_id)) {
+                Some(Ok(payload_path)) => !fs::try_exists(&payload_path).await?,
+                _ => true,
+            };
+            if orphaned {
+                Self::remove_file(&path).await?;
+            }
+        }
+
+        // Expired aliases are removed as well, but not counted
+        let mut entries = fs::read_dir(self.alias_dir.as_ref()).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path.extension().is_none_or(|ext| ext != ALIAS_EXTENSION) {
+                continue;
+            }
+
+            let expired = match Self::read_alias(&path).await {
+                Ok(Some(record)) => record.expiry_time <= now,
+                Ok(None) => false,
+                Err(e) => {
+                    warn!(path = %path.display(), error = %e, "Removing unreadable alias file");
+                    true
+                }
+            };
+            if expired {
+                Self::remove_file(&path).await?;
+            }
+        }
+
+        Ok(removed)
+    }
+
+    /// Path of the file holding a payload
+    fn payload_path(&self, hash_id: &HashId) -> Result<PathBuf, FileError> {
+        let id = hash_id.as_string();
+        if !is_safe_file_name(id) {
+            return Err(FileError::InvalidHashId(id.to_string()));
+        }
+
+        Ok(self.payload_dir.join(format!("{}.{}", id, PAYLOAD_EXTENSION)))
+    }
+
+    /// Path of the file holding a revision of a payload
+    fn revision_path(&self, hash_id: &HashId, number: u64) -> Result<PathBuf, FileError> {
+        let id = hash_id.as_string();
+        if !is_safe_file_name(id) {
+            return Err(FileError::InvalidHashId(id.to_string()));
+        }
+
+        Ok(self.revision_dir.join(format!("{}.{}.{}", id, number, REVISION_EXTENSION)))
+    }
+
+    /// Remove the revisions kept for a payload at revision `current`
+    async fn remove_revisions(&self, hash_id: &HashId, current: u64) -> Result<(), FileError> {
+        for number in 1..current {
+            Self::remove_file(&self.revision_path(hash_id, number)?).await?;
+        }
+        Ok(())
+    }
+
+    /// Remove the alias files of a removed payload, unless they have expired
+    /// and been reserved for another payload since. Must be called while
+    /// holding the update lock.
+    async fn remove_names(&self, payload: &Payload) -> Result<(), FileError> {
+        for name in payload.names() {
+            let Ok(path) = self.alias_path(name) else {
+                continue;
+            };
+            if matches!(Self::read_alias(&path).await?, Some(record) if record.hash_id == *payload.hash_id()) {
+                Self::remove_file(&path).await?;
+            }
+        }
+        Self::sync_dir(&self.alias_dir).await
+    }
+
+    /// Read a revision file
+    async fn read_revision(path: &Path) -> Result<Option<Revision>, FileError> {
+        let bytes = match fs::read(path).await {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        let split = bytes
+            .iter()
+            .position(|&b| b == b'\n')
+            .ok_or_else(|| FileError::Corrupt(path.display().to_string()))?;
+        let mut revision: Revision = REVISIONS.decode(&bytes[..split])?;
+        revision.restore_content(bytes[split + 1..].to_vec());
+
+        Ok(Some(revision))
+    }
+
+    /// Path of the file holding an alias
+    fn alias_path(&self, alias: &str) -> Result<PathBuf, FileError> {
+        if !is_safe_file_name(alias) {
+            return Err(FileError::InvalidAlias(alias.to_string()));
+        }
+
+        Ok(self.alias_dir.join(format!("{}.{}", alias, ALIAS_EXTENSION)))
+    }
+
+    /// Read an alias file
+    async fn read_alias(path: &Path) -> Result<Option<AliasRecord>, FileError> {
+        match fs::read(path).await {
+            Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Read only the metadata line of a payload file
+    async fn read_metadata(path: &Path) -> Result<Option<Payload>, FileError> {
+        let file = match fs::File::open(path).await {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut meta = Vec::new();
+        BufReader::new(file).read_until(b'\n', &mut meta).await?;
+        if meta.pop() != Some(b'\n') {
+            return Err(FileError::Corrupt(path.display().to_string()));
+        }
+
+        Ok(Some(PAYLOADS.decode(&meta)?))
+    }
+
+    /// Read a complete payload file
+    async fn read_payload(path: &Path) -> Result<Option<Payload>, FileError> {
+        let bytes = match fs::read(path).await {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        let split = bytes
+            .iter()
+            .position(|&b| b == b'\n')
+            .ok_or_else(|| FileError::Corrupt(path.display().to_string()))?;
+        let mut payload: Payload = PAYLOADS.decode(&bytes[..split])?;
+        payload.restore_content(bytes[split + 1..].to_vec());
+
+        Ok(Some(payload))
+    }
+
+    /// Write a payload to a synced temporary file next to `path`, returning
+    /// the temporary file's path
+    async fn write_temp(path: &Path, payload: &Payload) -> Result<PathBuf, FileError> {
+        let meta = PAYLOADS.encode(payload)?;
+        Self::write_temp_parts(path, &[&meta, b"\n", payload.content()]).await
+    }
+
+    /// Write bytes to a synced temporary file next to `path`, returning the
+    /// temporary file's path
+    async fn write_temp_parts(path: &Path, parts: &[&[u8]]) -> Result<PathBuf, FileError> {
+        let temp_path = path.with_extension(format!("{}.{}", Uuid::new_v4().simple(), TEMP_EXTENSION));
+
+        let result = async {
+            let mut file = fs::File::create(&temp_path).await?;
+            for part in parts {
+                file.write_all(part).await?;
+            }
+            file.sync_all().await?;
+            Ok::<_, FileError>(())
+        }
+        .await;
+
+        match result {
+            Ok(()) => Ok(temp_path),
+            Err(e) => {
+                let _ = fs::remove_file(&temp_path).await;
+                Err(e)
+            }
+        }
+    }
+
+    /// Atomically write a payload file
+    async fn write_payload(&self, path: &Path, payload: &Payload) -> Result<(), FileError> {
+        let temp_path = Self::write_temp(path, payload).await?;
+
+        if let Err(e) = fs::rename(&temp_path, path).await {
+            let _ = fs::remove_file(&temp_path).await;
+            return Err(e.into());
+        }
+        Self::sync_dir(&self.payload_dir).await
+    }
+
+    /// Atomically write a payload file unless one already exists.
+    ///
+    /// Hard links fail if the target exists, which makes the check and the
+    /// write one step. Returns false if the payload file was already taken.
+    async fn write_new_payload(&self, path: &Path, payload: &Payload) -> Result<bool, FileError> {
+        let temp_path = Self::write_temp(path, payload).await?;
+
+        let mut result = fs::hard_link(&temp_path, path).await;
+        if matches!(&result, Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists) {
+            // An expired payload that has not been swept yet does not count
+            let expired = matches!(
+                Self::read_metadata(path).await,
+                Ok(Some(existing)) if existing.expiry_time() <= Utc::now()
+            );
+            if expired && Self::remove_file(path).await? {
+                result = fs::hard_link(&temp_path, path).await;
+            }
+        }
+        let _ = fs::remove_file(&temp_path).await;
+
+        match result {
+            Ok(()) => {
+                Self::sync_dir(&self.payload_dir).await?;
+                Ok(true)
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Atomically write an alias file unless an unexpired one already exists.
+    ///
+    /// Works like [`FileRepository::write_new_payload`]. Returns false if
+    /// the alias was already taken.
+    async fn write_new_alias(&self, path: &Path, record: &AliasRecord) -> Result<bool, FileError> {
+        let temp_path = Self::write_temp_parts(path, &[&serde_json::to_vec(record)?]).await?;
+
+        let mut result = fs::hard_link(&temp_path, path).await;
+        if matches!(&result, Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists) {
+            let expired = matches!(
+                Self::read_alias(path).await,
+                Ok(Some(existing)) if existing.expiry_time <= Utc::now()
+            );
+            if expired && Self::remove_file(path).await? {
+                result = fs::hard_link(&temp_path, path).await;
+            }
+        }
+        let _ = fs::remove_file(&temp_path).await;
+
+        match result {
+            Ok(()) => {
+                Self::sync_dir(&self.alias_dir).await?;
+                Ok(true)
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Apply an update to a stored payload under the update lock, returning
+    /// the updated payload
+    async fn update(
+        &self,
+        hash_id: &HashId,
+        update: impl FnOnce(&mut Payload),
+    ) -> Result<Option<Payload>, FileError> {
+        let Ok(path) = self.payload_path(hash_id) else {
+            return Ok(None);
+        };
+
+        let _guard = self.update_lock.lock().await;
+        let Some(mut payload) = Self::read_payload(&path).await? else {
+            return Ok(None);
+        };
+        if payload.expiry_time() <= Utc::now() {
+            Self::remove_file(&path).await?;
+            return Ok(None);
+        }
+
+        update(&mut payload);
+        self.write_payload(&path, &payload).await?;
+        Ok(Some(payload))
+    }
+
+    /// Make renames and removals in a directory durable
+    async fn sync_dir(dir: &Path) -> Result<(), FileError> {
+        #[cfg(unix)]
+        fs::File::open(dir).await?.sync_all().await?;
+        Ok(())
+    }
+
+    /// Remove a file, returning false if it did not exist
+    async fn remove_file(path: &Path) -> Result<bool, FileError> {
+        match fs::remove_file(path).await {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+#[async_trait]
+impl Repository for FileRepository {
+    async fn save(&self, payload: &Payload) -> Result<(), RepositoryError> {
+        let path = self.payload_path(payload.hash_id())?;
+
+        // Like Redis, payloads that are already expired are not written
+        if payload.expiry_time() <= Utc::now() {
+            return Ok(());
+        }
+
+        // Revisions of a replaced payload go with it
+        if let Ok(Some(existing)) = Self::read_metadata(&path).await {
+            self.remove_revisions(existing.hash_id(), existing.revision()).await?;
+        }
+        self.write_payload(&path, payload).await?;
+        Ok(())
+    }
+
+    async fn insert(&self, payload: &Payload) -> Result<(), RepositoryError> {
+        let path = self.payload_path(payload.hash_id())?;
+
+        if payload.expiry_time() <= Utc::now() {
+            return Ok(());
+        }
+
+        if !self.write_new_payload(&path, payload).await? {
+            return Err(RepositoryError::Conflict(format!(
+                "Hash ID {} is already taken",
+                payload.hash_id().as_string()
+            )));
+        }
+        Ok(())
+    }
+
+    async fn get(&self, hash_id: &HashId) -> Result<Option<Payload>, RepositoryError> {
+        let Ok(path) = self.payload_path(hash_id) else {
+            return Ok(None);
+        };
+
+        match Self::read_payload(&path).await? {
+            Some(payload) if payload.expiry_time() <= Utc::now() => {
+                Self::remove_file(&path).await?;
+                Ok(None)
+            }
+            other => Ok(other),
+        }
+    }
+
+    async fn view(&self, hash_id: &HashId, unlocked: bool) -> Result<Option<Payload>, RepositoryError> {
+        let Ok(path) = self.payload_path(hash_id) else {
+            return Ok(None);
+        };
+
+        let _guard = self.update_lock.lock().await;
+        let Some(mut payload) = Self::read_payload(&path).await? else {
+            return Ok(None);
+        };
+        if payload.expiry_time() <= Utc::now() {
+            Self::remove_file(&path).await?;
+            return Ok(None);
+        }
+        if payload.is_password_protected() && !unlocked {
+            return Err(RepositoryError::PasswordRequired);
+        }
+        if payload.view_limit_reached() {
+            return Err(RepositoryError::ViewLimitReached);
+        }
+
+        payload.mark_viewed();
+        if payload.is_one_time() {
+            Self::remove_file(&path).await?;
+            Self::sync_dir(&self.payload_dir).await?;
+            self.remove_revisions(hash_id, payload.revision()).await?;
+            self.remove_names(&payload).await?;
+        } else {
+            self.write_payload(&path, &payload).await?;
+        }
+        Ok(Some(payload))
+    }
+
+    async fn try_unlock_attempt(
+        &self,
+        hash_id: &HashId,
+        at: DateTime<Utc>,
+    ) -> Result<Option<Payload>, RepositoryError> {
+        let mut reserved = None;
+        self.update(hash_id, |payload| reserved = Some(reserve_unlock_attempt(payload, at))).await?;
+        reserved.transpose()
+    }
+
+    async fn release_unlock_attempt(
+        &self,
+        hash_id: &HashId,
+        at: DateTime<Utc>,
+        previous: Option<DateTime<Utc>>,
+    ) -> Result<(), RepositoryError> {
+        self.update(hash_id, |payload| payload.release_unlock_attempt(at, previous)).await?;
+        Ok(())
+    }
+
+    async fn update_expiry(
+        &self,
+        hash_id: &HashId,
+        expiry_time: DateTime<Utc>,
+        version: Option<u64>,
+    ) -> Result<Option<Payload>, RepositoryError> {
+        let Ok(path) = self.payload_path(hash_id) else {
+            return Ok(None);
+        };
+
+        let _guard = self.update_lock.lock().await;
+        let Some(mut payload) = Self::read_payload(&path).await? else {
+            return Ok(None);
+        };
+        if payload.expiry_time() <= Utc::now() {
+            Self::remove_file(&path).await?;
+            return Ok(None);
+        }
+        check_version(&payload, version)?;
+
+        // Content and metadata share a file, so the whole file is rewritten
+        payload.set_expiry_time(expiry_time);
+        if expiry_time <= Utc::now() {
+            Self::remove_file(&path).await?;
+            self.remove_names(&payload).await?;
+        } else {
+            self.write_payload(&path, &payload).await?;
+        }
+        Ok(Some(payload))
+    }
+
+    async fn replace_content(
+        &self,
+        payload: &Payload,
+        previous: &Revision,
+        version: u64,
+        max_revisions: usize,
+    ) -> Result<Option<Payload>, RepositoryError> {
+        let hash_id = payload.hash_id();
+        let Ok(path) = self.payload_path(hash_id) else {
+            return Ok(None);
+        };
+
+        let _guard = self.update_lock.lock().await;
+        let Some(mut stored) = Self::read_payload(&path).await? else {
+            return Ok(None);
+        };
+        if stored.expiry_time() <= Utc::now() {
+            Self::remove_file(&path).await?;
+            return Ok(None);
+        }
+        check_version(&stored, Some(version))?;
+
+        // Keep the revision before moving the payload past it, so a crash in
+        // between only leaves a revision to be written again
+        let revision_path = self.revision_path(hash_id, previous.number())?;
+        let meta = REVISIONS.encode(previous).map_err(FileError::from)?;
+        let temp_path = Self::write_temp_parts(&revision_path, &[&meta, b"\n", previous.content()]).await?;
+        if let Err(e) = fs::rename(&temp_path, &revision_path).await {
+            let _ = fs::remove_file(&temp_path).await;
+            return Err(FileError::from(e).into());
+        }
+        Self::sync_dir(&self.revision_dir).await?;
+
+        stored.restore_revision(payload);
+        self.write_payload(&path, &stored).await?;
+
+        // Revisions are numbered without gaps, so the dropped ones end at
+        // the first that is already gone
+        for number in (1..oldest_kept_revision(previous, max_revisions)).rev() {
+            if !Self::remove_file(&self.revision_path(hash_id, number)?).await? {
+                break;
+            }
+        }
+        Ok(Some(stored))
+    }
+
+    async fn get_revision(
+        &self,
+        hash_id: &HashId,
+        number: u64,
+    ) -> Result<Option<Revision>, RepositoryError> {
+        let Ok(path) = self.payload_path(hash_id) else {
+            return Ok(None);
+        };
+
+        // Revision files of an earlier payload with the same hash ID may
+        // not have been swept yet
+        let kept = matches!(
+            Self::read_metadata(&path).await?,
+            Some(payload) if payload.expiry_time() > Utc::now() && number < payload.revision()
+        );
+        if !kept {
+            return Ok(None);
+        }
+        Ok(Self::read_revision(&self.revision_path(hash_id, number)?).await?)
+    }
+
+    async fn delete(&self, hash_id: &HashId, version: Option<u64>) -> Result<(), RepositoryError> {
+        let path = match self.payload_path(hash_id) {
+            Ok(path) => path,
+            Err(_) => return Err(RepositoryError::NotFound),
+        };
+
+        // Keep a concurrent view from writing the payload back
+        let _guard = self.update_lock.lock().await;
+
+        let (expired, stored) = match Self::read_metadata(&path).await {
+            Ok(Some(payload)) if payload.expiry_time() > Utc::now() => {
+                check_version(&payload, version)?;
+                (false, Some(payload))
+            }
+            Ok(Some(payload)) => (true, Some(payload)),
+            Ok(None) => {
+                debug!(path = %path.display(), "Payload file not found");
+                return Err(RepositoryError::NotFound);
+            }
+            // Any revisions and names are left to the sweeper and to expiry
+            Err(_) => (false, None),
+        };
+
+        if !Self::remove_file(&path).await? || expired {
+            return Err(RepositoryError::NotFound);
+        }
+        Self::sync_dir(&self.payload_dir).await?;
+        if let Some(stored) = stored {
+            self.remove_revisions(hash_id, stored.revision()).await?;
+            self.remove_names(&stored).await?;
+        }
+
+        info!(path = %path.display(), "Successfully deleted payload file");
+        Ok(())
+    }
+
+    async fn insert_alias(
+        &self,
+        alias: &str,
+        hash_id: &HashId,
+        expiry_time: DateTime<Utc>,
+    ) -> Result<(), RepositoryError> {
+        let path = self.alias_path(alias)?;
+
+        if expiry_time <= Utc::now() {
+            return Ok(());
+        }
+
+        let record = AliasRecord {
+            hash_id: hash_id.clone(),
+            expiry_time,
+        };
+        if !self.write_new_alias(&path, &record).await? {
+            return Err(RepositoryError::Conflict(format!("Alias {} is already taken", alias)));
+        }
+        Ok(())
+    }
+
+    async fn update_alias_expiry(
+        &self,
+        alias: &str,
+        hash_id: &HashId,
+        expiry_time: DateTime<Utc>,
+    ) -> Result<(), RepositoryError> {
+        let Ok(path) = self.alias_path(alias) else {
+            return Ok(());
+        };
+
+        // Serialize with other updates so the check and the rewrite are one step
+        let _guard = self.update_lock.lock().await;
+        let reserved = matches!(
+            Self::read_alias(&path).await?,
+            Some(record) if record.hash_id == *hash_id && record.expiry_time > Utc::now()
+        );
+        if !reserved {
+            return Ok(());
+        }
+
+        if expiry_time <= Utc::now() {
+            Self::remove_file(&path).await?;
+        } else {
+            let record = AliasRecord {
+                hash_id: hash_id.clone(),
+                expiry_time,
+            };
+            let temp_path = Self::write_temp_parts(&path, &[&serde_json::to_vec(&record)?]).await?;
+            if let Err(e) = fs::rename(&temp_path, &path).await {
+                let _ = fs::remove_file(&temp_path).await;
+                return Err(FileError::from(e).into());
+            }
+        }
+        Self::sync_dir(&self.alias_dir).await?;
+        Ok(())
+    }
+
+    async fn resolve_alias(&self, alias: &str) -> Result<Option<HashId>, RepositoryError> {
+        let Ok(path) = self.alias_path(alias) else {
+            return Ok(None);
+        };
+
+        match Self::read_alias(&path).await? {
+            Some(record) if record.expiry_time <= Utc::now() => {
+                Self::remove_file(&path).await?;
+                Ok(None)
+            }
+            record => Ok(record.map(|record| record.hash_id)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration as ChronoDuration;
+    use tempfile::TempDir;
+
+    fn test_repo() -> (TempDir, FileRepository) {
+        let dir = TempDir::new().unwrap();
+        let repo = FileRepository::new(FileConfig {
+            data_dir: dir.path().to_path_buf(),
+            sweep_interval: 1,
+        })
+        .unwrap();
+        (dir, repo)
+    }
+
+    fn payload_with(content: &[u8], expires_in: ChronoDuration) -> Payload {
+        Payload::new(content.to_vec(), None, Some(Utc::now() + expires_in)).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_save_and_get_binary() -> Result<(), anyhow::Error> {
+        let (_dir, repo) = test_repo();
+        let content = vec![0x89, b'P', b'N', b'G', b'\n', 0x00, 0xff];
+        let payload = Payload::new(content.clone(), Some("image/png".to_string()), None)?;
+
+        repo.save(&payload).await?;
+
+        let retrieved = repo.get(payload.hash_id()).await?.unwrap();
+        assert_eq!(retrieved.content(), content.as_slice());
+        assert_eq!(retrieved.mime_type().to_string(), "image/png");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_sweeper_stops_when_repository_is_dropped() {
+        let (_dir, repo) = test_repo();
+        let sweeper = repo.start_sweeper();
+        drop(repo);
+
+        tokio::time::timeout(std::time::Duration::from_secs(3), sweeper)
+            .await
+            .expect("sweeper should stop")
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_delete() -> Result<(), anyhow::Error> {
+        let (_dir, repo) = test_repo();
+        let payload = payload_with(b"test content", ChronoDuration::hours(1));
+
+        repo.save(&payload).await?;
+        repo.delete(payload.hash_id(), None).await?;
+
+        assert!(repo.get(payload.hash_id()).await?.is_none());
+        assert!(matches!(
+            repo.delete(payload.hash_id(), None).await,
+            Err(RepositoryError::NotFound)
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_payloads_survive_restart() -> Result<(), anyhow::Error> {
+        let (dir, repo) = test_repo();
+        let payload = payload_with(b"persistent", ChronoDuration::hours(1));
+        repo.save(&payload).await?;
+        drop(repo);
+
+        let reopened = FileRepository::new(FileConfig {
+            data_dir: dir.path().to_path_buf(),
+            sweep_interval: 1,
+        })?;
+        let retrieved = reopened.get(payload.hash_id()).await?.unwrap();
+        assert_eq!(retrieved.content(), b"persistent");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_incomplete_writes_are_cleaned_up() -> Result<(), anyhow::Error> {
+        let (dir, _repo) = test_repo();
+        let leftover = dir.path().join("payloads").join("abc.1234.tmp");
+        std::fs::write(&leftover, b"partial")?;
+
+        FileRepository::new(FileConfig {
+            data_dir: dir.path().to_path_buf(),
+            sweep_interval: 1,
+        })?;
+        assert!(!leftover.exists());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_expired_payload_is_swept() -> Result<(), anyhow::Error> {
+        let (_dir, repo) = test_repo();
+        let short = payload_with(b"short", ChronoDuration::milliseconds(100));
+        let long = payload_with(b"long", ChronoDuration::hours(1));
+        repo.save(&short).await?;
+        repo.save(&long).await?;
+
+        tokio::time::sleep(Duration::from_millis(150)).await;
+
+        assert_eq!(repo.sweep_expired().await?, 1);
+        assert!(repo.get(short.hash_id()).await?.is_none());
+        assert!(repo.get(long.hash_id()).await?.is_some());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_legacy_payload_files_are_migrated() -> Result<(), anyhow::Error> {
+        let (dir, repo) = test_repo();
+        let path = dir.path().join("payloads").join("legacy.payload");
+        let meta = serde_json::json!({
+            "hash_id": "legacy",
+            "mime_type": "textplain",
+            "created_at": Utc::now(),
+            "updated_at": Utc::now(),
+            "viewed_at": null,
+            "expiry_time": Utc::now() + ChronoDuration::hours(1),
+        });
+        std::fs::write(&path, [serde_json::to_vec(&meta)?, b"\nold content".to_vec()].concat())?;
+
+        let hash_id = HashId::from_string("legacy".to_string());
+        let payload = repo.get(&hash_id).await?.unwrap();
+        assert_eq!(payload.mime_type().essence(), "text/plain");
+        assert_eq!(payload.content(), b"old content");
+
+        // Written back in the current schema the next time it changes
+        repo.view(&hash_id, false).await?;
+        let stored = std::fs::read(&path)?;
+        let envelope: serde_json::Value = serde_json::Deserializer::from_slice(&stored)
+            .into_iter()
+            .next()
+            .unwrap()?;
+        assert_eq!(envelope["schema_version"], PAYLOADS.current_version());
+        assert_eq!(envelope["payload"]["mime_type"], "text/plain");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_unsafe_hash_id_is_rejected() -> Result<(), anyhow::Error> {
+        let (_dir, repo) = test_repo();
+        let hash_id = HashId::from_string("../escape".to_string());
+
+        assert!(repo.get(&hash_id).await?.is_none());
+        assert!(matches!(repo.delete(&hash_id, None).await, Err(RepositoryError::NotFound)));
+        Ok(())
+    }
+}
@@ -0,0 +1,609 @@
+//! In-memory repository implementation.
+//!
+//! This module provides a `Repository` backed by a process-local map. It is
+//! intended for single-node deployments and CI, where running Redis is not
+//! worth the trouble. Expiry follows the same rules as the Redis backend:
+//! expired payloads are never returned, and a background sweeper task
+//! reclaims their memory.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, Weak};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use thiserror::Error;
+use tokio::task::JoinHandle;
+use tracing::{debug, info};
+
+use crate::{
+    application::repository::{
+        check_version, oldest_kept_revision, reserve_unlock_attempt, Repository, RepositoryError,
+    },
+    domain::{hash_id::HashId, payload::Payload, revision::Revision},
+};
+
+/// In-memory repository configuration
+#[derive(Clone, Debug)]
+pub struct InMemoryConfig {
+    /// Maximum number of payloads held at once
+    pub max_entries: usize,
+    /// Maximum total size of stored content in bytes
+    pub max_bytes: usize,
+    /// Interval between expiry sweeps in seconds
+    pub sweep_interval: u64,
+}
+
+impl Default for InMemoryConfig {
+    fn default() -> Self {
+        Self {
+            max_entries: 10_000,
+            max_bytes: 256 * 1024 * 1024,
+            sweep_interval: 60,
+        }
+    }
+}
+
+/// In-memory repository errors
+#[derive(Error, Debug)]
+pub enum InMemoryError {
+    #[error("Entry limit of {0} payloads reached")]
+    EntryLimit(usize),
+    #[error("Memory limit of {0} bytes reached")]
+    ByteLimit(usize),
+}
+
+impl From<InMemoryError> for RepositoryError {
+    fn from(err: InMemoryError) -> Self {
+        RepositoryError::QuotaExceeded(err.to_string())
+    }
+}
+
+/// Stored payloads and their revisions, and the running total of their
+/// content size
+#[derive(Default)]
+struct Store {
+    payloads: HashMap<HashId, Payload>,
+    revisions: HashMap<HashId, Vec<Revision>>,
+    content_bytes: usize,
+    aliases: HashMap<String, (HashId, DateTime<Utc>)>,
+}
+
+impl Store {
+    /// Store a payload, replacing any payload and revisions with its hash ID
+    fn insert(&mut self, payload: Payload) {
+        self.remove_revisions(payload.hash_id());
+        self.content_bytes += payload.content().len();
+        if let Some(old) = self.payloads.insert(payload.hash_id().clone(), payload) {
+            self.content_bytes -= old.content().len();
+        }
+    }
+
+    /// Remove a payload with its revisions and the names resolving to it
+    fn remove(&mut self, hash_id: &HashId) -> Option<Payload> {
+        self.remove_revisions(hash_id);
+        let removed = self.payloads.remove(hash_id)?;
+        self.content_bytes -= removed.content().len();
+        for name in removed.names() {
+            // The name may have expired and been reserved for another payload
+            if self.aliases.get(name).is_some_and(|(owner, _)| owner == hash_id) {
+                self.aliases.remove(name);
+            }
+        }
+        Some(removed)
+    }
+
+    fn remove_revisions(&mut self, hash_id: &HashId) {
+        for revision in self.revisions.remove(hash_id).unwrap_or_default() {
+            self.content_bytes -= revision.content().len();
+        }
+    }
+
+    /// Remove all expired payloads, returning how many were removed
+    fn sweep(&mut self) -> usize {
+        let now = Utc::now();
+        let expired: Vec<HashId> = self
+            .payloads
+            .values()
+            .filter(|payload| payload.expiry_time() <= now)
+            .map(|payload| payload.hash_id().clone())
+            .collect();
+
+        for hash_id in &expired {
+            self.remove(hash_id);
+        }
+        self.aliases.retain(|_, (_, expiry_time)| *expiry_time > now);
+        expired.len()
+    }
+}
+
+/// In-memory repository for storing and retrieving payloads
+///
+/// Clones share the same underlying store.
+#[derive(Clone)]
+pub struct InMemoryRepository {
+    config: InMemoryConfig,
+    store: Arc<Mutex<Store>>,
+}
+
+impl InMemoryRepository {
+    /// Create a new, empty in-memory repository
+    pub fn new(config: InMemoryConfig) -> Self {
+        Self {
+            config,
+            store: Arc::new(Mutex::new(Store::default())),
+        }
+    }
+
+    /// Apply an update to a stored payload under the lock, returning the
+    /// updated payload
+    fn update(&self, hash_id: &HashId, update: impl FnOnce(&mut Payload)) -> Option<Payload> {
+        let mut store = self.store.lock().unwrap();
+
+        match store.payloads.get_mut(hash_id) {
+            Some(payload) if payload.expiry_time() <= Utc::now() => {
+                store.remove(hash_id);
+                None
+            }
+            Some(payload) => {
+                update(payload);
+                Some(payload.clone())
+            }
+            None => None,
+        }
+    }
+
+    /// Spawn the background task that periodically removes expired payloads.
+    ///
+    /// The task stops on its own once every clone of the repository has been
+    /// dropped. Must be called from within a Tokio runtime.
+    pub fn start_sweeper(&self) -> JoinHandle<()> {
+        let store: Weak<Mutex<Store>> = Arc::downgrade(&self.store);
+        let period = Duration::from_secs(self.config.sweep_interval.max(1));
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            loop {
+                interval.tick().await;
+                let Some(store) = store.upgrade() else {
+                    debug!("In-memory repository dropped, stopping expiry sweeper");
+                    break;
+                };
+                let removed = store.lock().unwrap().sweep();
+                if removed > 0 {
+                    info!(removed, "Swept expired payloads from memory");
+                }
+            }
+        })
+    }
+
+    /// Remove all expired payloads now, returning how many were removed
+    pub fn sweep_expired(&self) -> usize {
+        self.store.lock().unwrap().sweep()
+    }
+
+    /// Number of payloads currently held, including expired ones not yet swept
+    pub fn len(&self) -> usize {
+        self.store.lock().unwrap().payloads.len()
+    }
+
+    /// Returns true if no payloads are held
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Check whether `bytes` more content would stay within the configured
+    /// byte limit
+    fn check_bytes(&self, store: &Store, bytes: usize) -> Result<(), InMemoryError> {
+        if store.content_bytes + bytes > self.config.max_bytes {
+            return Err(InMemoryError::ByteLimit(self.config.max_bytes));
+        }
+        Ok(())
+    }
+
+    /// Check whether storing `payload` would stay within the configured limits
+    fn check_limits(&self, store: &Store, payload: &Payload) -> Result<(), InMemoryError> {
+        let existing = store.payloads.get(payload.hash_id());
+        let entries = store.payloads.len() + usize::from(existing.is_none());
+        let bytes = store.content_bytes - existing.map_or(0, |old| old.content().len())
+            + payload.content().len();
+
+        if entries > self.config.max_entries {
+            return Err(InMemoryError::EntryLimit(self.config.max_entries));
+        }
+        if bytes > self.config.max_bytes {
+            return Err(InMemoryError::ByteLimit(self.config.max_bytes));
+        }
+        Ok(())
+    }
+}
+
+impl Default for InMemoryRepository {
+    fn default() -> Self {
+        Self::new(InMemoryConfig::default())
+    }
+}
+
+#[async_trait]
+impl Repository for InMemoryRepository {
+    async fn save(&self, payload: &Payload) -> Result<(), RepositoryError> {
+        // Like Redis, payloads that are already expired are not written
+        if payload.expiry_time() <= Utc::now() {
+            return Ok(());
+        }
+
+        let mut store = self.store.lock().unwrap();
+        if self.check_limits(&store, payload).is_err() {
+            // Reclaim expired payloads before giving up
+            store.sweep();
+            self.check_limits(&store, payload)?;
+        }
+        store.insert(payload.clone());
+
+        Ok(())
+    }
+
+    async fn insert(&self, payload: &Payload) -> Result<(), RepositoryError> {
+        if payload.expiry_time() <= Utc::now() {
+            return Ok(());
+        }
+
+        let mut store = self.store.lock().unwrap();
+        let taken = store
+            .payloads
+            .get(payload.hash_id())
+            .is_some_and(|existing| existing.expiry_time() > Utc::now());
+        if taken {
+            return Err(RepositoryError::Conflict(format!(
+                "Hash ID {} is already taken",
+                payload.hash_id().as_string()
+            )));
+        }
+
+        if self.check_limits(&store, payload).is_err() {
+            store.sweep();
+            self.check_limits(&store, payload)?;
+        }
+        store.insert(payload.clone());
+
+        Ok(())
+    }
+
+    async fn get(&self, hash_id: &HashId) -> Result<Option<Payload>, RepositoryError> {
+        let mut store = self.store.lock().unwrap();
+
+        match store.payloads.get(hash_id) {
+            Some(payload) if payload.expiry_time() <= Utc::now() => {
+                store.remove(hash_id);
+                Ok(None)
+            }
+            Some(payload) => Ok(Some(payload.clone())),
+            None => Ok(None),
+        }
+    }
+
+    async fn view(&self, hash_id: &HashId, unlocked: bool) -> Result<Option<Payload>, RepositoryError> {
+        let mut store = self.store.lock().unwrap();
+
+        let viewed = match store.payloads.get_mut(hash_id) {
+            Some(payload) if payload.expiry_time() <= Utc::now() => {
+                store.remove(hash_id);
+                return Ok(None);
+            }
+            Some(payload) if payload.is_password_protected() && !unlocked => {
+                return Err(RepositoryError::PasswordRequired)
+            }
+            Some(payload) if payload.view_limit_reached() => return Err(RepositoryError::ViewLimitReached),
+            Some(payload) => {
+                payload.mark_viewed();
+                payload.clone()
+            }
+            None => return Ok(None),
+        };
+        if viewed.is_one_time() {
+            store.remove(hash_id);
+        }
+        Ok(Some(viewed))
+    }
+
+    async fn try_unlock_attempt(
+        &self,
+        hash_id: &HashId,
+        at: DateTime<Utc>,
+    ) -> Result<Option<Payload>, RepositoryError> {
+        let mut reserved = None;
+        self.update(hash_id, |payload| reserved = Some(reserve_unlock_attempt(payload, at)));
+        reserved.transpose()
+    }
+
+    async fn release_unlock_attempt(
+        &self,
+        hash_id: &HashId,
+        at: DateTime<Utc>,
+        previous: Option<DateTime<Utc>>,
+    ) -> Result<(), RepositoryError> {
+        self.update(hash_id, |payload| payload.release_unlock_attempt(at, previous));
+        Ok(())
+    }
+
+    async fn update_expiry(
+        &self,
+        hash_id: &HashId,
+        expiry_time: DateTime<Utc>,
+        version: Option<u64>,
+    ) -> Result<Option<Payload>, RepositoryError> {
+        let mut store = self.store.lock().unwrap();
+
+        let updated = match store.payloads.get_mut(hash_id) {
+            Some(payload) if payload.expiry_time() <= Utc::now() => {
+                store.remove(hash_id);
+                return Ok(None);
+            }
+            Some(payload) => {
+                check_version(payload, version)?;
+                payload.set_expiry_time(expiry_time);
+                payload.clone()
+            }
+            None => return Ok(None),
+        };
+        if expiry_time <= Utc::now() {
+            store.remove(hash_id);
+        }
+        Ok(Some(updated))
+    }
+
+    async fn replace_content(
+        &self,
+        payload: &Payload,
+        previous: &Revision,
+        version: u64,
+        max_revisions: usize,
+    ) -> Result<Option<Payload>, RepositoryError> {
+        let hash_id = payload.hash_id();
+        let mut store = self.store.lock().unwrap();
+
+        let stored = match store.payloads.get(hash_id) {
+            Some(stored) if stored.expiry_time() <= Utc::now() => {
+                store.remove(hash_id);
+                return Ok(None);
+            }
+            Some(stored) => stored,
+            None => return Ok(None),
+        };
+        check_version(stored, Some(version))?;
+
+        // The replaced content stays, so only the new content takes room
+        if self.check_bytes(&store, payload.content().len()).is_err() {
+            store.sweep();
+            self.check_bytes(&store, payload.content().len())?;
+        }
+
+        let Some(stored) = store.payloads.get_mut(hash_id) else {
+            return Ok(None);
+        };
+        let replaced_bytes = stored.content().len();
+        stored.restore_revision(payload);
+        let updated = stored.clone();
+
+        store.content_bytes += payload.content().len() + previous.content().len() - replaced_bytes;
+        let oldest = oldest_kept_revision(previous, max_revisions);
+        let revisions = store.revisions.entry(hash_id.clone()).or_default();
+        revisions.push(previous.clone());
+        let mut dropped_bytes = 0;
+        revisions.retain(|revision| {
+            let kept = revision.number() >= oldest;
+            if !kept {
+                dropped_bytes += revision.content().len();
+            }
+            kept
+        });
+        store.content_bytes -= dropped_bytes;
+        Ok(Some(updated))
+    }
+
+    async fn get_revision(
+        &self,
+        hash_id: &HashId,
+        number: u64,
+    ) -> Result<Option<Revision>, RepositoryError> {
+        let store = self.store.lock().unwrap();
+        let live = store
+            .payloads
+            .get(hash_id)
+            .is_some_and(|payload| payload.expiry_time() > Utc::now());
+        if !live {
+            return Ok(None);
+        }
+
+        Ok(store
+            .revisions
+            .get(hash_id)
+            .and_then(|revisions| revisions.iter().find(|revision| revision.number() == number))
+            .cloned())
+    }
+
+    async fn delete(&self, hash_id: &HashId, version: Option<u64>) -> Result<(), RepositoryError> {
+        let mut store = self.store.lock().unwrap();
+
+        if let Some(payload) = store.payloads.get(hash_id) {
+            if payload.expiry_time() > Utc::now() {
+                check_version(payload, version)?;
+            }
+        }
+        match store.remove(hash_id) {
+            Some(payload) if payload.expiry_time() > Utc::now() => Ok(()),
+            _ => {
+                debug!(hash_id = %hash_id.as_string(), "Payload not found in memory");
+                Err(RepositoryError::NotFound)
+            }
+        }
+    }
+
+    async fn insert_alias(
+        &self,
+        alias: &str,
+        hash_id: &HashId,
+        expiry_time: DateTime<Utc>,
+    ) -> Result<(), RepositoryError> {
+        let now = Utc::now();
+        if expiry_time <= now {
+            return Ok(());
+        }
+
+        let mut store = self.store.lock().unwrap();
+        let taken = store
+            .aliases
+            .get(alias)
+            .is_some_and(|(_, existing_expiry)| *existing_expiry > now);
+        if taken {
+            return Err(RepositoryError::Conflict(format!("Alias {} is already taken", alias)));
+        }
+
+        store.aliases.insert(alias.to_string(), (hash_id.clone(), expiry_time));
+        Ok(())
+    }
+
+    async fn update_alias_expiry(
+        &self,
+        alias: &str,
+        hash_id: &HashId,
+        expiry_time: DateTime<Utc>,
+    ) -> Result<(), RepositoryError> {
+        let now = Utc::now();
+        let mut store = self.store.lock().unwrap();
+
+        let reserved = store
+            .aliases
+            .get(alias)
+            .is_some_and(|(existing, existing_expiry)| existing == hash_id && *existing_expiry > now);
+        if reserved {
+            if expiry_time <= now {
+                store.aliases.remove(alias);
+            } else {
+                store.aliases.insert(alias.to_string(), (hash_id.clone(), expiry_time));
+            }
+        }
+        Ok(())
+    }
+
+    async fn resolve_alias(&self, alias: &str) -> Result<Option<HashId>, RepositoryError> {
+        let mut store = self.store.lock().unwrap();
+
+        match store.aliases.get(alias) {
+            Some((_, expiry_time)) if *expiry_time <= Utc::now() => {
+                store.aliases.remove(alias);
+                Ok(None)
+            }
+            Some((hash_id, _)) => Ok(Some(hash_id.clone())),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration as ChronoDuration;
+
+    fn payload_with(content: &[u8], expires_in: ChronoDuration) -> Payload {
+        Payload::new(content.to_vec(), None, Some(Utc::now() + expires_in)).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_save_and_get() -> Result<(), anyhow::Error> {
+        let repo = InMemoryRepository::default();
+        let payload = payload_with(b"test content", ChronoDuration::hours(1));
+
+        repo.save(&payload).await?;
+
+        let retrieved = repo.get(payload.hash_id()).await?.unwrap();
+        assert_eq!(retrieved.content(), payload.content());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_delete() -> Result<(), anyhow::Error> {
+        let repo = InMemoryRepository::default();
+        let payload = payload_with(b"test content", ChronoDuration::hours(1));
+
+        repo.save(&payload).await?;
+        repo.delete(payload.hash_id(), None).await?;
+
+        assert!(repo.get(payload.hash_id()).await?.is_none());
+        assert!(matches!(
+            repo.delete(payload.hash_id(), None).await,
+            Err(RepositoryError::NotFound)
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_expired_payload_is_not_returned() -> Result<(), anyhow::Error> {
+        let repo = InMemoryRepository::default();
+        let payload = payload_with(b"test content", ChronoDuration::milliseconds(100));
+
+        repo.save(&payload).await?;
+        tokio::time::sleep(Duration::from_millis(150)).await;
+
+        assert!(repo.get(payload.hash_id()).await?.is_none());
+        assert!(repo.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_sweeper_removes_expired_payloads() -> Result<(), anyhow::Error> {
+        let repo = InMemoryRepository::new(InMemoryConfig {
+            sweep_interval: 1,
+            ..InMemoryConfig::default()
+        });
+        let short = payload_with(b"short", ChronoDuration::milliseconds(100));
+        let long = payload_with(b"long", ChronoDuration::hours(1));
+        repo.save(&short).await?;
+        repo.save(&long).await?;
+
+        let sweeper = repo.start_sweeper();
+        tokio::time::sleep(Duration::from_millis(1200)).await;
+
+        assert_eq!(repo.len(), 1);
+        sweeper.abort();
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_entry_limit() -> Result<(), anyhow::Error> {
+        let repo = InMemoryRepository::new(InMemoryConfig {
+            max_entries: 1,
+            ..InMemoryConfig::default()
+        });
+        let first = payload_with(b"first", ChronoDuration::hours(1));
+        let second = payload_with(b"second", ChronoDuration::hours(1));
+
+        repo.save(&first).await?;
+        assert!(matches!(
+            repo.save(&second).await,
+            Err(RepositoryError::QuotaExceeded(_))
+        ));
+
+        // Overwriting an existing payload does not count as a new entry
+        repo.save(&first).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_byte_limit_reclaims_expired_payloads() -> Result<(), anyhow::Error> {
+        let repo = InMemoryRepository::new(InMemoryConfig {
+            max_bytes: 10,
+            ..InMemoryConfig::default()
+        });
+        let expiring = payload_with(b"12345678", ChronoDuration::milliseconds(100));
+        let next = payload_with(b"abcdefgh", ChronoDuration::hours(1));
+
+        repo.save(&expiring).await?;
+        assert!(repo.save(&next).await.is_err());
+
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        repo.save(&next).await?;
+        assert_eq!(repo.len(), 1);
+        Ok(())
+    }
+}
@@ -3,16 +3,49 @@
 //! This layer contains implementations of the interfaces defined in the application layer.
 //! It includes:
 //! - Redis repository implementation
+//! - In-memory repository implementation
+//! - File system repository implementation
 //! - Rate limiting implementation
 //! - Logging infrastructure
 
+use std::str::FromStr;
+
 pub mod redis;
+pub mod memory;
+pub mod file;
 pub mod rate_limit;
 pub mod logging;
+pub mod schema;
 
 pub use redis::RedisRepository;
-pub use rate_limit::{RateLimiter, RedisRateLimiter, RateLimitConfig, RateLimitError};
+pub use memory::InMemoryRepository;
+pub use file::FileRepository;
+pub use rate_limit::{RateLimiter, RedisRateLimiter, InMemoryRateLimiter, RateLimitConfig, RateLimitError};
 pub use logging::{LoggingConfig, init_logging, RequestLogger};
 
+/// Storage backend selected at startup.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageBackend {
+    /// Payloads are stored in Redis (the default)
+    Redis,
+    /// Payloads are stored in process memory
+    Memory,
+    /// Payloads are stored in files under a local data directory
+    File,
+}
+
+impl FromStr for StorageBackend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "redis" => Ok(Self::Redis),
+            "memory" => Ok(Self::Memory),
+            "file" => Ok(Self::File),
+            other => Err(format!("Unknown storage backend: {}", other)),
+        }
+    }
+}
+
 #[cfg(test)]
 pub mod tests;
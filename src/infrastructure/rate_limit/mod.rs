@@ -2,7 +2,11 @@
 //!
 //! This module provides rate limiting functionality using Redis as a backend.
 //! It implements a sliding window rate limiter that tracks requests per IP
-//! within a configurable time window.
+//! within a configurable time window. An in-process limiter with the same
+//! semantics is available for deployments without Redis.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
 
 use async_trait::async_trait;
 use thiserror::Error;
@@ -67,6 +71,13 @@ pub trait RateLimiter: Send + Sync {
     async fn check_rate_limit(&self, key: &str) -> Result<(), RateLimitError>;
 }
 
+#[async_trait]
+impl<T: RateLimiter + ?Sized> RateLimiter for Arc<T> {
+    async fn check_rate_limit(&self, key: &str) -> Result<(), RateLimitError> {
+        (**self).check_rate_limit(key).await
+    }
+}
+
 /// Redis-based rate limiter implementation using sliding window algorithm
 #[derive(Clone)]
 pub struct RedisRateLimiter {
@@ -98,7 +109,7 @@ impl RateLimiter for RedisRateLimiter {
         let _: () = redis::cmd("ZADD")
             .arg(&redis_key)
             .arg(now)
-            .arg(format!("req:{}", now)) // Use unique member for each request
+            .arg(format!("req:{}:{}", now, uuid::Uuid::new_v4().simple())) // Use unique member for each request
             .query_async(&mut conn)
             .await.map_err(|e| RateLimitError::Redis(e.to_string()))?;
         
@@ -150,6 +161,71 @@ impl RateLimiter for RedisRateLimiter {
     }
 }
 
+/// In-process rate limiter implementation using sliding window algorithm
+///
+/// Limits are tracked per process, so this is only accurate for single-node
+/// deployments. Clones share the same request history.
+#[derive(Clone)]
+pub struct InMemoryRateLimiter {
+    requests: Arc<Mutex<Requests>>,
+    config: RateLimitConfig,
+}
+
+/// Request timestamps per client
+#[derive(Default)]
+struct Requests {
+    by_key: HashMap<String, VecDeque<i64>>,
+    /// When clients whose window elapsed were last forgotten
+    pruned_at: i64,
+}
+
+impl InMemoryRateLimiter {
+    /// Create a new in-memory rate limiter
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            requests: Arc::new(Mutex::new(Requests::default())),
+            config,
+        }
+    }
+
+    /// Check a request made at `now`, a Unix time in seconds
+    fn check_at(&self, key: &str, now: i64) -> Result<(), RateLimitError> {
+        let window = self.config.window_seconds as i64;
+        let window_start = now - window;
+        let mut requests = self.requests.lock().unwrap();
+
+        // Forget clients whose window has fully elapsed, at most once per
+        // window rather than on every request
+        if now - requests.pruned_at >= window {
+            requests
+                .by_key
+                .retain(|_, timestamps| timestamps.back().is_some_and(|&t| t > window_start));
+            requests.pruned_at = now;
+        }
+
+        let timestamps = requests.by_key.entry(key.to_string()).or_default();
+        timestamps.push_back(now);
+        while timestamps.front().is_some_and(|&t| t <= window_start) {
+            timestamps.pop_front();
+        }
+
+        if timestamps.len() > self.config.max_requests as usize {
+            let oldest = timestamps.front().copied().unwrap_or(now);
+            let wait_time = (oldest - window_start).max(1) as u64;
+            return Err(RateLimitError::LimitExceeded(wait_time));
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl RateLimiter for InMemoryRateLimiter {
+    async fn check_rate_limit(&self, key: &str) -> Result<(), RateLimitError> {
+        self.check_at(key, chrono::Utc::now().timestamp())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -350,4 +426,45 @@ mod tests {
         let result = limiter.check_rate_limit(&key).await;
         assert!(result.is_ok(), "Request after window reset should be allowed");
     }
+
+    #[tokio::test]
+    async fn test_in_memory_rate_limit_blocks_excess_requests() {
+        let limiter = InMemoryRateLimiter::new(RateLimitConfig {
+            max_requests: 2,
+            window_seconds: 60,
+        });
+
+        assert!(limiter.check_rate_limit("client").await.is_ok());
+        assert!(limiter.check_rate_limit("client").await.is_ok());
+        match limiter.check_rate_limit("client").await {
+            Err(RateLimitError::LimitExceeded(wait)) => assert!(wait >= 1),
+            other => panic!("Expected LimitExceeded, got {:?}", other),
+        }
+
+        // Other clients are tracked separately
+        assert!(limiter.check_rate_limit("other").await.is_ok());
+    }
+
+    #[test]
+    fn test_in_memory_rate_limit_prunes_once_per_window() {
+        let limiter = InMemoryRateLimiter::new(RateLimitConfig {
+            max_requests: 2,
+            window_seconds: 60,
+        });
+        let start = 1_700_000_000;
+        let clients = |limiter: &InMemoryRateLimiter| limiter.requests.lock().unwrap().by_key.len();
+
+        limiter.check_at("first", start).unwrap();
+        limiter.check_at("first", start + 1).unwrap();
+        limiter.check_at("second", start + 60).unwrap();
+        assert_eq!(clients(&limiter), 2);
+
+        // The first client's window has elapsed, but the last prune is recent
+        limiter.check_at("third", start + 70).unwrap();
+        assert_eq!(clients(&limiter), 3);
+
+        // A window after the last prune, elapsed clients are forgotten
+        limiter.check_at("third", start + 120).unwrap();
+        assert_eq!(clients(&limiter), 1);
+    }
 }
@@ -0,0 +1,226 @@
+//! Codecs for the metadata of payloads and revisions stored in Redis.
+//!
+//! Metadata is written either as JSON or, more compactly, as MessagePack.
+//! The codec is chosen by configuration for new writes only: every entry
+//! tells by its first byte which codec wrote it, so entries written under
+//! either codec stay readable after switching. JSON entries start with `{`,
+//! and binary entries with [`BINARY_TAG`], a byte MessagePack never uses.
+//!
+//! Both codecs write the same [`schema`](crate::infrastructure::schema)
+//! envelope, so entries are migrated the same way whichever wrote them.
+//! Content is stored as raw bytes next to the metadata under either codec.
+
+use std::str::FromStr;
+
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::{
+    application::repository::RepositoryError,
+    infrastructure::schema::{MigrationRegistry, SchemaError},
+};
+
+/// First byte of entries written by [`Codec::Binary`]
+pub const BINARY_TAG: u8 = 0xc1;
+
+/// Codec errors
+#[derive(Error, Debug)]
+pub enum CodecError {
+    #[error("Storage schema error: {0}")]
+    Schema(#[from] SchemaError),
+    #[error("MessagePack encoding error: {0}")]
+    Encode(#[from] rmp_serde::encode::Error),
+    #[error("MessagePack decoding error: {0}")]
+    Decode(#[from] rmp_serde::decode::Error),
+}
+
+impl From<CodecError> for RepositoryError {
+    fn from(err: CodecError) -> Self {
+        match err {
+            CodecError::Schema(e) => e.into(),
+            other => RepositoryError::Serialization(other.to_string()),
+        }
+    }
+}
+
+/// Format metadata is written in.
+///
+/// # Examples
+///
+/// ```
+/// use jump::infrastructure::redis::Codec;
+/// use jump::infrastructure::schema::MigrationRegistry;
+/// use serde_json::{json, Value};
+///
+/// let registry = MigrationRegistry::new("note", 1);
+/// let note = json!({"title": "Groceries"});
+///
+/// let json = Codec::Json.encode(&registry, &note).unwrap();
+/// let binary = Codec::Binary.encode(&registry, &note).unwrap();
+/// assert!(binary.len() < json.len());
+///
+/// // Readers tell the codecs apart by themselves
+/// assert_eq!(Codec::detect(&binary), Codec::Binary);
+/// assert_eq!(Codec::decode::<Value>(&registry, &json).unwrap(), note);
+/// assert_eq!(Codec::decode::<Value>(&registry, &binary).unwrap(), note);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Codec {
+    /// Metadata is written as JSON (the default)
+    #[default]
+    Json,
+    /// Metadata is written as MessagePack, after [`BINARY_TAG`]
+    Binary,
+}
+
+impl FromStr for Codec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "json" => Ok(Self::Json),
+            "binary" => Ok(Self::Binary),
+            other => Err(format!("Unknown codec: {}", other)),
+        }
+    }
+}
+
+impl Codec {
+    /// Serializes a record in an envelope with the current schema version of
+    /// `registry`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the record cannot be serialized.
+    pub fn encode<T: Serialize>(
+        self,
+        registry: &MigrationRegistry,
+        record: &T,
+    ) -> Result<Vec<u8>, CodecError> {
+        match self {
+            Self::Json => Ok(registry.encode(record)?),
+            Self::Binary => {
+                let mut bytes = vec![BINARY_TAG];
+                rmp_serde::encode::write_named(&mut bytes, &registry.envelope(record))?;
+                Ok(bytes)
+            }
+        }
+    }
+
+    /// Returns the codec an entry was written by.
+    pub fn detect(stored: &[u8]) -> Self {
+        match stored.first() {
+            Some(&BINARY_TAG) => Self::Binary,
+            _ => Self::Json,
+        }
+    }
+
+    /// Deserializes a record written by either codec under any supported
+    /// schema version of `registry`, migrating it to the current one.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the entry is malformed, or the errors of
+    /// [`MigrationRegistry::decode`].
+    pub fn decode<T: DeserializeOwned>(
+        registry: &MigrationRegistry,
+        stored: &[u8],
+    ) -> Result<T, CodecError> {
+        match Self::detect(stored) {
+            Self::Json => Ok(registry.decode(stored)?),
+            Self::Binary => {
+                let stored: Value = rmp_serde::from_slice(&stored[1..])?;
+                Ok(registry.restore(stored)?)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use chrono::{Duration, Utc};
+
+    use crate::{
+        domain::{
+            expiry::ExpiryMode, metadata::Metadata, payload::Payload, revision::Revision,
+        },
+        infrastructure::schema::{PAYLOADS, REVISIONS},
+    };
+
+    fn payload() -> Payload {
+        let metadata = Metadata::default()
+            .with_filename("notes.md")
+            .unwrap()
+            .with_title(Some("Notes".to_string()));
+        Payload::new(
+            b"# Notes".to_vec(),
+            Some("text/markdown; charset=utf-8".to_string()),
+            Some(Utc::now() + Duration::hours(1)),
+        )
+        .unwrap()
+        .with_metadata(metadata)
+    }
+
+    #[test]
+    fn test_codec_from_str() {
+        assert_eq!("JSON".parse::<Codec>().unwrap(), Codec::Json);
+        assert_eq!("binary".parse::<Codec>().unwrap(), Codec::Binary);
+        assert!("xml".parse::<Codec>().is_err());
+    }
+
+    #[test]
+    fn test_payloads_round_trip_in_either_codec() {
+        let payload = payload();
+        for codec in [Codec::Json, Codec::Binary] {
+            let stored = codec.encode(&PAYLOADS, &payload).unwrap();
+            assert_eq!(Codec::detect(&stored), codec);
+
+            let decoded: Payload = Codec::decode(&PAYLOADS, &stored).unwrap();
+            assert_eq!(decoded.hash_id(), payload.hash_id());
+            assert_eq!(decoded.mime_type(), payload.mime_type());
+            assert_eq!(decoded.expiry_time(), payload.expiry_time());
+            assert_eq!(decoded.expiry_mode(), ExpiryMode::Fixed);
+            assert_eq!(decoded.metadata(), payload.metadata());
+        }
+    }
+
+    #[test]
+    fn test_binary_entries_are_smaller() {
+        let payload = payload();
+        let json = Codec::Json.encode(&PAYLOADS, &payload).unwrap();
+        let binary = Codec::Binary.encode(&PAYLOADS, &payload).unwrap();
+        assert!(binary.len() < json.len(), "{} >= {}", binary.len(), json.len());
+    }
+
+    #[test]
+    fn test_binary_entries_are_migrated() {
+        // A revision of the first schema version, written by the binary codec
+        let mut stored = vec![BINARY_TAG];
+        rmp_serde::encode::write_named(
+            &mut stored,
+            &serde_json::json!({
+                "number": 1,
+                "mime_type": "imagepng",
+                "created_at": "2024-06-01T14:20:00Z",
+            }),
+        )
+        .unwrap();
+        let revision: Revision = Codec::decode(&REVISIONS, &stored).unwrap();
+        assert_eq!(revision.mime_type().essence(), "image/png");
+    }
+
+    #[test]
+    fn test_malformed_entries_are_refused() {
+        assert!(matches!(
+            Codec::decode::<Payload>(&PAYLOADS, &[BINARY_TAG, 0xc1]),
+            Err(CodecError::Decode(_))
+        ));
+        assert!(matches!(
+            Codec::decode::<Payload>(&PAYLOADS, b"{oops"),
+            Err(CodecError::Schema(SchemaError::Json(_)))
+        ));
+    }
+}
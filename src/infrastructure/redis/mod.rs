@@ -2,8 +2,43 @@
 //!
 //! This module provides Redis connection pooling and basic operations
 //! for storing and retrieving payloads.
+//!
+//! Each payload is stored as a Redis hash under `payload:{hash_id}` with the
+//! fields `meta`, the payload metadata in a versioned [`schema`](super::schema)
+//! envelope written by the configured [`Codec`], and `content`, the raw
+//! content bytes. Counters that change on
+//! reads (`views`/`viewed_at` and `failed_unlocks`/`failed_unlock_at`) live
+//! in their own fields so they can be updated in place, as do the expiry
+//! fields `expiry_time`, `expiry_limit` and `expiry_deadline` (Unix times
+//! in milliseconds) and `updated_at`; their values take precedence over the
+//! copies in `meta`. Payloads whose expiry moves with views also store
+//! `expiry_mode` and `expiry_timeout` (milliseconds), so views can push the
+//! expiry forward inside Redis. The key expires at the payload's expiry time,
+//! to the millisecond, so Redis evicts idle payloads on its own. Reads fetch
+//! a payload and record the view in a single script, which also enforces the
+//! view limit and removes one-time payloads.
+//!
+//! The current revision number lives in the field `revision`, and earlier
+//! revisions in the fields `revision:{n}:meta` and `revision:{n}:content` of
+//! the same hash, so they expire and are deleted together with the payload.
+//! The field `version` holds the payload's version; scripts changing the
+//! payload on behalf of its owner compare and increment it atomically.
+//!
+//! Aliases are plain string keys `alias:{alias}` holding the hash ID they
+//! resolve to, with a TTL of their own.
+//!
+//! Scripts that remove a payload also delete its alias keys, whose names they
+//! read from the payload rather than receive in `KEYS`. A payload and its
+//! aliases hash to unrelated slots, so this backend needs a standalone Redis
+//! server (with or without replicas) and does not support Redis Cluster.
+//!
+//! Releases before payloads were stored as hashes kept each payload as a
+//! JSON string, content included, under the same key. Such payloads are
+//! moved into the hash layout the first time a command finds one.
 
+use chrono::{DateTime, Utc};
 use deadpool_redis::{Config, Pool, Runtime};
+use lazy_static::lazy_static;
 use redis;
 use serde_json;
 use thiserror::Error;
@@ -11,10 +46,357 @@ use async_trait::async_trait;
 use tracing::{debug, error, info};
 
 use crate::{
-    application::repository::Repository,
-    domain::{hash_id::HashId, payload::Payload},
+    application::repository::{oldest_kept_revision, Repository, RepositoryError},
+    domain::{expiry::ExpiryMode, hash_id::HashId, payload::Payload, revision::Revision},
+    infrastructure::schema::{PAYLOADS, REVISIONS},
 };
 
+mod codec;
+
+pub use codec::{Codec, CodecError, BINARY_TAG};
+
+/// Hash field holding the payload metadata in a schema envelope
+const META_FIELD: &str = "meta";
+
+/// Hash field holding the raw payload content
+const CONTENT_FIELD: &str = "content";
+
+/// Hash field holding the view count
+const VIEWS_FIELD: &str = "views";
+
+/// Hash field holding the RFC 3339 time of the last view
+const VIEWED_AT_FIELD: &str = "viewed_at";
+
+/// Hash field holding the number of wrong passwords given
+const FAILED_UNLOCKS_FIELD: &str = "failed_unlocks";
+
+/// Hash field holding the RFC 3339 time of the last wrong password
+const FAILED_UNLOCK_AT_FIELD: &str = "failed_unlock_at";
+
+/// Hash field holding the expiry time in Unix milliseconds, once changed
+/// or for payloads whose expiry moves with views
+const EXPIRY_TIME_FIELD: &str = "expiry_time";
+
+/// Hash field holding the RFC 3339 time of the last update, once changed
+const UPDATED_AT_FIELD: &str = "updated_at";
+
+/// Hash field holding the latest allowed expiry time in Unix milliseconds,
+/// for payloads whose expiry moves with views
+const EXPIRY_LIMIT_FIELD: &str = "expiry_limit";
+
+/// Hash field holding the expiry time set by views in Unix milliseconds
+const EXPIRY_DEADLINE_FIELD: &str = "expiry_deadline";
+
+/// Hash field holding the expiry mode, for payloads whose expiry moves
+/// with views
+const EXPIRY_MODE_FIELD: &str = "expiry_mode";
+
+/// Hash field holding the expiry mode's timeout in milliseconds
+const EXPIRY_TIMEOUT_FIELD: &str = "expiry_timeout";
+
+/// Hash field holding the current revision number
+const REVISION_FIELD: &str = "revision";
+
+/// Hash field holding the current version number
+const VERSION_FIELD: &str = "version";
+
+/// Times a password attempt is tried again when other attempts on the same
+/// payload keep getting recorded first
+const MAX_UNLOCK_RESERVE_ATTEMPTS: usize = 3;
+
+/// Stored hash fields, in the order they are fetched
+type StoredFields = (
+    Option<Vec<u8>>,
+    Option<Vec<u8>>,
+    Option<u64>,
+    Option<String>,
+    Option<u64>,
+    Option<String>,
+    Option<i64>,
+    Option<String>,
+    Option<i64>,
+    Option<i64>,
+    Option<u64>,
+);
+
+/// Lua function prefixed to scripts that delete payloads. Deletes the keys of
+/// a payload's alias and share code if they still resolve to it, so removing
+/// a payload releases its names in the same step. The alias keys are derived
+/// from the stored payload and not declared in `KEYS`, which only works on a
+/// standalone Redis server.
+const RELEASE_NAMES: &str = r"
+local function release_names(key)
+    local meta = redis.call('HGET', key, 'meta')
+    if not meta then
+        return
+    end
+    local stored
+    if string.byte(meta, 1) == 0xc1 then
+        stored = cmsgpack.unpack(string.sub(meta, 2))
+    else
+        stored = cjson.decode(meta)
+    end
+    local payload = stored.payload or stored
+    for _, field in ipairs({'alias', 'share_code'}) do
+        local name = payload[field]
+        if type(name) == 'string' and redis.call('GET', 'alias:' .. name) == payload.hash_id then
+            redis.call('DEL', 'alias:' .. name)
+        end
+    end
+end
+";
+
+lazy_static! {
+    /// Fetches an existing payload and records a view of it, in one step.
+    /// Takes the time of the view in RFC 3339 and as a Unix time in
+    /// milliseconds, and 1 if the payload's password was checked. Returns
+    /// nil if the payload does not exist or has expired, deleting it in the
+    /// latter case, and without recording the view -1 if it needs a password
+    /// that was not checked or 0 if it has used up its views. Otherwise
+    /// increments the view count, records the time of the view, pushes the
+    /// expiry forward if the payload's expiry mode asks for it, deletes
+    /// one-time payloads and returns the payload's stored fields. The TTL is
+    /// only changed by expiry modes. Deleted payloads release their names.
+    static ref VIEW_SCRIPT: redis::Script = redis::Script::new(&[RELEASE_NAMES, r"
+        local meta = redis.call('HGET', KEYS[1], 'meta')
+        if not meta then
+            return false
+        end
+        local expiry_time = tonumber(redis.call('HGET', KEYS[1], 'expiry_time'))
+        if expiry_time and expiry_time <= tonumber(ARGV[2]) then
+            release_names(KEYS[1])
+            redis.call('DEL', KEYS[1])
+            return false
+        end
+        local stored
+        if string.byte(meta, 1) == 0xc1 then
+            stored = cmsgpack.unpack(string.sub(meta, 2))
+        else
+            stored = cjson.decode(meta)
+        end
+        local payload = stored.payload or stored
+        if ARGV[3] ~= '1' and type(payload.password_hash) == 'string' then
+            return -1
+        end
+        local views = tonumber(redis.call('HGET', KEYS[1], 'views')) or payload.view_count or 0
+        if type(payload.max_views) == 'number' and views >= payload.max_views then
+            return 0
+        end
+        views = views + 1
+        redis.call('HSET', KEYS[1], 'views', string.format('%d', views), 'viewed_at', ARGV[1])
+        local mode = redis.call('HGET', KEYS[1], 'expiry_mode')
+        if mode == 'idle' or (mode == 'after_first_view' and views == 1) then
+            local timeout = tonumber(redis.call('HGET', KEYS[1], 'expiry_timeout'))
+            local limit = tonumber(redis.call('HGET', KEYS[1], 'expiry_limit'))
+            local deadline = tonumber(ARGV[2]) + timeout
+            local expiry = string.format('%d', math.min(deadline, limit))
+            redis.call('HSET', KEYS[1], 'expiry_deadline', string.format('%d', deadline),
+                'expiry_time', expiry)
+            redis.call('PEXPIREAT', KEYS[1], expiry)
+        end
+        local fields = redis.call('HMGET', KEYS[1], 'meta', 'content', 'views', 'viewed_at',
+            'failed_unlocks', 'failed_unlock_at', 'expiry_time', 'updated_at',
+            'expiry_limit', 'expiry_deadline', 'version')
+        if payload.one_time then
+            release_names(KEYS[1])
+            redis.call('DEL', KEYS[1])
+        end
+        return fields
+        "].concat());
+
+    /// Records a password attempt on an existing payload as a wrong password,
+    /// if its failed unlock count is still the expected one. Takes the
+    /// expected count and the RFC 3339 time of the attempt, and returns 1 if
+    /// the attempt was recorded.
+    static ref RESERVE_UNLOCK_SCRIPT: redis::Script = redis::Script::new(
+        r"
+        if redis.call('HEXISTS', KEYS[1], 'meta') == 0 then
+            return 0
+        end
+        if (tonumber(redis.call('HGET', KEYS[1], 'failed_unlocks')) or 0) ~= tonumber(ARGV[1]) then
+            return 0
+        end
+        redis.call('HINCRBY', KEYS[1], 'failed_unlocks', 1)
+        redis.call('HSET', KEYS[1], 'failed_unlock_at', ARGV[2])
+        return 1
+        ",
+    );
+
+    /// Hands back a password attempt recorded by RESERVE_UNLOCK_SCRIPT. Takes
+    /// the RFC 3339 time of the attempt and of the failed unlock before it,
+    /// or an empty string if there was none; the latter is restored unless
+    /// another attempt has been recorded since.
+    static ref RELEASE_UNLOCK_SCRIPT: redis::Script = redis::Script::new(
+        r"
+        if redis.call('HEXISTS', KEYS[1], 'meta') == 0 then
+            return 0
+        end
+        if (tonumber(redis.call('HGET', KEYS[1], 'failed_unlocks')) or 0) > 0 then
+            redis.call('HINCRBY', KEYS[1], 'failed_unlocks', -1)
+        end
+        if redis.call('HGET', KEYS[1], 'failed_unlock_at') == ARGV[1] then
+            if ARGV[2] == '' then
+                redis.call('HDEL', KEYS[1], 'failed_unlock_at')
+            else
+                redis.call('HSET', KEYS[1], 'failed_unlock_at', ARGV[2])
+            end
+        end
+        return 1
+        ",
+    );
+
+    /// Changes the expiry of an existing payload and increments its version.
+    /// Takes the expiry as a Unix time in milliseconds, the RFC 3339 update
+    /// time, the expected version, or an empty string to accept any, and the
+    /// current Unix time in milliseconds. Returns the payload's stored
+    /// fields, nil if it does not exist, or 0 if it is at another version.
+    /// For payloads whose expiry moves with views this changes the limit,
+    /// keeping any earlier deadline. The content is left alone, and an
+    /// expiry in the past deletes the key and releases the payload's names.
+    static ref UPDATE_EXPIRY_SCRIPT: redis::Script = redis::Script::new(&[RELEASE_NAMES, r"
+        if redis.call('HEXISTS', KEYS[1], 'meta') == 0 then
+            return false
+        end
+        local version = redis.call('HGET', KEYS[1], 'version') or '1'
+        if ARGV[3] ~= '' and version ~= ARGV[3] then
+            return 0
+        end
+        redis.call('HSET', KEYS[1], 'version', tonumber(version) + 1)
+        local expiry = ARGV[1]
+        if redis.call('HEXISTS', KEYS[1], 'expiry_mode') == 1 then
+            redis.call('HSET', KEYS[1], 'expiry_limit', ARGV[1])
+            local deadline = tonumber(redis.call('HGET', KEYS[1], 'expiry_deadline'))
+            if deadline and deadline < tonumber(ARGV[1]) then
+                expiry = string.format('%d', deadline)
+            end
+        end
+        redis.call('HSET', KEYS[1], 'expiry_time', expiry, 'updated_at', ARGV[2])
+        local fields = redis.call('HMGET', KEYS[1], 'meta', 'content', 'views', 'viewed_at',
+            'failed_unlocks', 'failed_unlock_at', 'expiry_time', 'updated_at',
+            'expiry_limit', 'expiry_deadline', 'version')
+        if tonumber(expiry) <= tonumber(ARGV[4]) then
+            release_names(KEYS[1])
+        end
+        redis.call('PEXPIREAT', KEYS[1], expiry)
+        return fields
+        "].concat());
+
+    /// Replaces the content of an existing payload if it is still at the
+    /// expected version, keeping the replaced revision. Takes the expected
+    /// version, the replaced revision's number, encoded metadata and content,
+    /// then the new metadata, content, revision and version numbers, RFC 3339
+    /// update time and the number of the oldest revision to keep. Earlier
+    /// revisions are deleted, walking back until one is already gone. Returns
+    /// the payload's stored fields, nil if it does not exist, or 0 if it is
+    /// at another version.
+    static ref REPLACE_CONTENT_SCRIPT: redis::Script = redis::Script::new(
+        r"
+        if redis.call('HEXISTS', KEYS[1], 'meta') == 0 then
+            return false
+        end
+        if (redis.call('HGET', KEYS[1], 'version') or '1') ~= ARGV[1] then
+            return 0
+        end
+        local revision = 'revision:' .. ARGV[2]
+        redis.call('HSET', KEYS[1], revision .. ':meta', ARGV[3], revision .. ':content', ARGV[4],
+            'meta', ARGV[5], 'content', ARGV[6], 'revision', ARGV[7], 'version', ARGV[8],
+            'updated_at', ARGV[9])
+        local dropped = tonumber(ARGV[10]) - 1
+        while dropped > 0 and redis.call('HDEL', KEYS[1], 'revision:' .. dropped .. ':meta',
+                'revision:' .. dropped .. ':content') > 0 do
+            dropped = dropped - 1
+        end
+        return redis.call('HMGET', KEYS[1], 'meta', 'content', 'views', 'viewed_at',
+            'failed_unlocks', 'failed_unlock_at', 'expiry_time', 'updated_at',
+            'expiry_limit', 'expiry_deadline', 'version')
+        ",
+    );
+
+    /// Changes the expiry of an alias if it resolves to the given hash ID.
+    /// Takes the hash ID and the expiry as a Unix time in milliseconds.
+    static ref UPDATE_ALIAS_EXPIRY_SCRIPT: redis::Script = redis::Script::new(
+        r"
+        if redis.call('GET', KEYS[1]) ~= ARGV[1] then
+            return 0
+        end
+        return redis.call('PEXPIREAT', KEYS[1], ARGV[2])
+        ",
+    );
+
+    /// Deletes a payload and releases its names. Returns the number of keys
+    /// DEL removed, so 0 if the payload does not exist.
+    static ref DELETE_SCRIPT: redis::Script = redis::Script::new(&[RELEASE_NAMES, r"
+        release_names(KEYS[1])
+        return redis.call('DEL', KEYS[1])
+        "].concat());
+
+    /// Deletes an existing payload like DELETE_SCRIPT if it is at the
+    /// expected version. Returns 1 if the payload was deleted, nil if it
+    /// does not exist, or 0 if it is at another version.
+    static ref DELETE_VERSION_SCRIPT: redis::Script = redis::Script::new(&[RELEASE_NAMES, r"
+        if redis.call('HEXISTS', KEYS[1], 'meta') == 0 then
+            return false
+        end
+        if (redis.call('HGET', KEYS[1], 'version') or '1') ~= ARGV[1] then
+            return 0
+        end
+        release_names(KEYS[1])
+        return redis.call('DEL', KEYS[1])
+        "].concat());
+
+    /// Replaces a payload stored as a JSON string by an earlier release with
+    /// its hash, unless the key changed in the meantime. Takes the JSON
+    /// string, the expiry as a Unix time in milliseconds and field/value
+    /// pairs, and returns 1 if the payload was replaced.
+    static ref UPGRADE_LEGACY_SCRIPT: redis::Script = redis::Script::new(
+        r"
+        if redis.call('TYPE', KEYS[1]).ok ~= 'string' or redis.call('GET', KEYS[1]) ~= ARGV[1] then
+            return 0
+        end
+        redis.call('DEL', KEYS[1])
+        redis.call('HSET', KEYS[1], unpack(ARGV, 3))
+        redis.call('PEXPIREAT', KEYS[1], ARGV[2])
+        return 1
+        ",
+    );
+
+    /// Stores a new payload unless its key already exists. Takes the expiry
+    /// as a Unix time in milliseconds followed by field/value pairs, and
+    /// returns 1 if the payload was stored.
+    static ref INSERT_SCRIPT: redis::Script = redis::Script::new(
+        r"
+        if redis.call('EXISTS', KEYS[1]) == 1 then
+            return 0
+        end
+        redis.call('HSET', KEYS[1], unpack(ARGV, 2))
+        redis.call('PEXPIREAT', KEYS[1], ARGV[1])
+        return 1
+        ",
+    );
+}
+
+/// Returns true if a command failed because the key holds a payload stored
+/// as a JSON string by an earlier release.
+fn is_wrong_type(err: &redis::RedisError) -> bool {
+    err.code() == Some("WRONGTYPE") || err.to_string().contains("WRONGTYPE")
+}
+
+/// Runs a query against a payload's key, and if the key still holds a
+/// payload stored as a JSON string by an earlier release, moves it into the
+/// hash layout and runs the query again.
+macro_rules! upgrading_legacy {
+    ($repo:expr, $conn:expr, $key:expr, $query:expr) => {{
+        let result = $query;
+        match result {
+            Err(e) if is_wrong_type(&e) => {
+                $repo.upgrade_legacy(&mut $conn, $key).await?;
+                $query
+            }
+            result => result,
+        }
+    }};
+}
+
 /// Redis configuration
 #[derive(Clone, Debug)]
 pub struct RedisConfig {
@@ -24,6 +406,8 @@ pub struct RedisConfig {
     pub pool_max_size: usize,
     /// Connection timeout in seconds
     pub connection_timeout: u64,
+    /// Codec metadata is written with; entries written by either are read
+    pub codec: Codec,
 }
 
 impl Default for RedisConfig {
@@ -32,6 +416,7 @@ impl Default for RedisConfig {
             url: "redis://localhost:6379".to_string(),
             pool_max_size: 16,
             connection_timeout: 5,
+            codec: Codec::default(),
         }
     }
 }
@@ -49,10 +434,43 @@ pub enum RedisError {
     Json(#[from] serde_json::Error),
 }
 
+impl From<redis::RedisError> for RedisError {
+    fn from(err: redis::RedisError) -> Self {
+        if err.is_io_error()
+            || err.is_connection_refusal()
+            || err.is_connection_dropped()
+            || err.is_timeout()
+        {
+            RedisError::Connection(err.to_string())
+        } else {
+            RedisError::Operation(err.to_string())
+        }
+    }
+}
+
+impl From<RedisError> for RepositoryError {
+    fn from(err: RedisError) -> Self {
+        match err {
+            RedisError::PoolCreation(msg) | RedisError::Connection(msg) => {
+                RepositoryError::Unavailable(msg)
+            }
+            RedisError::Operation(msg) => RepositoryError::Internal(msg),
+            RedisError::Json(e) => RepositoryError::Serialization(e.to_string()),
+        }
+    }
+}
+
+impl From<redis::RedisError> for RepositoryError {
+    fn from(err: redis::RedisError) -> Self {
+        RedisError::from(err).into()
+    }
+}
+
 /// Redis repository for storing and retrieving payloads
 #[derive(Clone)]
 pub struct RedisRepository {
     pool: Pool,
+    codec: Codec,
 }
 
 impl RedisRepository {
@@ -63,7 +481,10 @@ impl RedisRepository {
             .create_pool(Some(Runtime::Tokio1))
             .map_err(|e| RedisError::PoolCreation(e.to_string()))?;
 
-        Ok(Self { pool })
+        Ok(Self {
+            pool,
+            codec: config.codec,
+        })
     }
 
     /// Get a Redis connection from the pool
@@ -79,6 +500,183 @@ impl RedisRepository {
         format!("payload:{}", hash_id.as_string())
     }
     
+    /// Generate Redis key for an alias
+    fn alias_key(alias: &str) -> String {
+        format!("alias:{}", alias)
+    }
+
+    /// Hash fields and values storing a payload.
+    ///
+    /// Metadata is stored by the configured codec and content as raw bytes
+    /// in the same hash, so binary content is never escaped.
+    fn encode_fields(&self, payload: &Payload) -> Result<Vec<(&'static str, Vec<u8>)>, RepositoryError> {
+        let mut fields = vec![
+            (META_FIELD, self.codec.encode(&PAYLOADS, payload)?),
+            (CONTENT_FIELD, payload.content().to_vec()),
+            (VIEWS_FIELD, payload.view_count().to_string().into_bytes()),
+            (FAILED_UNLOCKS_FIELD, payload.failed_unlocks().to_string().into_bytes()),
+            (REVISION_FIELD, payload.revision().to_string().into_bytes()),
+            (VERSION_FIELD, payload.version().to_string().into_bytes()),
+        ];
+        if let Some(viewed_at) = payload.viewed_at() {
+            fields.push((VIEWED_AT_FIELD, viewed_at.to_rfc3339().into_bytes()));
+        }
+        if let Some(failed_unlock_at) = payload.failed_unlock_at() {
+            fields.push((FAILED_UNLOCK_AT_FIELD, failed_unlock_at.to_rfc3339().into_bytes()));
+        }
+
+        // Views of payloads whose expiry moves need these to reschedule it
+        let millis = |time: DateTime<Utc>| time.timestamp_millis().to_string().into_bytes();
+        if let Some(timeout) = payload.expiry_mode().timeout() {
+            let mode = match payload.expiry_mode() {
+                ExpiryMode::AfterFirstView { .. } => "after_first_view",
+                _ => "idle",
+            };
+            fields.push((EXPIRY_MODE_FIELD, mode.as_bytes().to_vec()));
+            fields.push((EXPIRY_TIMEOUT_FIELD, timeout.num_milliseconds().to_string().into_bytes()));
+            fields.push((EXPIRY_TIME_FIELD, millis(payload.expiry_time())));
+            fields.push((EXPIRY_LIMIT_FIELD, millis(payload.expiry_limit())));
+            if let Some(deadline) = payload.expiry_deadline() {
+                fields.push((EXPIRY_DEADLINE_FIELD, millis(deadline)));
+            }
+        }
+        Ok(fields)
+    }
+
+    /// Command fetching the stored hash fields of a payload
+    fn fetch_fields(key: &str) -> redis::Cmd {
+        let mut cmd = redis::cmd("HMGET");
+        cmd.arg(key)
+            .arg(META_FIELD)
+            .arg(CONTENT_FIELD)
+            .arg(VIEWS_FIELD)
+            .arg(VIEWED_AT_FIELD)
+            .arg(FAILED_UNLOCKS_FIELD)
+            .arg(FAILED_UNLOCK_AT_FIELD)
+            .arg(EXPIRY_TIME_FIELD)
+            .arg(UPDATED_AT_FIELD)
+            .arg(EXPIRY_LIMIT_FIELD)
+            .arg(EXPIRY_DEADLINE_FIELD)
+            .arg(VERSION_FIELD);
+        cmd
+    }
+
+    /// Parse a stored RFC 3339 timestamp
+    fn parse_time(value: Option<String>) -> Result<Option<DateTime<Utc>>, RepositoryError> {
+        value
+            .map(|value| {
+                DateTime::parse_from_rfc3339(&value)
+                    .map(|time| time.with_timezone(&Utc))
+                    .map_err(|e| RepositoryError::Serialization(e.to_string()))
+            })
+            .transpose()
+    }
+
+    /// Parse a stored Unix time in milliseconds
+    fn parse_millis(value: Option<i64>) -> Result<Option<DateTime<Utc>>, RepositoryError> {
+        value
+            .map(|value| {
+                DateTime::from_timestamp_millis(value).ok_or_else(|| {
+                    RepositoryError::Serialization(format!("Invalid timestamp: {}", value))
+                })
+            })
+            .transpose()
+    }
+
+    /// Rebuild a payload from its stored hash fields
+    fn decode_payload(fields: StoredFields) -> Result<Option<Payload>, RepositoryError> {
+        let (
+            meta,
+            content,
+            views,
+            viewed_at,
+            failed_unlocks,
+            failed_unlock_at,
+            expiry_time,
+            updated_at,
+            expiry_limit,
+            expiry_deadline,
+            version,
+        ) = fields;
+        let Some(meta) = meta else {
+            return Ok(None);
+        };
+
+        let mut payload: Payload = Codec::decode(&PAYLOADS, &meta)?;
+        payload.restore_content(content.unwrap_or_default());
+
+        let viewed_at = Self::parse_time(viewed_at)?.or(payload.viewed_at());
+        payload.restore_views(views.unwrap_or(payload.view_count()), viewed_at);
+
+        let failed_unlock_at = Self::parse_time(failed_unlock_at)?.or(payload.failed_unlock_at());
+        payload.restore_failed_unlocks(
+            failed_unlocks.unwrap_or(payload.failed_unlocks()),
+            failed_unlock_at,
+        );
+
+        if let Some(expiry_time) = Self::parse_millis(expiry_time)? {
+            let expiry_limit = Self::parse_millis(expiry_limit)?;
+            let expiry_deadline = Self::parse_millis(expiry_deadline)?;
+            let updated_at = Self::parse_time(updated_at)?.unwrap_or(payload.updated_at());
+            payload.restore_expiry(expiry_time, expiry_limit, expiry_deadline, updated_at);
+        }
+        payload.restore_version(version.unwrap_or(payload.version()));
+
+        Ok(Some(payload))
+    }
+
+    /// Rebuild a payload stored as a JSON string by an earlier release, which
+    /// held the content inline
+    fn decode_legacy(stored: &[u8]) -> Result<Payload, RepositoryError> {
+        let mut stored: serde_json::Value = serde_json::from_slice(stored)?;
+        let content = match stored.as_object_mut().and_then(|stored| stored.remove(CONTENT_FIELD)) {
+            Some(serde_json::Value::String(content)) => content.into_bytes(),
+            _ => {
+                return Err(RepositoryError::Serialization(
+                    "Legacy payload has no content".to_string(),
+                ))
+            }
+        };
+
+        let mut payload: Payload = PAYLOADS.restore(stored)?;
+        payload.restore_content(content);
+        Ok(payload)
+    }
+
+    /// Move a payload stored as a JSON string by an earlier release into the
+    /// hash layout. Keys that no longer hold the string read are left alone,
+    /// so concurrent upgrades and deletions are safe.
+    async fn upgrade_legacy(
+        &self,
+        conn: &mut deadpool_redis::Connection,
+        key: &str,
+    ) -> Result<(), RepositoryError> {
+        let stored: Option<Vec<u8>> = match redis::cmd("GET").arg(key).query_async(conn).await {
+            Ok(stored) => stored,
+            // Upgraded by another caller in the meantime
+            Err(e) if is_wrong_type(&e) => return Ok(()),
+            Err(e) => return Err(e.into()),
+        };
+        let Some(stored) = stored else {
+            return Ok(());
+        };
+
+        let payload = Self::decode_legacy(&stored)?;
+        let mut invocation = UPGRADE_LEGACY_SCRIPT.key(key);
+        invocation
+            .arg(&stored)
+            .arg(payload.expiry_time().timestamp_millis());
+        for (field, value) in self.encode_fields(&payload)? {
+            invocation.arg(field).arg(value);
+        }
+
+        let upgraded: bool = invocation.invoke_async(conn).await?;
+        if upgraded {
+            info!(key = %key, "Moved payload stored by an earlier release into a hash");
+        }
+        Ok(())
+    }
+
     /// Disable the stop-writes-on-bgsave-error setting in Redis
     /// This is useful for development environments where we don't care about data persistence
     pub async fn disable_stop_writes_on_bgsave_error(&self) -> Result<(), RedisError> {
@@ -97,21 +695,29 @@ impl RedisRepository {
 
 #[async_trait]
 impl Repository for RedisRepository {
-    async fn save(&self, payload: &Payload) -> Result<(), anyhow::Error> {
+    async fn save(&self, payload: &Payload) -> Result<(), RepositoryError> {
         let mut conn = self.get_conn().await?;
         let key = Self::payload_key(payload.hash_id());
-        let json = serde_json::to_string(payload)?;
-
-        // Calculate expiry duration
-        let expiry = payload.expiry_time().timestamp() - chrono::Utc::now().timestamp();
         
         // Only save if not expired
-        if expiry > 0 {
-            let _: () = redis::cmd("SET")
+        if payload.expiry_time() > Utc::now() {
+            let mut fields = redis::cmd("HSET");
+            fields.arg(&key);
+            for (field, value) in self.encode_fields(payload)? {
+                fields.arg(field).arg(value);
+            }
+
+            let _: () = redis::pipe()
+                .atomic()
+                .cmd("DEL")
                 .arg(&key)
-                .arg(&json)
-                .arg("EX")
-                .arg(expiry as usize)
+                .ignore()
+                .add_command(fields)
+                .ignore()
+                .cmd("PEXPIREAT")
+                .arg(&key)
+                .arg(payload.expiry_time().timestamp_millis())
+                .ignore()
                 .query_async(&mut conn)
                 .await?;
         }
@@ -119,66 +725,329 @@ impl Repository for RedisRepository {
         Ok(())
     }
 
-    async fn get(&self, hash_id: &HashId) -> Result<Option<Payload>, anyhow::Error> {
+    async fn insert(&self, payload: &Payload) -> Result<(), RepositoryError> {
+        let mut conn = self.get_conn().await?;
+        let key = Self::payload_key(payload.hash_id());
+
+        // Like save, payloads that are already expired are not written
+        if payload.expiry_time() <= Utc::now() {
+            return Ok(());
+        }
+
+        let mut invocation = INSERT_SCRIPT.key(&key);
+        invocation.arg(payload.expiry_time().timestamp_millis());
+        for (field, value) in self.encode_fields(payload)? {
+            invocation.arg(field).arg(value);
+        }
+
+        let inserted: bool = invocation.invoke_async(&mut conn).await?;
+        if !inserted {
+            return Err(RepositoryError::Conflict(format!(
+                "Hash ID {} is already taken",
+                payload.hash_id().as_string()
+            )));
+        }
+        Ok(())
+    }
+
+    async fn get(&self, hash_id: &HashId) -> Result<Option<Payload>, RepositoryError> {
         let mut conn = self.get_conn().await?;
         let key = Self::payload_key(hash_id);
 
-        let json: Option<String> = redis::cmd("GET")
-            .arg(&key)
-            .query_async::<_, Option<String>>(&mut conn)
-            .await
-            .map_err(|e| anyhow::anyhow!("Failed to get payload: {}", e))?;
+        let fields: StoredFields = upgrading_legacy!(
+            self,
+            conn,
+            &key,
+            Self::fetch_fields(&key).query_async(&mut conn).await
+        )?;
+
+        Self::decode_payload(fields)
+    }
+
+    async fn view(&self, hash_id: &HashId, unlocked: bool) -> Result<Option<Payload>, RepositoryError> {
+        let mut conn = self.get_conn().await?;
+        let key = Self::payload_key(hash_id);
+        let now = Utc::now();
+
+        let reply: redis::Value = upgrading_legacy!(
+            self,
+            conn,
+            &key,
+            VIEW_SCRIPT
+                .key(&key)
+                .arg(now.to_rfc3339())
+                .arg(now.timestamp_millis())
+                .arg(if unlocked { "1" } else { "0" })
+                .invoke_async(&mut conn)
+                .await
+        )?;
 
-        match json {
-            Some(json) => {
-                let payload: Payload = serde_json::from_str(&json)
-                    .map_err(|e| anyhow::anyhow!("Failed to deserialize payload: {}", e))?;
-                Ok(Some(payload))
+        match reply {
+            redis::Value::Nil => Ok(None),
+            redis::Value::Int(0) => Err(RepositoryError::ViewLimitReached),
+            redis::Value::Int(-1) => Err(RepositoryError::PasswordRequired),
+            reply => Self::decode_payload(redis::from_redis_value(&reply)?),
+        }
+    }
+
+    async fn try_unlock_attempt(
+        &self,
+        hash_id: &HashId,
+        at: DateTime<Utc>,
+    ) -> Result<Option<Payload>, RepositoryError> {
+        let key = Self::payload_key(hash_id);
+
+        // Check the throttle against the stored count, and record the attempt
+        // only if no other attempt was recorded in the meantime
+        for _ in 0..MAX_UNLOCK_RESERVE_ATTEMPTS {
+            let Some(payload) = self.get(hash_id).await? else {
+                return Ok(None);
+            };
+            if !payload.is_password_protected() {
+                return Ok(Some(payload));
             }
-            None => Ok(None),
+            if payload.unlock_retry_after().is_some() {
+                return Err(RepositoryError::UnlockThrottled);
+            }
+
+            let mut conn = self.get_conn().await?;
+            let reserved: bool = RESERVE_UNLOCK_SCRIPT
+                .key(&key)
+                .arg(payload.failed_unlocks())
+                .arg(at.to_rfc3339())
+                .invoke_async(&mut conn)
+                .await?;
+            if reserved {
+                return Ok(Some(payload));
+            }
+        }
+
+        // Other attempts keep getting in first
+        Err(RepositoryError::UnlockThrottled)
+    }
+
+    async fn release_unlock_attempt(
+        &self,
+        hash_id: &HashId,
+        at: DateTime<Utc>,
+        previous: Option<DateTime<Utc>>,
+    ) -> Result<(), RepositoryError> {
+        let mut conn = self.get_conn().await?;
+
+        let _: bool = RELEASE_UNLOCK_SCRIPT
+            .key(Self::payload_key(hash_id))
+            .arg(at.to_rfc3339())
+            .arg(previous.map(|previous| previous.to_rfc3339()).unwrap_or_default())
+            .invoke_async(&mut conn)
+            .await?;
+        Ok(())
+    }
+
+    async fn update_expiry(
+        &self,
+        hash_id: &HashId,
+        expiry_time: DateTime<Utc>,
+        version: Option<u64>,
+    ) -> Result<Option<Payload>, RepositoryError> {
+        let mut conn = self.get_conn().await?;
+        let key = Self::payload_key(hash_id);
+
+        let reply: redis::Value = upgrading_legacy!(
+            self,
+            conn,
+            &key,
+            UPDATE_EXPIRY_SCRIPT
+                .key(&key)
+                .arg(expiry_time.timestamp_millis())
+                .arg(Utc::now().to_rfc3339())
+                .arg(version.map(|version| version.to_string()).unwrap_or_default())
+                .arg(Utc::now().timestamp_millis())
+                .invoke_async(&mut conn)
+                .await
+        )?;
+
+        match reply {
+            redis::Value::Nil => Ok(None),
+            redis::Value::Int(0) => Err(RepositoryError::VersionMismatch(version.unwrap_or_default())),
+            reply => Self::decode_payload(redis::from_redis_value(&reply)?),
         }
     }
 
-    async fn delete(&self, hash_id: &HashId) -> Result<(), anyhow::Error> {
+    async fn replace_content(
+        &self,
+        payload: &Payload,
+        previous: &Revision,
+        version: u64,
+        max_revisions: usize,
+    ) -> Result<Option<Payload>, RepositoryError> {
+        let mut conn = self.get_conn().await?;
+        let key = Self::payload_key(payload.hash_id());
+
+        let previous_meta = self.codec.encode(&REVISIONS, previous)?;
+        let meta = self.codec.encode(&PAYLOADS, payload)?;
+        let reply: redis::Value = upgrading_legacy!(
+            self,
+            conn,
+            &key,
+            REPLACE_CONTENT_SCRIPT
+                .key(&key)
+                .arg(version)
+                .arg(previous.number())
+                .arg(&previous_meta)
+                .arg(previous.content())
+                .arg(&meta)
+                .arg(payload.content())
+                .arg(payload.revision())
+                .arg(payload.version())
+                .arg(payload.updated_at().to_rfc3339())
+                .arg(oldest_kept_revision(previous, max_revisions))
+                .invoke_async(&mut conn)
+                .await
+        )?;
+
+        match reply {
+            redis::Value::Nil => Ok(None),
+            redis::Value::Int(0) => Err(RepositoryError::VersionMismatch(version)),
+            reply => Self::decode_payload(redis::from_redis_value(&reply)?),
+        }
+    }
+
+    async fn get_revision(
+        &self,
+        hash_id: &HashId,
+        number: u64,
+    ) -> Result<Option<Revision>, RepositoryError> {
+        let mut conn = self.get_conn().await?;
+        let key = Self::payload_key(hash_id);
+
+        let (meta, content): (Option<Vec<u8>>, Option<Vec<u8>>) = upgrading_legacy!(
+            self,
+            conn,
+            &key,
+            redis::cmd("HMGET")
+                .arg(&key)
+                .arg(format!("{}:{}:meta", REVISION_FIELD, number))
+                .arg(format!("{}:{}:content", REVISION_FIELD, number))
+                .query_async(&mut conn)
+                .await
+        )?;
+
+        let Some(meta) = meta else {
+            return Ok(None);
+        };
+        let mut revision: Revision = Codec::decode(&REVISIONS, &meta)?;
+        revision.restore_content(content.unwrap_or_default());
+        Ok(Some(revision))
+    }
+
+    async fn delete(&self, hash_id: &HashId, version: Option<u64>) -> Result<(), RepositoryError> {
         let mut conn = match self.get_conn().await {
             Ok(conn) => conn,
             Err(e) => {
                 error!(error = %e, "Failed to get Redis connection");
-                return Err(anyhow::anyhow!("Failed to get Redis connection: {}", e));
+                return Err(e.into());
             }
         };
         let key = Self::payload_key(hash_id);
-        
-        // Check if key exists first
-        let exists: bool = match redis::cmd("EXISTS")
-            .arg(&key)
-            .query_async::<_, bool>(&mut conn)
-            .await {
-                Ok(exists) => exists,
-                Err(e) => {
-                    error!(error = %e, key = %key, "Failed to check if key exists in Redis");
-                    return Err(anyhow::anyhow!("Failed to check if payload exists: {}", e));
-                }
+
+        if let Some(version) = version {
+            let deleted: Option<u64> = upgrading_legacy!(
+                self,
+                conn,
+                &key,
+                DELETE_VERSION_SCRIPT
+                    .key(&key)
+                    .arg(version)
+                    .invoke_async(&mut conn)
+                    .await
+            )?;
+            return match deleted {
+                Some(0) => Err(RepositoryError::VersionMismatch(version)),
+                Some(_) => Ok(()),
+                None => Err(RepositoryError::NotFound),
             };
+        }
+        
+        // DEL reports how many keys it removed, which makes the existence
+        // check atomic with the deletion.
+        let removed: u64 = match upgrading_legacy!(
+            self,
+            conn,
+            &key,
+            DELETE_SCRIPT.key(&key).invoke_async(&mut conn).await
+        ) {
+            Ok(removed) => removed,
+            Err(e) => {
+                error!(error = %e, key = %key, "Failed to delete key from Redis");
+                return Err(e.into());
+            }
+        };
 
-        if !exists {
+        if removed == 0 {
             debug!(key = %key, "Key not found in Redis");
-            return Err(anyhow::anyhow!("Payload not found"));
+            return Err(RepositoryError::NotFound);
         }
-        
-        match redis::cmd("DEL")
+
+        info!(key = %key, "Successfully deleted key from Redis");
+        Ok(())
+    }
+
+    async fn insert_alias(
+        &self,
+        alias: &str,
+        hash_id: &HashId,
+        expiry_time: DateTime<Utc>,
+    ) -> Result<(), RepositoryError> {
+        let mut conn = self.get_conn().await?;
+        let key = Self::alias_key(alias);
+
+        // Like payloads, aliases that are already expired are not written
+        if expiry_time <= Utc::now() {
+            return Ok(());
+        }
+
+        // SET NX replies nil instead of OK if the key exists. The alias
+        // expires at the same millisecond as its payload.
+        let reserved: Option<String> = redis::cmd("SET")
             .arg(&key)
-            .query_async::<_, ()>(&mut conn)
-            .await {
-                Ok(_) => {
-                    info!(key = %key, "Successfully deleted key from Redis");
-                    Ok(())
-                }
-                Err(e) => {
-                    error!(error = %e, key = %key, "Failed to delete key from Redis");
-                    Err(anyhow::anyhow!("Failed to delete payload: {}", e))
-                }
-            }
+            .arg(hash_id.as_string())
+            .arg("NX")
+            .arg("PXAT")
+            .arg(expiry_time.timestamp_millis())
+            .query_async(&mut conn)
+            .await?;
+        if reserved.is_none() {
+            return Err(RepositoryError::Conflict(format!("Alias {} is already taken", alias)));
+        }
+        Ok(())
+    }
+
+    async fn update_alias_expiry(
+        &self,
+        alias: &str,
+        hash_id: &HashId,
+        expiry_time: DateTime<Utc>,
+    ) -> Result<(), RepositoryError> {
+        let mut conn = self.get_conn().await?;
+
+        let _: i64 = UPDATE_ALIAS_EXPIRY_SCRIPT
+            .key(Self::alias_key(alias))
+            .arg(hash_id.as_string())
+            .arg(expiry_time.timestamp_millis())
+            .invoke_async(&mut conn)
+            .await?;
+        Ok(())
+    }
+
+    async fn resolve_alias(&self, alias: &str) -> Result<Option<HashId>, RepositoryError> {
+        let mut conn = self.get_conn().await?;
+
+        let hash_id: Option<String> = redis::cmd("GET")
+            .arg(Self::alias_key(alias))
+            .query_async(&mut conn)
+            .await?;
+
+        Ok(hash_id.map(HashId::from_string))
     }
 }
 
@@ -187,36 +1056,12 @@ mod tests {
     use super::*;
     use chrono::{Duration, Utc};
 
-    async fn is_redis_available() -> bool {
-        match RedisRepository::new(RedisConfig::default()) {
-            Ok(repo) => {
-                let conn_result = repo.get_conn().await;
-                if conn_result.is_err() {
-                    return false;
-                }
-                
-                // Try a simple ping command to verify Redis is working properly
-                let mut conn = conn_result.unwrap();
-                let ping_result: Result<String, redis::RedisError> = redis::cmd("PING")
-                    .query_async(&mut conn)
-                    .await;
-                
-                ping_result.is_ok()
-            },
-            Err(_) => false,
-        }
-    }
-
     #[tokio::test]
+    #[ignore = "requires a running Redis server"]
     async fn test_redis_save_and_get() -> Result<(), anyhow::Error> {
-        if !is_redis_available().await {
-            eprintln!("Skipping Redis test: Redis is not available");
-            return Ok(());
-        }
-
         let repo = RedisRepository::new(RedisConfig::default())?;
         let payload = Payload::new(
-            "test content".to_string(),
+            b"test content".to_vec(),
             Some("text/plain".to_string()),
             Some(Utc::now() + Duration::hours(1)),
         )?;
@@ -233,36 +1078,130 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_redis_delete() -> Result<(), anyhow::Error> {
-        if !is_redis_available().await {
-            eprintln!("Skipping Redis test: Redis is not available");
-            return Ok(());
-        }
+    #[ignore = "requires a running Redis server"]
+    async fn test_redis_save_and_get_binary() -> Result<(), anyhow::Error> {
+        let repo = RedisRepository::new(RedisConfig::default())?;
+        let content = vec![0x47, 0x49, 0x46, 0x38, 0x00, 0xff, 0x80, b'"', b'\\'];
+        let payload = Payload::new(content.clone(), Some("image/gif".to_string()), None)?;
+
+        repo.save(&payload).await?;
 
+        let retrieved = repo.get(payload.hash_id()).await?.unwrap();
+        assert_eq!(retrieved.content(), content.as_slice());
+        assert_eq!(retrieved.mime_type().to_string(), "image/gif");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a running Redis server"]
+    async fn test_redis_reads_entries_of_either_codec() -> Result<(), anyhow::Error> {
+        let json = RedisRepository::new(RedisConfig::default())?;
+        let binary = RedisRepository::new(RedisConfig {
+            codec: Codec::Binary,
+            ..RedisConfig::default()
+        })?;
+        let payload = Payload::new(b"test content".to_vec(), None, None)?.with_max_views(Some(1));
+
+        // Written by one codec, viewed through the other
+        binary.save(&payload).await?;
+        let viewed = json.view(payload.hash_id(), false).await?.unwrap();
+        assert_eq!(viewed.content(), payload.content());
+        assert_eq!(viewed.view_count(), 1);
+        assert!(json.view(payload.hash_id(), false).await.is_err());
+
+        Ok(())
+    }
+
+    /// A payload as the release before the hash layout stored it under its
+    /// key, with inline content and that release's MIME type names
+    const LEGACY_PAYLOAD: &str = include_str!("../schema/corpus/payload-v1-baseline-string-key.json");
+
+    #[test]
+    fn test_legacy_payloads_are_decoded() {
+        let payload = RedisRepository::decode_legacy(LEGACY_PAYLOAD.as_bytes()).unwrap();
+        assert_eq!(payload.hash_id().as_string(), "1edda9d8c03744e7939c5e7b0ac5156c");
+        assert_eq!(payload.content(), br#"{"status":"ok"}"#);
+        assert_eq!(payload.mime_type().to_string(), "application/json");
+        assert!(payload.viewed_at().is_some());
+        assert_eq!(payload.revision(), 1);
+
+        assert!(RedisRepository::decode_legacy(b"{\"hash_id\":\"abc\"}").is_err());
+    }
+
+    #[test]
+    fn test_wrong_type_errors_are_recognized() {
+        let direct = redis::RedisError::from((
+            redis::ErrorKind::ExtensionError,
+            "WRONGTYPE",
+            "Operation against a key holding the wrong kind of value".to_string(),
+        ));
+        let scripted = redis::RedisError::from((
+            redis::ErrorKind::ResponseError,
+            "script failed",
+            "Error running script: WRONGTYPE Operation against a key holding the wrong kind of value".to_string(),
+        ));
+        let other = redis::RedisError::from((redis::ErrorKind::IoError, "connection reset"));
+        assert!(is_wrong_type(&direct));
+        assert!(is_wrong_type(&scripted));
+        assert!(!is_wrong_type(&other));
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a running Redis server"]
+    async fn test_redis_upgrades_legacy_payloads() -> Result<(), anyhow::Error> {
         let repo = RedisRepository::new(RedisConfig::default())?;
-        let payload = Payload::new("test content".to_string(), None, None)?;
+        // The sample has long expired, so it is stored with a fresh expiry
+        let mut legacy: serde_json::Value = serde_json::from_str(LEGACY_PAYLOAD)?;
+        legacy["expiry_time"] = (Utc::now() + Duration::hours(1)).to_rfc3339().into();
+        let hash_id = HashId::from_string(legacy["hash_id"].as_str().unwrap().to_string());
+        let key = RedisRepository::payload_key(&hash_id);
+        let mut conn = repo.get_conn().await?;
+        redis::cmd("SET")
+            .arg(&key)
+            .arg(legacy.to_string())
+            .arg("EX")
+            .arg(3600)
+            .query_async::<_, ()>(&mut conn)
+            .await?;
+
+        let viewed = repo.view(&hash_id, false).await?.unwrap();
+        assert_eq!(viewed.content(), br#"{"status":"ok"}"#);
+        assert_eq!(viewed.view_count(), 1);
+
+        // Moved into the hash layout, keeping its TTL
+        let key_type: String = redis::cmd("TYPE").arg(&key).query_async(&mut conn).await?;
+        assert_eq!(key_type, "hash");
+        let ttl: i64 = redis::cmd("TTL").arg(&key).query_async(&mut conn).await?;
+        assert!(ttl > 0);
+
+        repo.delete(&hash_id, None).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a running Redis server"]
+    async fn test_redis_delete() -> Result<(), anyhow::Error> {
+        let repo = RedisRepository::new(RedisConfig::default())?;
+        let payload = Payload::new(b"test content".to_vec(), None, None)?;
 
         // Save and verify
         repo.save(&payload).await?;
         assert!(repo.get(payload.hash_id()).await?.is_some());
 
         // Delete and verify
-        repo.delete(payload.hash_id()).await?;
+        repo.delete(payload.hash_id(), None).await?;
         assert!(repo.get(payload.hash_id()).await?.is_none());
 
         Ok(())
     }
 
     #[tokio::test]
+    #[ignore = "requires a running Redis server"]
     async fn test_expired_payload() -> Result<(), anyhow::Error> {
-        if !is_redis_available().await {
-            eprintln!("Skipping Redis test: Redis is not available");
-            return Ok(());
-        }
-
         let repo = RedisRepository::new(RedisConfig::default())?;
         let payload = Payload::new(
-            "test content".to_string(),
+            b"test content".to_vec(),
             None,
             Some(Utc::now() + Duration::seconds(1)),
         )?;
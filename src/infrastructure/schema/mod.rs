@@ -0,0 +1,416 @@
+//! Versioned storage schema for payloads and revisions.
+//!
+//! Backends that serialize records store them in an envelope naming the
+//! version of their schema:
+//!
+//! ```json
+//! {"schema_version": 3, "payload": {"hash_id": "...", "mime_type": "text/plain"}}
+//! ```
+//!
+//! Records are migrated lazily. One written under an older schema is passed
+//! through the [`MigrationRegistry`]'s migrations when it is read, one
+//! version at a time, and is written back in the current form the next time
+//! it is saved. Records stored before envelopes existed are recognized by
+//! their shape.
+//!
+//! Fields added with a serde default need no migration. Renaming, removing
+//! or restructuring a field means raising the record's schema version,
+//! registering a migration from the previous version, and adding a sample
+//! of the old form to the `corpus` directory next to this module, whose
+//! samples the tests decode on every run.
+
+use std::collections::BTreeMap;
+
+use lazy_static::lazy_static;
+use serde::{de::DeserializeOwned, ser::SerializeMap, Serialize, Serializer};
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::application::repository::RepositoryError;
+
+/// Envelope field holding the schema version of the record
+const VERSION_FIELD: &str = "schema_version";
+
+/// Current schema version of stored payloads
+pub const PAYLOAD_SCHEMA_VERSION: u32 = 3;
+
+/// Current schema version of stored revisions
+pub const REVISION_SCHEMA_VERSION: u32 = 2;
+
+/// Storage schema errors
+#[derive(Error, Debug)]
+pub enum SchemaError {
+    #[error("JSON serialization error: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Stored {record} has schema version {version}, but only versions up to {current} are supported")]
+    UnsupportedVersion {
+        record: &'static str,
+        version: u32,
+        current: u32,
+    },
+    #[error("No migration for {record} from schema version {version}")]
+    MissingMigration { record: &'static str, version: u32 },
+    #[error("Failed to migrate {record} from schema version {version}: {message}")]
+    Migration {
+        record: &'static str,
+        version: u32,
+        message: String,
+    },
+}
+
+impl From<SchemaError> for RepositoryError {
+    fn from(err: SchemaError) -> Self {
+        RepositoryError::Serialization(err.to_string())
+    }
+}
+
+/// A record in an envelope with the current schema version of its kind.
+pub struct Envelope<'a, T> {
+    registry: &'a MigrationRegistry,
+    record: &'a T,
+}
+
+impl<T: Serialize> Serialize for Envelope<'_, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut envelope = serializer.serialize_map(Some(2))?;
+        envelope.serialize_entry(VERSION_FIELD, &self.registry.current_version)?;
+        envelope.serialize_entry(self.registry.record, self.record)?;
+        envelope.end()
+    }
+}
+
+/// Upgrades a serialized record from one schema version to the next.
+pub type Migration = fn(&mut Value) -> Result<(), String>;
+
+/// Migrations of one kind of record, up to its current schema version.
+///
+/// # Examples
+///
+/// ```
+/// use jump::infrastructure::schema::MigrationRegistry;
+/// use serde_json::{json, Value};
+///
+/// // Version 2 renamed `name` to `title`
+/// let registry = MigrationRegistry::new("note", 2).with_migration(1, |note| {
+///     let name = note["name"].take();
+///     note["title"] = name;
+///     Ok(())
+/// });
+///
+/// // Records written before version 2 are migrated as they are read
+/// let note: Value = registry.decode(br#"{"name": "Old"}"#).unwrap();
+/// assert_eq!(note["title"], "Old");
+///
+/// // Records are written in an envelope with the current version
+/// let stored = registry.encode(&json!({"title": "New"})).unwrap();
+/// let envelope: Value = serde_json::from_slice(&stored).unwrap();
+/// assert_eq!(envelope, json!({"schema_version": 2, "note": {"title": "New"}}));
+/// assert_eq!(registry.decode::<Value>(&stored).unwrap(), json!({"title": "New"}));
+/// ```
+pub struct MigrationRegistry {
+    record: &'static str,
+    current_version: u32,
+    migrations: BTreeMap<u32, Migration>,
+}
+
+impl MigrationRegistry {
+    /// Creates a registry for records named `record` in their envelope,
+    /// currently at schema version `current_version`.
+    pub fn new(record: &'static str, current_version: u32) -> Self {
+        Self {
+            record,
+            current_version,
+            migrations: BTreeMap::new(),
+        }
+    }
+
+    /// Registers the migration from schema version `from` to `from + 1`.
+    pub fn with_migration(mut self, from: u32, migration: Migration) -> Self {
+        self.migrations.insert(from, migration);
+        self
+    }
+
+    /// Returns the schema version records are written with.
+    pub fn current_version(&self) -> u32 {
+        self.current_version
+    }
+
+    /// Serializes a record in an envelope with the current schema version.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SchemaError::Json` if the record cannot be serialized.
+    pub fn encode<T: Serialize>(&self, record: &T) -> Result<Vec<u8>, SchemaError> {
+        Ok(serde_json::to_vec(&self.envelope(record))?)
+    }
+
+    /// Wraps a record in an envelope with the current schema version, to be
+    /// serialized in any format.
+    pub fn envelope<'a, T: Serialize>(&'a self, record: &'a T) -> Envelope<'a, T> {
+        Envelope {
+            registry: self,
+            record,
+        }
+    }
+
+    /// Deserializes a record written under any supported schema version,
+    /// migrating it to the current one.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SchemaError::UnsupportedVersion` for records written under a
+    /// newer schema, an error if a migration is missing or fails, and
+    /// `SchemaError::Json` if the record does not match its schema.
+    pub fn decode<T: DeserializeOwned>(&self, bytes: &[u8]) -> Result<T, SchemaError> {
+        self.restore(serde_json::from_slice(bytes)?)
+    }
+
+    /// Restores a record from its stored form, read in any format, like
+    /// [`decode`](Self::decode).
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`decode`](Self::decode).
+    pub fn restore<T: DeserializeOwned>(&self, stored: Value) -> Result<T, SchemaError> {
+        let (version, record) = self.open(stored);
+        Ok(serde_json::from_value(self.upgrade(record, version)?)?)
+    }
+
+    /// Returns the schema version and contents of a stored record.
+    ///
+    /// Records stored before envelopes existed carry their version inline,
+    /// if at all, and are otherwise at version 1.
+    fn open(&self, stored: Value) -> (u32, Value) {
+        let version = |value: Option<&Value>| value.and_then(Value::as_u64).map_or(1, |v| v as u32);
+        match stored {
+            Value::Object(mut envelope)
+                if envelope.get(self.record).is_some_and(Value::is_object) =>
+            {
+                let record = envelope.remove(self.record).unwrap_or_default();
+                (version(envelope.get(VERSION_FIELD)), record)
+            }
+            record => (version(record.get(VERSION_FIELD)), record),
+        }
+    }
+
+    /// Migrates a record from schema version `version` to the current one.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SchemaError::UnsupportedVersion` if `version` is newer than
+    /// the current version, or an error if a migration is missing or fails.
+    pub fn upgrade(&self, mut record: Value, version: u32) -> Result<Value, SchemaError> {
+        if version > self.current_version {
+            return Err(SchemaError::UnsupportedVersion {
+                record: self.record,
+                version,
+                current: self.current_version,
+            });
+        }
+        for version in version..self.current_version {
+            let migration = self
+                .migrations
+                .get(&version)
+                .ok_or(SchemaError::MissingMigration {
+                    record: self.record,
+                    version,
+                })?;
+            migration(&mut record).map_err(|message| SchemaError::Migration {
+                record: self.record,
+                version,
+                message,
+            })?;
+        }
+        Ok(record)
+    }
+}
+
+/// Names under which MIME types were stored before they were parsed.
+const LEGACY_MIME_TYPE_NAMES: &[(&str, &str)] = &[
+    ("textplain", "text/plain"),
+    ("texthtml", "text/html"),
+    ("applicationjson", "application/json"),
+    ("imagejpeg", "image/jpeg"),
+    ("imagepng", "image/png"),
+    ("imagegif", "image/gif"),
+];
+
+/// Replaces a MIME type stored under its legacy name with the type itself.
+fn rename_legacy_mime_type(record: &mut Value) -> Result<(), String> {
+    let Some(mime_type) = record.get_mut("mime_type") else {
+        return Ok(());
+    };
+    let name = mime_type.as_str().ok_or("mime_type is not a string")?;
+    if let Some((_, renamed)) = LEGACY_MIME_TYPE_NAMES
+        .iter()
+        .find(|(legacy, _)| *legacy == name)
+    {
+        *mime_type = Value::from(*renamed);
+    }
+    Ok(())
+}
+
+/// Removes the schema version payloads carried inline before envelopes.
+fn remove_inline_schema_version(record: &mut Value) -> Result<(), String> {
+    record
+        .as_object_mut()
+        .ok_or("payload is not an object")?
+        .remove(VERSION_FIELD);
+    Ok(())
+}
+
+lazy_static! {
+    /// Migrations of stored payloads:
+    /// 1. Unversioned, with MIME types possibly stored under legacy names
+    /// 2. Unversioned, with the schema version inline and metadata
+    /// 3. In an envelope
+    pub static ref PAYLOADS: MigrationRegistry =
+        MigrationRegistry::new("payload", PAYLOAD_SCHEMA_VERSION)
+            .with_migration(1, rename_legacy_mime_type)
+            .with_migration(2, remove_inline_schema_version);
+
+    /// Migrations of stored revisions:
+    /// 1. Unversioned, with MIME types possibly stored under legacy names
+    /// 2. In an envelope
+    pub static ref REVISIONS: MigrationRegistry =
+        MigrationRegistry::new("revision", REVISION_SCHEMA_VERSION)
+            .with_migration(1, rename_legacy_mime_type);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::path::Path;
+
+    use crate::domain::{payload::Payload, revision::Revision};
+
+    /// Directory of records as stored by earlier versions
+    fn corpus_dir() -> &'static Path {
+        Path::new(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/src/infrastructure/schema/corpus"
+        ))
+    }
+
+    /// Samples in the corpus, as `(record, version, file name, contents)`.
+    ///
+    /// Samples are named `{record}-v{version}-{description}.json`.
+    fn corpus() -> Vec<(String, u32, String, Vec<u8>)> {
+        let mut samples = Vec::new();
+        for entry in std::fs::read_dir(corpus_dir()).unwrap() {
+            let path = entry.unwrap().path();
+            let name = path.file_name().unwrap().to_string_lossy().to_string();
+            let mut parts = name.splitn(3, '-');
+            let record = parts.next().unwrap().to_string();
+            let version = parts
+                .next()
+                .and_then(|version| version.strip_prefix('v'))
+                .and_then(|version| version.parse().ok())
+                .unwrap_or_else(|| panic!("{} is not named {{record}}-v{{version}}-...", name));
+            samples.push((record, version, name, std::fs::read(&path).unwrap()));
+        }
+        samples.sort_by(|a, b| a.2.cmp(&b.2));
+        samples
+    }
+
+    #[test]
+    fn test_corpus_decodes() {
+        for (record, version, name, bytes) in corpus() {
+            let (registry, decoded) = match record.as_str() {
+                "payload" => (&*PAYLOADS, PAYLOADS.decode::<Payload>(&bytes).map(|_| ())),
+                "revision" => (
+                    &*REVISIONS,
+                    REVISIONS.decode::<Revision>(&bytes).map(|_| ()),
+                ),
+                other => panic!("{} holds an unknown record {}", name, other),
+            };
+            decoded.unwrap_or_else(|e| panic!("{} does not decode: {}", name, e));
+            let (stored_version, _) = registry.open(serde_json::from_slice(&bytes).unwrap());
+            assert_eq!(
+                stored_version, version,
+                "{} is stored as version {}",
+                name, stored_version
+            );
+        }
+    }
+
+    #[test]
+    fn test_corpus_covers_every_version() {
+        let corpus = corpus();
+        for registry in [&*PAYLOADS, &*REVISIONS] {
+            for version in 1..=registry.current_version() {
+                assert!(
+                    corpus
+                        .iter()
+                        .any(|(record, v, _, _)| record == registry.record && *v == version),
+                    "No sample of {} version {} in the corpus",
+                    registry.record,
+                    version
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_legacy_payloads_are_migrated() {
+        let bytes = std::fs::read(corpus_dir().join("payload-v1-legacy-mime-type.json")).unwrap();
+        let payload: Payload = PAYLOADS.decode(&bytes).unwrap();
+        assert_eq!(payload.mime_type().essence(), "text/plain");
+        assert_eq!(payload.revision(), 1);
+        assert_eq!(payload.version(), 1);
+
+        // Stored by the first release, with its content inline
+        let bytes = std::fs::read(corpus_dir().join("payload-v1-baseline-string-key.json")).unwrap();
+        let payload: Payload = PAYLOADS.decode(&bytes).unwrap();
+        assert_eq!(payload.mime_type().essence(), "application/json");
+        assert!(payload.viewed_at().is_some());
+
+        let bytes = std::fs::read(corpus_dir().join("payload-v2-metadata.json")).unwrap();
+        let payload: Payload = PAYLOADS.decode(&bytes).unwrap();
+        assert_eq!(payload.metadata().filename(), Some("deploy-4711.log"));
+
+        let bytes = std::fs::read(corpus_dir().join("revision-v1-legacy-mime-type.json")).unwrap();
+        let revision: Revision = REVISIONS.decode(&bytes).unwrap();
+        assert_eq!(revision.mime_type().essence(), "text/html");
+    }
+
+    #[test]
+    fn test_payloads_round_trip_in_current_envelope() {
+        let payload =
+            Payload::new(b"content".to_vec(), Some("image/gif".to_string()), None).unwrap();
+        let stored = PAYLOADS.encode(&payload).unwrap();
+
+        let envelope: Value = serde_json::from_slice(&stored).unwrap();
+        assert_eq!(envelope[VERSION_FIELD], PAYLOAD_SCHEMA_VERSION);
+        assert_eq!(envelope["payload"]["mime_type"], "image/gif");
+
+        let decoded: Payload = PAYLOADS.decode(&stored).unwrap();
+        assert_eq!(decoded.hash_id(), payload.hash_id());
+        assert_eq!(decoded.mime_type(), payload.mime_type());
+    }
+
+    #[test]
+    fn test_newer_schema_versions_are_refused() {
+        let stored = br#"{"schema_version": 99, "payload": {}}"#;
+        assert!(matches!(
+            PAYLOADS.decode::<Payload>(stored),
+            Err(SchemaError::UnsupportedVersion { version: 99, .. })
+        ));
+    }
+
+    #[test]
+    fn test_missing_and_failing_migrations() {
+        let registry = MigrationRegistry::new("note", 3)
+            .with_migration(1, |_| Err("cannot upgrade".to_string()));
+        assert!(matches!(
+            registry.upgrade(Value::Null, 1),
+            Err(SchemaError::Migration { version: 1, .. })
+        ));
+        assert!(matches!(
+            registry.upgrade(Value::Null, 2),
+            Err(SchemaError::MissingMigration { version: 2, .. })
+        ));
+        assert_eq!(registry.upgrade(Value::Null, 3).unwrap(), Value::Null);
+    }
+}
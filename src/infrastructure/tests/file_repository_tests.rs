@@ -0,0 +1,21 @@
+//! Tests for the file system repository implementation.
+
+use std::sync::Arc;
+
+use tempfile::TempDir;
+
+use crate::application::tests::repository_contract::repository_contract_tests;
+use crate::infrastructure::file::{FileConfig, FileRepository};
+
+/// Open a file repository in a fresh temporary directory
+fn setup() -> (TempDir, Arc<FileRepository>) {
+    let dir = TempDir::new().unwrap();
+    let repository = FileRepository::new(FileConfig {
+        data_dir: dir.path().to_path_buf(),
+        ..FileConfig::default()
+    })
+    .unwrap();
+    (dir, Arc::new(repository))
+}
+
+repository_contract_tests!(setup());
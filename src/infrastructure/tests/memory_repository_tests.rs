@@ -0,0 +1,8 @@
+//! Tests for the in-memory repository implementation.
+
+use std::sync::Arc;
+
+use crate::application::tests::repository_contract::repository_contract_tests;
+use crate::infrastructure::memory::InMemoryRepository;
+
+repository_contract_tests!(((), Arc::new(InMemoryRepository::default())));
@@ -1,5 +1,9 @@
 //! Test utilities for the infrastructure layer.
 
+mod redis_repository_tests;
+mod memory_repository_tests;
+mod file_repository_tests;
+
 use chrono::{DateTime, Utc};
 
 use crate::domain::payload::Payload;
@@ -10,7 +14,7 @@ pub fn create_test_payload(
     expiry_time: Option<DateTime<Utc>>
 ) -> Payload {
     Payload::new(
-        content.to_string(),
+        content.as_bytes().to_vec(),
         Some("text/plain".to_string()),
         expiry_time,
     ).unwrap()
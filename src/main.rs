@@ -1,21 +1,39 @@
-use actix_web::{web, App, HttpServer};
+use actix_web::{http::header, web, App, HttpServer};
 use actix_cors::Cors;
+use chrono::Duration;
 use std::sync::Arc;
 use tracing::info;
 
 use jump::{
-    api::{self, middleware::{ErrorHandlerMiddleware, RateLimitMiddleware, configure_json_error_handling}},
+    api::{
+        self,
+        middleware::{ErrorHandlerMiddleware, RateLimitMiddleware, configure_json_error_handling},
+        v1::ShareCodeLimiter,
+    },
     application::{
         repository::Repository,
         use_cases::{
             CreatePayloadUseCaseImpl,
             GetPayloadUseCaseImpl,
             DeletePayloadUseCaseImpl,
+            UpdatePayloadUseCaseImpl,
+            ReplacePayloadUseCaseImpl,
+            ResolveShareCodeUseCaseImpl,
+            ResolveAliasUseCaseImpl,
         },
     },
+    domain::{
+        expiry::{parse_duration, ExpiryPolicy, HumanDuration},
+        hash_id::{AlphabetGenerator, BASE62_ALPHABET, DEFAULT_LENGTH},
+        mime_type::{MimeRegistry, MimeType, MismatchPolicy, AUTO},
+        revision::DEFAULT_MAX_REVISIONS,
+    },
     infrastructure::{
+        StorageBackend,
         redis::{RedisConfig, RedisRepository},
-        rate_limit::{RateLimitConfig, RedisRateLimiter},
+        memory::{InMemoryConfig, InMemoryRepository},
+        file::{FileConfig, FileRepository},
+        rate_limit::{RateLimitConfig, RateLimiter, RedisRateLimiter, InMemoryRateLimiter},
         logging::{LoggingConfig, init_logging, RequestLogger},
     },
 };
@@ -27,40 +45,194 @@ async fn main() -> std::io::Result<()> {
     
     info!("Starting Jump service");
     
-    // Configure Redis
-    let redis_config = RedisConfig::default();
-    let redis_repo = match RedisRepository::new(redis_config.clone()) {
-        Ok(repo) => {
-            info!("Connected to Redis");
-            repo
-        },
-        Err(e) => {
-            panic!("Failed to connect to Redis: {}", e);
-        }
+    // Select storage backend (STORAGE_BACKEND=redis|memory|file)
+    let backend: StorageBackend = std::env::var("STORAGE_BACKEND")
+        .unwrap_or_else(|_| "redis".to_string())
+        .parse()
+        .unwrap_or_else(|e| panic!("Invalid STORAGE_BACKEND: {}", e));
+    
+    // Configure rate limiting
+    let rate_limit_config = RateLimitConfig {
+        max_requests: 100,
+        window_seconds: 60,
     };
     
-    // Disable Redis persistence error checking for development
-    if let Err(e) = redis_repo.disable_stop_writes_on_bgsave_error().await {
-        info!("Warning: Could not disable Redis persistence error checking: {}", e);
-        info!("Some write operations may fail if Redis cannot persist to disk");
-    } else {
-        info!("Disabled Redis persistence error checking for development");
-    }
+    // Share codes are easy to guess, so lookups by code are limited far more
+    let share_code_rate_limit_config = RateLimitConfig {
+        max_requests: 10,
+        window_seconds: 600,
+    };
     
-    // Create repository
-    let repository: Arc<dyn Repository> = Arc::new(redis_repo.clone());
+    // Create repository and rate limiters for the selected backend
+    let (repository, rate_limiter, share_code_limiter): (
+        Arc<dyn Repository>,
+        Arc<dyn RateLimiter>,
+        Arc<dyn RateLimiter>,
+    ) = match backend {
+        StorageBackend::Redis => {
+            // Codec new entries are written with (REDIS_CODEC=json|binary)
+            let codec = std::env::var("REDIS_CODEC")
+                .map(|codec| codec.parse().unwrap_or_else(|e| panic!("Invalid REDIS_CODEC: {}", e)))
+                .unwrap_or_default();
+            let redis_config = RedisConfig {
+                codec,
+                ..RedisConfig::default()
+            };
+            let redis_repo = match RedisRepository::new(redis_config.clone()) {
+                Ok(repo) => {
+                    info!("Connected to Redis, writing entries as {:?}", codec);
+                    repo
+                },
+                Err(e) => {
+                    panic!("Failed to connect to Redis: {}", e);
+                }
+            };
+            
+            // Disable Redis persistence error checking for development
+            if let Err(e) = redis_repo.disable_stop_writes_on_bgsave_error().await {
+                info!("Warning: Could not disable Redis persistence error checking: {}", e);
+                info!("Some write operations may fail if Redis cannot persist to disk");
+            } else {
+                info!("Disabled Redis persistence error checking for development");
+            }
+            
+            (
+                Arc::new(redis_repo.clone()),
+                Arc::new(RedisRateLimiter::new(redis_repo.clone(), rate_limit_config)),
+                Arc::new(RedisRateLimiter::new(redis_repo, share_code_rate_limit_config)),
+            )
+        }
+        StorageBackend::Memory => {
+            let memory_repo = InMemoryRepository::new(InMemoryConfig::default());
+            memory_repo.start_sweeper();
+            info!("Using in-memory storage");
+            
+            (
+                Arc::new(memory_repo),
+                Arc::new(InMemoryRateLimiter::new(rate_limit_config)),
+                Arc::new(InMemoryRateLimiter::new(share_code_rate_limit_config)),
+            )
+        }
+        StorageBackend::File => {
+            let file_config = FileConfig {
+                data_dir: std::env::var("DATA_DIR")
+                    .map(Into::into)
+                    .unwrap_or_else(|_| FileConfig::default().data_dir),
+                ..FileConfig::default()
+            };
+            let file_repo = match FileRepository::new(file_config.clone()) {
+                Ok(repo) => repo,
+                Err(e) => {
+                    panic!("Failed to open data directory {}: {}", file_config.data_dir.display(), e);
+                }
+            };
+            file_repo.start_sweeper();
+            info!("Using file storage in {}", file_config.data_dir.display());
+            
+            (
+                Arc::new(file_repo),
+                Arc::new(InMemoryRateLimiter::new(rate_limit_config)),
+                Arc::new(InMemoryRateLimiter::new(share_code_rate_limit_config)),
+            )
+        }
+    };
     
+    // Configure hash ID generation (HASH_ID_LENGTH, HASH_ID_ALPHABET)
+    let hash_id_length = std::env::var("HASH_ID_LENGTH")
+        .map(|length| length.parse().unwrap_or_else(|e| panic!("Invalid HASH_ID_LENGTH: {}", e)))
+        .unwrap_or(DEFAULT_LENGTH);
+    let hash_id_alphabet = std::env::var("HASH_ID_ALPHABET")
+        .unwrap_or_else(|_| BASE62_ALPHABET.to_string());
+    let id_generator = AlphabetGenerator::new(&hash_id_alphabet, hash_id_length)
+        .unwrap_or_else(|e| panic!("Invalid hash ID configuration: {}", e));
+    info!("Generating {}-character hash IDs ({:.0} bits)", hash_id_length, id_generator.entropy_bits());
+    
+    // Configure payload lifetimes (EXPIRY_DEFAULT, EXPIRY_MIN, EXPIRY_MAX)
+    let defaults = ExpiryPolicy::default();
+    let lifetime = |name: &str, default: Duration| {
+        std::env::var(name)
+            .map(|val| parse_duration(&val).unwrap_or_else(|e| panic!("Invalid {}: {}", name, e)))
+            .unwrap_or(default)
+    };
+    let expiry_policy = ExpiryPolicy::new(
+        lifetime("EXPIRY_DEFAULT", defaults.default_lifetime()),
+        lifetime("EXPIRY_MIN", defaults.min_lifetime()),
+        lifetime("EXPIRY_MAX", defaults.max_lifetime()),
+    )
+    .unwrap_or_else(|e| panic!("{}", e));
+    info!(
+        "Payloads live for {} by default, between {} and {}",
+        HumanDuration(expiry_policy.default_lifetime()),
+        HumanDuration(expiry_policy.min_lifetime()),
+        HumanDuration(expiry_policy.max_lifetime()),
+    );
+
+    // Configure accepted MIME types and their policies (MIME_TYPES), the type
+    // of payloads created without one (MIME_DEFAULT) and what happens to
+    // content that does not match its type (MIME_MISMATCH=reject|correct)
+    let mut mime_registry = std::env::var("MIME_TYPES")
+        .map(|types| MimeRegistry::from_config(&types).unwrap_or_else(|e| panic!("{}", e)))
+        .unwrap_or_default();
+    if let Ok(default_type) = std::env::var("MIME_DEFAULT") {
+        let default_type = MimeType::parse_requested(&default_type)
+            .unwrap_or_else(|e| panic!("Invalid MIME_DEFAULT: {}", e));
+        mime_registry = mime_registry.with_default_type(default_type);
+    }
+    if let Ok(mismatch_policy) = std::env::var("MIME_MISMATCH") {
+        let mismatch_policy = mismatch_policy
+            .parse()
+            .unwrap_or_else(|e| panic!("Invalid MIME_MISMATCH: {}", e));
+        mime_registry = mime_registry.with_mismatch_policy(mismatch_policy);
+    }
+    let mime_registry = Arc::new(mime_registry);
+    info!(
+        "Accepting MIME types {}, defaulting to {}, {} mismatched content",
+        mime_registry.supported_types().collect::<Vec<_>>().join(", "),
+        mime_registry.default_type().map_or(AUTO.to_string(), ToString::to_string),
+        match mime_registry.mismatch_policy() {
+            MismatchPolicy::Reject => "rejecting",
+            MismatchPolicy::Correct => "correcting",
+        },
+    );
+
+    // Number of earlier revisions kept per payload (MAX_REVISIONS)
+    let max_revisions = std::env::var("MAX_REVISIONS")
+        .map(|max| max.parse().unwrap_or_else(|e| panic!("Invalid MAX_REVISIONS: {}", e)))
+        .unwrap_or(DEFAULT_MAX_REVISIONS);
+    info!("Keeping up to {} earlier revisions per payload", max_revisions);
+
     // Create use cases
-    let create_payload_use_case = Arc::new(CreatePayloadUseCaseImpl::new(repository.clone()));
+    let create_payload_use_case = Arc::new(
+        CreatePayloadUseCaseImpl::new(repository.clone())
+            .with_id_generator(Arc::new(id_generator))
+            .with_expiry_policy(expiry_policy)
+            .with_mime_registry(mime_registry.clone()),
+    );
+    let update_payload_use_case = Arc::new(
+        UpdatePayloadUseCaseImpl::new(repository.clone()).with_expiry_policy(expiry_policy),
+    );
+    let replace_payload_use_case = Arc::new(
+        ReplacePayloadUseCaseImpl::new(repository.clone())
+            .with_mime_registry(mime_registry.clone())
+            .with_max_revisions(max_revisions),
+    );
     let get_payload_use_case = Arc::new(GetPayloadUseCaseImpl::new(repository.clone()));
     let delete_payload_use_case = Arc::new(DeletePayloadUseCaseImpl::new(repository.clone()));
-    
-    // Configure rate limiter
-    let rate_limit_config = RateLimitConfig {
-        max_requests: 100,
-        window_seconds: 60,
-    };
-    let rate_limiter = RedisRateLimiter::new(redis_repo, rate_limit_config);
+    let resolve_share_code_use_case = Arc::new(ResolveShareCodeUseCaseImpl::new(repository.clone()));
+    let resolve_alias_use_case = Arc::new(ResolveAliasUseCaseImpl::new(repository.clone()));
+    // Proxies whose X-Forwarded-For client addresses are trusted for share
+    // code throttling (TRUSTED_PROXIES, comma-separated IP addresses)
+    let trusted_proxies = std::env::var("TRUSTED_PROXIES")
+        .map(|proxies| {
+            proxies
+                .split(',')
+                .map(str::trim)
+                .filter(|proxy| !proxy.is_empty())
+                .map(|proxy| proxy.parse().unwrap_or_else(|e| panic!("Invalid TRUSTED_PROXIES entry {}: {}", proxy, e)))
+                .collect()
+        })
+        .unwrap_or_default();
+    let share_code_limiter = ShareCodeLimiter::new(share_code_limiter).with_trusted_proxies(trusted_proxies);
     
     // Start HTTP server
     info!("Starting HTTP server on 127.0.0.1:8080");
@@ -70,6 +242,8 @@ async fn main() -> std::io::Result<()> {
             .allow_any_origin()
             .allow_any_method()
             .allow_any_header()
+            // Let browser clients use versions for conditional requests
+            .expose_headers([header::ETAG])
             .max_age(3600);
         
         App::new()
@@ -84,6 +258,12 @@ async fn main() -> std::io::Result<()> {
             .app_data(web::Data::new(create_payload_use_case.clone()))
             .app_data(web::Data::new(get_payload_use_case.clone()))
             .app_data(web::Data::new(delete_payload_use_case.clone()))
+            .app_data(web::Data::new(update_payload_use_case.clone()))
+            .app_data(web::Data::new(replace_payload_use_case.clone()))
+            .app_data(web::Data::new(resolve_share_code_use_case.clone()))
+            .app_data(web::Data::new(share_code_limiter.clone()))
+            .app_data(web::Data::new(resolve_alias_use_case.clone()))
+            .app_data(web::Data::new(mime_registry.clone()))
             // Add API routes
             .configure(api::configure())
     })
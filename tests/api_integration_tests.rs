@@ -1,59 +1,24 @@
 //! Integration tests for the API endpoints.
 
 use actix_web::{test, web, App, http::StatusCode};
+use chrono::{DateTime, Utc};
 use std::sync::Arc;
 use serde_json::json;
 
 use jump::{
-    api::{self, middleware::configure_json_error_handling},
+    api::{self, middleware::configure_json_error_handling, v1::ShareCodeLimiter},
     application::{
-        use_cases::{CreatePayloadUseCaseImpl, GetPayloadUseCaseImpl, DeletePayloadUseCaseImpl},
-        repository::Repository,
+        use_cases::{
+            CreatePayloadUseCaseImpl, GetPayloadUseCaseImpl, DeletePayloadUseCaseImpl,
+            UpdatePayloadUseCaseImpl, ReplacePayloadUseCaseImpl, ResolveShareCodeUseCaseImpl,
+            ResolveAliasUseCaseImpl,
+        },
+        repository::{Repository, RepositoryError},
     },
 };
 
-// Import the mock repository from our application tests
 mod test_utils {
-    use std::collections::HashMap;
-    use std::sync::{Arc, Mutex};
-    use async_trait::async_trait;
-    
-    use jump::domain::hash_id::HashId;
     use jump::domain::payload::Payload;
-    use jump::application::repository::Repository;
-
-    #[derive(Clone)]
-    pub struct MockRepository {
-        payloads: Arc<Mutex<HashMap<String, Payload>>>,
-    }
-
-    impl MockRepository {
-        pub fn new() -> Self {
-            Self {
-                payloads: Arc::new(Mutex::new(HashMap::new())),
-            }
-        }
-    }
-
-    #[async_trait]
-    impl Repository for MockRepository {
-        async fn save(&self, payload: &Payload) -> Result<(), anyhow::Error> {
-            let mut payloads = self.payloads.lock().unwrap();
-            payloads.insert(payload.hash_id().as_string().to_string(), payload.clone());
-            Ok(())
-        }
-
-        async fn get(&self, id: &HashId) -> Result<Option<Payload>, anyhow::Error> {
-            let payloads = self.payloads.lock().unwrap();
-            Ok(payloads.get(id.as_string()).cloned())
-        }
-
-        async fn delete(&self, id: &HashId) -> Result<(), anyhow::Error> {
-            let mut payloads = self.payloads.lock().unwrap();
-            payloads.remove(id.as_string());
-            Ok(())
-        }
-    }
 
     /// Create a test payload with the given content and expiry time.
     pub fn create_test_payload(
@@ -63,11 +28,17 @@ mod test_utils {
         use chrono::{Duration, Utc};
         
         let expiry = expiry_seconds.map(|secs| Utc::now() + Duration::seconds(secs));
-        Payload::new(content.to_string(), Some("text/plain".to_string()), expiry).unwrap()
+        Payload::new(content.as_bytes().to_vec(), Some("text/plain".to_string()), expiry).unwrap()
     }
 }
 
-use test_utils::{MockRepository, create_test_payload};
+use jump::domain::{
+    hash_id::HashId, management_token::ManagementToken, mime_type::{MimeRegistry, MismatchPolicy}, payload::Payload,
+    revision::Revision,
+};
+use jump::infrastructure::memory::InMemoryRepository;
+use jump::infrastructure::rate_limit::{InMemoryRateLimiter, RateLimitConfig};
+use test_utils::create_test_payload;
 
 /// Test the health check endpoint.
 #[actix_web::test]
@@ -98,7 +69,7 @@ async fn test_health_endpoint() {
 #[actix_web::test]
 async fn test_create_payload_endpoint() {
     // Arrange
-    let repository = Arc::new(MockRepository::new());
+    let repository = Arc::new(InMemoryRepository::default());
     let create_use_case = Arc::new(CreatePayloadUseCaseImpl::new(repository.clone()));
     let get_use_case = Arc::new(GetPayloadUseCaseImpl::new(repository.clone()));
     let delete_use_case = Arc::new(DeletePayloadUseCaseImpl::new(repository.clone()));
@@ -130,13 +101,15 @@ async fn test_create_payload_endpoint() {
     assert!(body.is_object(), "Response body should be a JSON object");
     assert!(body.get("hash_id").is_some(), "Response should contain a hash_id field");
     assert!(body["hash_id"].is_string(), "hash_id should be a string");
+    assert_eq!(body["hash_id"].as_str().unwrap().len(), 12, "hash_id should be short");
+    assert_eq!(body["version"], 1, "New payloads start at version 1");
 }
 
 /// Test getting a payload.
 #[actix_web::test]
 async fn test_get_payload_endpoint() {
     // Arrange
-    let repository = Arc::new(MockRepository::new());
+    let repository = Arc::new(InMemoryRepository::default());
     let create_use_case = Arc::new(CreatePayloadUseCaseImpl::new(repository.clone()));
     let get_use_case = Arc::new(GetPayloadUseCaseImpl::new(repository.clone()));
     let delete_use_case = Arc::new(DeletePayloadUseCaseImpl::new(repository.clone()));
@@ -171,13 +144,14 @@ async fn test_get_payload_endpoint() {
     assert_eq!(body["hash_id"].as_str().unwrap(), hash_id);
     assert_eq!(body["content"].as_str().unwrap(), content);
     assert_eq!(body["mime_type"].as_str().unwrap(), "text/plain");
+    assert_eq!(body["encoding"].as_str().unwrap(), "utf8");
 }
 
 /// Test getting a non-existent payload.
 #[actix_web::test]
 async fn test_get_nonexistent_payload() {
     // Arrange
-    let repository = Arc::new(MockRepository::new());
+    let repository = Arc::new(InMemoryRepository::default());
     let create_use_case = Arc::new(CreatePayloadUseCaseImpl::new(repository.clone()));
     let get_use_case = Arc::new(GetPayloadUseCaseImpl::new(repository.clone()));
     let delete_use_case = Arc::new(DeletePayloadUseCaseImpl::new(repository.clone()));
@@ -207,7 +181,7 @@ async fn test_get_nonexistent_payload() {
 #[actix_web::test]
 async fn test_payload_size_validation() {
     // Arrange
-    let repository = Arc::new(MockRepository::new());
+    let repository = Arc::new(InMemoryRepository::default());
     let create_use_case = Arc::new(CreatePayloadUseCaseImpl::new(repository.clone()));
     let get_use_case = Arc::new(GetPayloadUseCaseImpl::new(repository.clone()));
     let delete_use_case = Arc::new(DeletePayloadUseCaseImpl::new(repository.clone()));
@@ -253,13 +227,14 @@ async fn test_payload_size_validation() {
 #[actix_web::test]
 async fn test_delete_payload_endpoint() {
     // Create a repository and use cases
-    let repository = Arc::new(MockRepository::new());
+    let repository = Arc::new(InMemoryRepository::default());
     let create_use_case = Arc::new(CreatePayloadUseCaseImpl::new(repository.clone()));
     let get_use_case = Arc::new(GetPayloadUseCaseImpl::new(repository.clone()));
     let delete_use_case = Arc::new(DeletePayloadUseCaseImpl::new(repository.clone()));
     
     // Create a test payload and add it to the repository
-    let payload = create_test_payload("Content to delete", None);
+    let token = ManagementToken::generate();
+    let payload = create_test_payload("Content to delete", None).with_management_token(&token);
     let hash_id = payload.hash_id().as_string().to_string();
     repository.save(&payload).await.unwrap();
     
@@ -275,6 +250,7 @@ async fn test_delete_payload_endpoint() {
     
     let req = test::TestRequest::delete()
         .uri(&format!("/api/v1/payloads/{}", hash_id))
+        .insert_header(("Authorization", format!("Bearer {}", token.as_string())))
         .to_request();
     
     let resp = test::call_service(&app, req).await;
@@ -282,3 +258,1408 @@ async fn test_delete_payload_endpoint() {
     // Assert
     assert_eq!(resp.status(), StatusCode::NO_CONTENT);
 }
+
+/// Test getting the raw content of a payload.
+#[actix_web::test]
+async fn test_get_raw_payload_endpoint() {
+    // Arrange
+    let repository = Arc::new(InMemoryRepository::default());
+    let create_use_case = Arc::new(CreatePayloadUseCaseImpl::new(repository.clone()));
+    let get_use_case = Arc::new(GetPayloadUseCaseImpl::new(repository.clone()));
+    let delete_use_case = Arc::new(DeletePayloadUseCaseImpl::new(repository.clone()));
+
+    let content = "<p>Raw content</p>";
+    let payload = jump::domain::payload::Payload::new(
+        content.as_bytes().to_vec(),
+        Some("text/html".to_string()),
+        None,
+    ).unwrap();
+    let hash_id = payload.hash_id().as_string().to_string();
+    repository.save(&payload).await.unwrap();
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(create_use_case.clone()))
+            .app_data(web::Data::new(get_use_case.clone()))
+            .app_data(web::Data::new(Arc::new(MimeRegistry::default())))
+            .app_data(web::Data::new(delete_use_case.clone()))
+            .app_data(configure_json_error_handling())
+            .configure(api::configure())
+    )
+    .await;
+
+    // Act
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/v1/payloads/{}/raw", hash_id))
+        .to_request();
+
+    let resp = test::call_service(&app, req).await;
+
+    // Assert
+    assert_eq!(resp.status(), StatusCode::OK);
+    let headers = resp.headers();
+    assert_eq!(headers.get("content-type").unwrap(), "text/html");
+    assert_eq!(
+        headers.get("content-length").unwrap().to_str().unwrap(),
+        content.len().to_string()
+    );
+    assert_eq!(
+        headers.get("content-disposition").unwrap().to_str().unwrap(),
+        format!("inline; filename=\"{}.html\"", hash_id)
+    );
+    assert!(headers.get("cache-control").unwrap().to_str().unwrap().contains("no-store"));
+
+    let body = test::read_body(resp).await;
+    assert_eq!(body, content.as_bytes());
+
+    // The raw read is tracked like any other view
+    let stored = repository.get(&HashId::from_string(hash_id)).await.unwrap().unwrap();
+    assert!(stored.viewed_at().is_some(), "Raw read should mark the payload as viewed");
+}
+
+/// Test that payload metadata is returned and names raw downloads.
+#[actix_web::test]
+async fn test_payload_metadata() {
+    // Arrange
+    let repository = Arc::new(InMemoryRepository::default());
+    let create_use_case = Arc::new(CreatePayloadUseCaseImpl::new(repository.clone()));
+    let get_use_case = Arc::new(GetPayloadUseCaseImpl::new(repository.clone()));
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(create_use_case.clone()))
+            .app_data(web::Data::new(get_use_case.clone()))
+            .app_data(web::Data::new(Arc::new(MimeRegistry::default())))
+            .app_data(configure_json_error_handling())
+            .configure(api::configure())
+    )
+    .await;
+
+    let create = |metadata: serde_json::Value| {
+        let mut body = metadata;
+        body["content"] = "Deploy log".into();
+        test::TestRequest::post()
+            .uri("/api/v1/payloads")
+            .set_json(body)
+            .to_request()
+    };
+
+    // Act
+    let resp = test::call_service(&app, create(json!({
+        "filename": "deploy.log",
+        "title": "Deploy 4711",
+        "labels": { "team": "payments", "severity": "high" }
+    }))).await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    let hash_id = body["hash_id"].as_str().unwrap().to_string();
+
+    // Assert - Reads return the metadata
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/v1/payloads/{}", hash_id))
+        .to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["filename"], "deploy.log");
+    assert_eq!(body["title"], "Deploy 4711");
+    assert_eq!(body["description"], serde_json::Value::Null);
+    assert_eq!(body["tags"], json!({ "severity": "high", "team": "payments" }));
+
+    // Assert - Raw downloads are named after the filename
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/v1/payloads/{}/raw", hash_id))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(
+        resp.headers().get("content-disposition").unwrap().to_str().unwrap(),
+        "inline; filename=\"deploy.log\""
+    );
+
+    // Assert - Filenames outside ASCII are sent as filename*
+    let resp = test::call_service(&app, create(json!({ "filename": "Protokoll-Übersicht.txt" }))).await;
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/v1/payloads/{}/raw", body["hash_id"].as_str().unwrap()))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    let disposition = resp.headers().get("content-disposition").unwrap().to_str().unwrap();
+    assert!(disposition.contains("filename=\"Protokoll-_bersicht.txt\""));
+    assert!(disposition.contains("filename*=UTF-8''Protokoll%2D%C3%9Cbersicht.txt"));
+
+    // Assert - Filenames must not be paths
+    let resp = test::call_service(&app, create(json!({ "filename": "../../etc/passwd" }))).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+}
+
+/// Test getting the raw content of a non-existent payload.
+#[actix_web::test]
+async fn test_get_raw_nonexistent_payload() {
+    // Arrange
+    let repository = Arc::new(InMemoryRepository::default());
+    let get_use_case = Arc::new(GetPayloadUseCaseImpl::new(repository.clone()));
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(get_use_case.clone()))
+            .app_data(web::Data::new(Arc::new(MimeRegistry::default())))
+            .app_data(configure_json_error_handling())
+            .configure(api::configure())
+    )
+    .await;
+
+    // Act
+    let req = test::TestRequest::get()
+        .uri("/api/v1/payloads/nonexistent/raw")
+        .to_request();
+
+    let resp = test::call_service(&app, req).await;
+
+    // Assert
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+}
+
+/// Test that binary content survives a base64 create and raw read.
+#[actix_web::test]
+async fn test_binary_payload_round_trip() {
+    // Arrange
+    let repository = Arc::new(InMemoryRepository::default());
+    let create_use_case = Arc::new(CreatePayloadUseCaseImpl::new(repository.clone()));
+    let get_use_case = Arc::new(GetPayloadUseCaseImpl::new(repository.clone()));
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(create_use_case.clone()))
+            .app_data(web::Data::new(get_use_case.clone()))
+            .app_data(web::Data::new(Arc::new(MimeRegistry::default())))
+            .app_data(configure_json_error_handling())
+            .configure(api::configure())
+    )
+    .await;
+
+    // A PNG signature followed by bytes that are not valid UTF-8
+    let image: Vec<u8> = vec![0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0xff, 0xfe];
+    let encoded = "iVBORw0KGgoA//4=";
+
+    // Act - Create
+    let req = test::TestRequest::post()
+        .uri("/api/v1/payloads")
+        .set_json(json!({
+            "content": encoded,
+            "mime_type": "image/png",
+            "encoding": "base64"
+        }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    let hash_id = body["hash_id"].as_str().unwrap().to_string();
+
+    // Act - Get JSON
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/v1/payloads/{}", hash_id))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["encoding"].as_str().unwrap(), "base64");
+    assert_eq!(body["content"].as_str().unwrap(), encoded);
+
+    // Act - Get raw
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/v1/payloads/{}/raw", hash_id))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+
+    // Assert
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.headers().get("content-type").unwrap(), "image/png");
+    let body = test::read_body(resp).await;
+    assert_eq!(body.as_ref(), image.as_slice());
+}
+
+/// Test that invalid base64 content is rejected.
+#[actix_web::test]
+async fn test_create_payload_with_invalid_base64() {
+    // Arrange
+    let repository = Arc::new(InMemoryRepository::default());
+    let create_use_case = Arc::new(CreatePayloadUseCaseImpl::new(repository.clone()));
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(create_use_case.clone()))
+            .app_data(configure_json_error_handling())
+            .configure(api::configure())
+    )
+    .await;
+
+    // Act
+    let req = test::TestRequest::post()
+        .uri("/api/v1/payloads")
+        .set_json(json!({
+            "content": "%%% not base64 %%%",
+            "mime_type": "image/png",
+            "encoding": "base64"
+        }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+
+    // Assert
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+}
+
+/// Test that relative expiries are accepted and past expiries rejected.
+#[actix_web::test]
+async fn test_create_payload_expiry() {
+    // Arrange
+    let repository = Arc::new(InMemoryRepository::default());
+    let create_use_case = Arc::new(CreatePayloadUseCaseImpl::new(repository.clone()));
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(create_use_case.clone()))
+            .app_data(configure_json_error_handling())
+            .configure(api::configure())
+    )
+    .await;
+
+    // Act & Assert: a relative expiry is resolved on the server
+    let before = Utc::now();
+    let req = test::TestRequest::post()
+        .uri("/api/v1/payloads")
+        .set_json(json!({
+            "content": "Expires in two hours",
+            "expires_in": "2h"
+        }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    let expires_at: DateTime<Utc> = body["expires_at"].as_str().unwrap().parse().unwrap();
+    assert!(expires_at >= before + chrono::Duration::hours(2));
+    assert!(expires_at <= Utc::now() + chrono::Duration::hours(2));
+
+    // Act & Assert: nothing is created for an expiry in the past
+    for body in [
+        json!({ "content": "Already gone", "expiry_time": "2020-01-01T00:00:00Z" }),
+        json!({ "content": "Never", "expires_in": "forever" }),
+        json!({ "content": "Too long", "expires_in": "365d" }),
+    ] {
+        let req = test::TestRequest::post()
+            .uri("/api/v1/payloads")
+            .set_json(body)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+}
+
+/// Test that the first read of a payload starts its countdown.
+#[actix_web::test]
+async fn test_payload_expires_after_first_view() {
+    // Arrange
+    let repository = Arc::new(InMemoryRepository::default());
+    let create_use_case = Arc::new(CreatePayloadUseCaseImpl::new(repository.clone()));
+    let get_use_case = Arc::new(GetPayloadUseCaseImpl::new(repository.clone()));
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(create_use_case.clone()))
+            .app_data(web::Data::new(get_use_case.clone()))
+            .app_data(configure_json_error_handling())
+            .configure(api::configure())
+    )
+    .await;
+
+    let req = test::TestRequest::post()
+        .uri("/api/v1/payloads")
+        .set_json(json!({
+            "content": "Gone five minutes after reading",
+            "expires_after_view": "5m"
+        }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    let hash_id = body["hash_id"].as_str().unwrap().to_string();
+    let expires_at: DateTime<Utc> = body["expires_at"].as_str().unwrap().parse().unwrap();
+    assert!(expires_at > Utc::now() + chrono::Duration::hours(23));
+
+    // Act & Assert: the first read moves the expiry up
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/v1/payloads/{}", hash_id))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    let expiry_time: DateTime<Utc> = body["expiry_time"].as_str().unwrap().parse().unwrap();
+    assert!(expiry_time <= Utc::now() + chrono::Duration::minutes(5));
+
+    // Act & Assert: only one expiry mode can be chosen
+    let req = test::TestRequest::post()
+        .uri("/api/v1/payloads")
+        .set_json(json!({
+            "content": "Undecided",
+            "idle_timeout": "5m",
+            "expires_after_view": "5m"
+        }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+}
+
+/// Test that an expired payload is no longer served.
+#[actix_web::test]
+async fn test_get_expired_payload() {
+    // Arrange
+    let repository = Arc::new(InMemoryRepository::default());
+    let get_use_case = Arc::new(GetPayloadUseCaseImpl::new(repository.clone()));
+
+    let payload = jump::domain::payload::Payload::new(
+        b"Short-lived".to_vec(),
+        None,
+        Some(chrono::Utc::now() + chrono::Duration::milliseconds(100)),
+    ).unwrap();
+    let hash_id = payload.hash_id().as_string().to_string();
+    repository.save(&payload).await.unwrap();
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(get_use_case.clone()))
+            .app_data(configure_json_error_handling())
+            .configure(api::configure())
+    )
+    .await;
+
+    tokio::time::sleep(std::time::Duration::from_millis(150)).await;
+
+    // Act
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/v1/payloads/{}", hash_id))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+
+    // Assert
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+}
+
+/// Test that a one-time payload can only be read once.
+#[actix_web::test]
+async fn test_one_time_payload_is_burned_after_reading() {
+    // Arrange
+    let repository = Arc::new(InMemoryRepository::default());
+    let create_use_case = Arc::new(CreatePayloadUseCaseImpl::new(repository.clone()));
+    let get_use_case = Arc::new(GetPayloadUseCaseImpl::new(repository.clone()));
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(create_use_case.clone()))
+            .app_data(web::Data::new(get_use_case.clone()))
+            .app_data(configure_json_error_handling())
+            .configure(api::configure())
+    )
+    .await;
+
+    let req = test::TestRequest::post()
+        .uri("/api/v1/payloads")
+        .set_json(json!({
+            "content": "hunter2",
+            "one_time": true
+        }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert!(body["one_time"].as_bool().unwrap());
+    let hash_id = body["hash_id"].as_str().unwrap().to_string();
+
+    // Act - First read
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/v1/payloads/{}", hash_id))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["content"].as_str().unwrap(), "hunter2");
+
+    // Act - Second read
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/v1/payloads/{}", hash_id))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+
+    // Assert
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    assert!(repository.is_empty());
+}
+
+/// Test that a payload stops being served once its view limit is reached.
+#[actix_web::test]
+async fn test_view_limit() {
+    // Arrange
+    let repository = Arc::new(InMemoryRepository::default());
+    let create_use_case = Arc::new(CreatePayloadUseCaseImpl::new(repository.clone()));
+    let get_use_case = Arc::new(GetPayloadUseCaseImpl::new(repository.clone()));
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(create_use_case.clone()))
+            .app_data(web::Data::new(get_use_case.clone()))
+            .app_data(configure_json_error_handling())
+            .configure(api::configure())
+    )
+    .await;
+
+    let req = test::TestRequest::post()
+        .uri("/api/v1/payloads")
+        .set_json(json!({
+            "content": "Seen twice",
+            "max_views": 2
+        }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    let hash_id = body["hash_id"].as_str().unwrap().to_string();
+
+    // Act - Views within the limit
+    for expected_count in 1..=2 {
+        let req = test::TestRequest::get()
+            .uri(&format!("/api/v1/payloads/{}", hash_id))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["view_count"].as_u64().unwrap(), expected_count);
+        assert_eq!(body["max_views"].as_u64().unwrap(), 2);
+    }
+
+    // Act - One view too many
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/v1/payloads/{}", hash_id))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+
+    // Assert
+    assert_eq!(resp.status(), StatusCode::GONE);
+}
+
+/// Test that a view limit below one is rejected.
+#[actix_web::test]
+async fn test_create_payload_with_zero_max_views() {
+    // Arrange
+    let repository = Arc::new(InMemoryRepository::default());
+    let create_use_case = Arc::new(CreatePayloadUseCaseImpl::new(repository.clone()));
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(create_use_case.clone()))
+            .app_data(configure_json_error_handling())
+            .configure(api::configure())
+    )
+    .await;
+
+    // Act
+    let req = test::TestRequest::post()
+        .uri("/api/v1/payloads")
+        .set_json(json!({
+            "content": "Never visible",
+            "max_views": 0
+        }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+
+    // Assert
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+}
+
+/// Test reading a password-protected payload via header and unlock endpoint.
+#[actix_web::test]
+async fn test_password_protected_payload() {
+    // Arrange
+    let repository = Arc::new(InMemoryRepository::default());
+    let create_use_case = Arc::new(CreatePayloadUseCaseImpl::new(repository.clone()));
+    let get_use_case = Arc::new(GetPayloadUseCaseImpl::new(repository.clone()));
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(create_use_case.clone()))
+            .app_data(web::Data::new(get_use_case.clone()))
+            .app_data(web::Data::new(Arc::new(MimeRegistry::default())))
+            .app_data(configure_json_error_handling())
+            .configure(api::configure())
+    )
+    .await;
+
+    let req = test::TestRequest::post()
+        .uri("/api/v1/payloads")
+        .set_json(json!({
+            "content": "Launch codes",
+            "password": "swordfish"
+        }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert!(body["password_protected"].as_bool().unwrap());
+    let hash_id = body["hash_id"].as_str().unwrap().to_string();
+
+    // Act & Assert - No password
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/v1/payloads/{}", hash_id))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    let body = test::read_body(resp).await;
+    assert!(!String::from_utf8_lossy(&body).contains("Launch codes"));
+
+    // Act & Assert - Wrong password
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/v1/payloads/{}", hash_id))
+        .insert_header(("X-Payload-Password", "trout"))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+    // Act & Assert - Password header
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/v1/payloads/{}/raw", hash_id))
+        .insert_header(("X-Payload-Password", "swordfish"))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body = test::read_body(resp).await;
+    assert_eq!(body.as_ref(), b"Launch codes");
+
+    // Act & Assert - Unlock endpoint
+    let req = test::TestRequest::post()
+        .uri(&format!("/api/v1/payloads/{}/unlock", hash_id))
+        .set_json(json!({ "password": "swordfish" }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["content"].as_str().unwrap(), "Launch codes");
+    assert!(body.get("password_hash").is_none());
+}
+
+/// Test that password guesses look the same whether or not the payload exists.
+#[actix_web::test]
+async fn test_unlock_nonexistent_payload() {
+    // Arrange
+    let repository = Arc::new(InMemoryRepository::default());
+    let get_use_case = Arc::new(GetPayloadUseCaseImpl::new(repository.clone()));
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(get_use_case.clone()))
+            .app_data(configure_json_error_handling())
+            .configure(api::configure())
+    )
+    .await;
+
+    // Act
+    let req = test::TestRequest::post()
+        .uri("/api/v1/payloads/nonexistent/unlock")
+        .set_json(json!({ "password": "swordfish" }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+
+    // Assert
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+}
+
+/// Test that only the holder of the management token can delete a payload.
+#[actix_web::test]
+async fn test_delete_requires_management_token() {
+    // Arrange
+    let repository = Arc::new(InMemoryRepository::default());
+    let create_use_case = Arc::new(CreatePayloadUseCaseImpl::new(repository.clone()));
+    let delete_use_case = Arc::new(DeletePayloadUseCaseImpl::new(repository.clone()));
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(create_use_case.clone()))
+            .app_data(web::Data::new(delete_use_case.clone()))
+            .app_data(configure_json_error_handling())
+            .configure(api::configure())
+    )
+    .await;
+
+    let req = test::TestRequest::post()
+        .uri("/api/v1/payloads")
+        .set_json(json!({ "content": "Keep me" }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    let hash_id = body["hash_id"].as_str().unwrap().to_string();
+    let token = body["management_token"].as_str().unwrap().to_string();
+
+    // Act & Assert - Readers of the link cannot delete it
+    for authorization in [None, Some("Bearer not-the-token".to_string())] {
+        let mut req = test::TestRequest::delete()
+            .uri(&format!("/api/v1/payloads/{}", hash_id));
+        if let Some(authorization) = authorization {
+            req = req.insert_header(("Authorization", authorization));
+        }
+        let resp = test::call_service(&app, req.to_request()).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    }
+    assert_eq!(repository.len(), 1);
+
+    // Act & Assert - The owner can
+    let req = test::TestRequest::delete()
+        .uri(&format!("/api/v1/payloads/{}", hash_id))
+        .insert_header(("Authorization", format!("Bearer {}", token)))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+    assert!(repository.is_empty());
+}
+
+/// Test that the owner can extend and revoke a payload's expiry.
+#[actix_web::test]
+async fn test_update_payload_expiry() {
+    // Arrange
+    let repository = Arc::new(InMemoryRepository::default());
+    let create_use_case = Arc::new(CreatePayloadUseCaseImpl::new(repository.clone()));
+    let get_use_case = Arc::new(GetPayloadUseCaseImpl::new(repository.clone()));
+    let update_use_case = Arc::new(UpdatePayloadUseCaseImpl::new(repository.clone()));
+    let resolve_alias_use_case = Arc::new(ResolveAliasUseCaseImpl::new(repository.clone()));
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(create_use_case.clone()))
+            .app_data(web::Data::new(get_use_case.clone()))
+            .app_data(web::Data::new(update_use_case.clone()))
+            .app_data(web::Data::new(resolve_alias_use_case.clone()))
+            .app_data(configure_json_error_handling())
+            .configure(api::configure())
+    )
+    .await;
+
+    let req = test::TestRequest::post()
+        .uri("/api/v1/payloads")
+        .set_json(json!({ "content": "Build log", "expires_in": "1h", "alias": "build-log" }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    let hash_id = body["hash_id"].as_str().unwrap().to_string();
+    let token = body["management_token"].as_str().unwrap().to_string();
+
+    // Act & Assert - Readers of the link cannot change the expiry
+    let req = test::TestRequest::patch()
+        .uri(&format!("/api/v1/payloads/{}", hash_id))
+        .insert_header(("Authorization", "Bearer not-the-token"))
+        .set_json(json!({ "revoke": true }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+    // Act & Assert - The owner can extend it, and the alias follows
+    let before = Utc::now();
+    let req = test::TestRequest::patch()
+        .uri(&format!("/api/v1/payloads/{}", hash_id))
+        .insert_header(("Authorization", format!("Bearer {}", token)))
+        .set_json(json!({ "expires_in": "6h" }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    let expiry_time: DateTime<Utc> = body["expiry_time"].as_str().unwrap().parse().unwrap();
+    assert!(expiry_time >= before + chrono::Duration::hours(6));
+
+    let req = test::TestRequest::get()
+        .uri("/api/v1/aliases/build-log")
+        .to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["expiry_time"].as_str().unwrap().parse::<DateTime<Utc>>().unwrap(), expiry_time);
+
+    // Act & Assert - Expiries in the past are rejected
+    let req = test::TestRequest::patch()
+        .uri(&format!("/api/v1/payloads/{}", hash_id))
+        .insert_header(("Authorization", format!("Bearer {}", token)))
+        .set_json(json!({ "expiry_time": "2020-01-01T00:00:00Z" }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    // Act & Assert - Revoking expires the payload and its alias at once
+    let req = test::TestRequest::patch()
+        .uri(&format!("/api/v1/payloads/{}", hash_id))
+        .insert_header(("Authorization", format!("Bearer {}", token)))
+        .set_json(json!({ "revoke": true }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    for uri in [format!("/api/v1/payloads/{}", hash_id), "/api/v1/aliases/build-log".to_string()] {
+        let req = test::TestRequest::get().uri(&uri).to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND, "GET {}", uri);
+    }
+}
+
+/// Test that the owner can replace a payload's content and that earlier
+/// revisions stay readable.
+#[actix_web::test]
+async fn test_replace_payload_content() {
+    // Arrange
+    let repository = Arc::new(InMemoryRepository::default());
+    let create_use_case = Arc::new(CreatePayloadUseCaseImpl::new(repository.clone()));
+    let get_use_case = Arc::new(GetPayloadUseCaseImpl::new(repository.clone()));
+    let replace_use_case = Arc::new(ReplacePayloadUseCaseImpl::new(repository.clone()));
+    let delete_use_case = Arc::new(DeletePayloadUseCaseImpl::new(repository.clone()));
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(create_use_case.clone()))
+            .app_data(web::Data::new(get_use_case.clone()))
+            .app_data(web::Data::new(replace_use_case.clone()))
+            .app_data(web::Data::new(delete_use_case.clone()))
+            .app_data(configure_json_error_handling())
+            .configure(api::configure())
+    )
+    .await;
+
+    let req = test::TestRequest::post()
+        .uri("/api/v1/payloads")
+        .set_json(json!({ "content": "Draft", "expires_in": "1h" }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    let hash_id = body["hash_id"].as_str().unwrap().to_string();
+    let token = body["management_token"].as_str().unwrap().to_string();
+
+    // Act & Assert - Readers of the link cannot replace the content
+    let req = test::TestRequest::put()
+        .uri(&format!("/api/v1/payloads/{}", hash_id))
+        .set_json(json!({ "content": "Defaced" }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+    // Act & Assert - The owner can replace content and MIME type
+    let req = test::TestRequest::put()
+        .uri(&format!("/api/v1/payloads/{}", hash_id))
+        .insert_header(("Authorization", format!("Bearer {}", token)))
+        .set_json(json!({ "content": "<h1>Final</h1>", "mime_type": "text/html" }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["revision"], 2);
+
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/v1/payloads/{}", hash_id))
+        .to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["content"], "<h1>Final</h1>");
+    assert_eq!(body["mime_type"], "text/html");
+    assert_eq!(body["revision"], 2);
+
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/v1/payloads/{}/revisions/1", hash_id))
+        .to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["content"], "Draft");
+    assert_eq!(body["mime_type"], "text/plain");
+    assert_eq!(body["revision"], 1);
+    assert_eq!(body["view_count"], 2);
+
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/v1/payloads/{}/revisions/3", hash_id))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+    // Act & Assert - Revisions go away with the payload
+    let req = test::TestRequest::delete()
+        .uri(&format!("/api/v1/payloads/{}", hash_id))
+        .insert_header(("Authorization", format!("Bearer {}", token)))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/v1/payloads/{}/revisions/1", hash_id))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+}
+
+/// Test MIME types enabled and limited by configuration.
+#[actix_web::test]
+async fn test_configured_mime_types() {
+    // Arrange
+    let mime_registry = Arc::new(
+        MimeRegistry::from_config("text/plain, text/markdown; max_size=16, application/pdf; inline=false")
+            .unwrap(),
+    );
+    let repository = Arc::new(InMemoryRepository::default());
+    let create_use_case = Arc::new(
+        CreatePayloadUseCaseImpl::new(repository.clone()).with_mime_registry(mime_registry.clone()),
+    );
+    let get_use_case = Arc::new(GetPayloadUseCaseImpl::new(repository.clone()));
+    let replace_use_case = Arc::new(
+        ReplacePayloadUseCaseImpl::new(repository.clone()).with_mime_registry(mime_registry.clone()),
+    );
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(create_use_case.clone()))
+            .app_data(web::Data::new(get_use_case.clone()))
+            .app_data(web::Data::new(replace_use_case.clone()))
+            .app_data(web::Data::new(mime_registry.clone()))
+            .app_data(configure_json_error_handling())
+            .configure(api::configure())
+    )
+    .await;
+
+    // Act & Assert - Types that are not enabled are refused
+    let req = test::TestRequest::post()
+        .uri("/api/v1/payloads")
+        .set_json(json!({ "content": "<p>Hi</p>", "mime_type": "text/html" }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    // Act & Assert - Parameters are kept, and the type's size limit applies
+    let req = test::TestRequest::post()
+        .uri("/api/v1/payloads")
+        .set_json(json!({ "content": "# Notes", "mime_type": "text/markdown; charset=UTF-8" }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    let hash_id = body["hash_id"].as_str().unwrap().to_string();
+    let token = body["management_token"].as_str().unwrap().to_string();
+
+    let req = test::TestRequest::put()
+        .uri(&format!("/api/v1/payloads/{}", hash_id))
+        .insert_header(("Authorization", format!("Bearer {}", token)))
+        .set_json(json!({ "content": "# Notes that run far too long" }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert!(body["error"].as_str().unwrap().contains("16 bytes"));
+
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/v1/payloads/{}/raw", hash_id))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.headers().get("content-type").unwrap(), "text/markdown; charset=utf-8");
+    let disposition = resp.headers().get("content-disposition").unwrap().to_str().unwrap();
+    assert!(disposition.starts_with("inline"));
+    assert!(disposition.contains(&format!("{}.md", hash_id)));
+
+    // Act & Assert - Types that must not be displayed are served as downloads
+    let req = test::TestRequest::post()
+        .uri("/api/v1/payloads")
+        .set_json(json!({
+            "content": "JVBERi0xLjcK",
+            "encoding": "base64",
+            "mime_type": "application/pdf"
+        }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    let hash_id = body["hash_id"].as_str().unwrap().to_string();
+
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/v1/payloads/{}/raw", hash_id))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.headers().get("content-type").unwrap(), "application/pdf");
+    let disposition = resp.headers().get("content-disposition").unwrap().to_str().unwrap();
+    assert!(disposition.starts_with("attachment"));
+    assert!(disposition.contains(&format!("{}.pdf", hash_id)));
+}
+
+/// Test that content is inspected against its declared MIME type.
+#[actix_web::test]
+async fn test_mime_type_inspection() {
+    // Arrange
+    let repository = Arc::new(InMemoryRepository::default());
+    let strict_use_case = Arc::new(CreatePayloadUseCaseImpl::new(repository.clone()));
+    let lenient_use_case = Arc::new(
+        CreatePayloadUseCaseImpl::new(repository.clone()).with_mime_registry(Arc::new(
+            MimeRegistry::default().with_mismatch_policy(MismatchPolicy::Correct),
+        )),
+    );
+
+    let strict_app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(strict_use_case.clone()))
+            .app_data(configure_json_error_handling())
+            .configure(api::configure())
+    )
+    .await;
+    let lenient_app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(lenient_use_case.clone()))
+            .app_data(configure_json_error_handling())
+            .configure(api::configure())
+    )
+    .await;
+
+    // Act & Assert - Text labelled as an image is refused
+    let req = test::TestRequest::post()
+        .uri("/api/v1/payloads")
+        .set_json(json!({ "content": "not an image", "mime_type": "image/png" }))
+        .to_request();
+    let resp = test::call_service(&strict_app, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert!(body["error"].as_str().unwrap().contains("magic number"));
+
+    // Act & Assert - The type is detected when asked for
+    let req = test::TestRequest::post()
+        .uri("/api/v1/payloads")
+        .set_json(json!({ "content": "R0lGODlhAQABAAAAACw=", "encoding": "base64", "mime_type": "auto" }))
+        .to_request();
+    let resp = test::call_service(&strict_app, req).await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["mime_type"], "image/gif");
+
+    // Act & Assert - Mismatches are corrected when configured
+    let req = test::TestRequest::post()
+        .uri("/api/v1/payloads")
+        .set_json(json!({ "content": "not an image", "mime_type": "image/png" }))
+        .to_request();
+    let resp = test::call_service(&lenient_app, req).await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["mime_type"], "text/plain");
+}
+
+/// Test conditional requests against payload versions.
+#[actix_web::test]
+async fn test_conditional_requests() {
+    // Arrange
+    let repository = Arc::new(InMemoryRepository::default());
+    let create_use_case = Arc::new(CreatePayloadUseCaseImpl::new(repository.clone()));
+    let get_use_case = Arc::new(GetPayloadUseCaseImpl::new(repository.clone()));
+    let update_use_case = Arc::new(UpdatePayloadUseCaseImpl::new(repository.clone()));
+    let replace_use_case = Arc::new(ReplacePayloadUseCaseImpl::new(repository.clone()));
+    let delete_use_case = Arc::new(DeletePayloadUseCaseImpl::new(repository.clone()));
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(create_use_case.clone()))
+            .app_data(web::Data::new(get_use_case.clone()))
+            .app_data(web::Data::new(update_use_case.clone()))
+            .app_data(web::Data::new(replace_use_case.clone()))
+            .app_data(web::Data::new(delete_use_case.clone()))
+            .app_data(configure_json_error_handling())
+            .configure(api::configure())
+    )
+    .await;
+
+    let req = test::TestRequest::post()
+        .uri("/api/v1/payloads")
+        .set_json(json!({ "content": "Draft", "max_views": 2 }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    assert_eq!(resp.headers().get("ETag").unwrap(), "\"1\"");
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    let uri = format!("/api/v1/payloads/{}", body["hash_id"].as_str().unwrap());
+    let authorization = format!("Bearer {}", body["management_token"].as_str().unwrap());
+
+    // Act & Assert - Reads carry the version, and clients holding it get 304
+    let req = test::TestRequest::get().uri(&uri).to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.headers().get("ETag").unwrap(), "\"1\"");
+
+    let req = test::TestRequest::get()
+        .uri(&uri)
+        .insert_header(("If-None-Match", "\"1\""))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::NOT_MODIFIED);
+    assert_eq!(resp.headers().get("ETag").unwrap(), "\"1\"");
+
+    // Act & Assert - Changes based on an outdated version are refused
+    let req = test::TestRequest::put()
+        .uri(&uri)
+        .insert_header(("Authorization", authorization.clone()))
+        .insert_header(("If-Match", "\"1\""))
+        .set_json(json!({ "content": "Final" }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.headers().get("ETag").unwrap(), "\"2\"");
+
+    let req = test::TestRequest::patch()
+        .uri(&uri)
+        .insert_header(("Authorization", authorization.clone()))
+        .insert_header(("If-Match", "\"1\""))
+        .set_json(json!({ "expires_in": "2h" }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::PRECONDITION_FAILED);
+
+    let req = test::TestRequest::delete()
+        .uri(&uri)
+        .insert_header(("Authorization", authorization.clone()))
+        .insert_header(("If-Match", "\"1\""))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::PRECONDITION_FAILED);
+
+    // Act & Assert - The outdated copy is replaced, and 304s used no views
+    let req = test::TestRequest::get()
+        .uri(&uri)
+        .insert_header(("If-None-Match", "\"1\""))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.headers().get("ETag").unwrap(), "\"2\"");
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["content"], "Final");
+    assert_eq!(body["version"], 2);
+    assert_eq!(body["view_count"], 2);
+
+    let req = test::TestRequest::delete()
+        .uri(&uri)
+        .insert_header(("Authorization", authorization))
+        .insert_header(("If-Match", "\"2\""))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+}
+
+/// Test reading a payload by its share code.
+#[actix_web::test]
+async fn test_share_code() {
+    // Arrange
+    let repository = Arc::new(InMemoryRepository::default());
+    let create_use_case = Arc::new(CreatePayloadUseCaseImpl::new(repository.clone()));
+    let get_use_case = Arc::new(GetPayloadUseCaseImpl::new(repository.clone()));
+    let resolve_use_case = Arc::new(ResolveShareCodeUseCaseImpl::new(repository.clone()));
+    let limiter = ShareCodeLimiter::new(Arc::new(InMemoryRateLimiter::new(RateLimitConfig::default())));
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(create_use_case.clone()))
+            .app_data(web::Data::new(get_use_case.clone()))
+            .app_data(web::Data::new(resolve_use_case.clone()))
+            .app_data(web::Data::new(limiter))
+            .app_data(configure_json_error_handling())
+            .configure(api::configure())
+    )
+    .await;
+
+    let req = test::TestRequest::post()
+        .uri("/api/v1/payloads")
+        .set_json(json!({
+            "content": "The wifi password is hunter2",
+            "share_code": true
+        }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    let hash_id = body["hash_id"].as_str().unwrap().to_string();
+    let share_code = body["share_code"].as_str().expect("share code should be returned").to_string();
+
+    // Act & Assert - Canonical code
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/v1/codes/{}", share_code))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["hash_id"].as_str().unwrap(), hash_id);
+    assert_eq!(body["content"].as_str().unwrap(), "The wifi password is hunter2");
+    assert_eq!(body["share_code"].as_str().unwrap(), share_code);
+
+    // Act & Assert - Code typed in with spaces and capitals
+    let typed = share_code.replace('-', "%20").to_uppercase();
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/v1/codes/{}", typed))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    // Act & Assert - Unknown and malformed codes
+    let unknown = if share_code.starts_with("1-") { "2-anchor-bagel" } else { "1-anchor-bagel" };
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/v1/codes/{}", unknown))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+    let req = test::TestRequest::get()
+        .uri("/api/v1/codes/not-a-code")
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+}
+
+/// Test that share code lookups are throttled per client.
+#[actix_web::test]
+async fn test_share_code_lookups_are_throttled() {
+    // Arrange
+    let repository = Arc::new(InMemoryRepository::default());
+    let get_use_case = Arc::new(GetPayloadUseCaseImpl::new(repository.clone()));
+    let resolve_use_case = Arc::new(ResolveShareCodeUseCaseImpl::new(repository.clone()));
+    let limiter = ShareCodeLimiter::new(Arc::new(InMemoryRateLimiter::new(RateLimitConfig {
+        max_requests: 2,
+        window_seconds: 600,
+    })));
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(get_use_case.clone()))
+            .app_data(web::Data::new(resolve_use_case.clone()))
+            .app_data(web::Data::new(limiter))
+            .app_data(configure_json_error_handling())
+            .configure(api::configure())
+    )
+    .await;
+
+    // Act & Assert - Guesses count, whether they are valid codes or not
+    for guess in ["7-crossover-clockwork", "not-a-code"] {
+        let req = test::TestRequest::get()
+            .uri(&format!("/api/v1/codes/{}", guess))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_ne!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    let req = test::TestRequest::get()
+        .uri("/api/v1/codes/8-crossover-clockwork")
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert!(resp.headers().contains_key("Retry-After"));
+}
+
+/// Test that share code lookups are throttled by the connecting address,
+/// trusting forwarded addresses only from configured proxies.
+#[actix_web::test]
+async fn test_share_code_throttling_uses_trusted_client_address() {
+    // Arrange
+    let repository = Arc::new(InMemoryRepository::default());
+    let get_use_case = Arc::new(GetPayloadUseCaseImpl::new(repository.clone()));
+    let resolve_use_case = Arc::new(ResolveShareCodeUseCaseImpl::new(repository.clone()));
+    let proxy: std::net::SocketAddr = "10.0.0.2:40000".parse().unwrap();
+    let limiter = ShareCodeLimiter::new(Arc::new(InMemoryRateLimiter::new(RateLimitConfig {
+        max_requests: 2,
+        window_seconds: 600,
+    })))
+    .with_trusted_proxies(vec![proxy.ip()]);
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(get_use_case.clone()))
+            .app_data(web::Data::new(resolve_use_case.clone()))
+            .app_data(web::Data::new(limiter))
+            .app_data(configure_json_error_handling())
+            .configure(api::configure())
+    )
+    .await;
+    let lookup = |peer: std::net::SocketAddr, forwarded_for: &str| {
+        test::TestRequest::get()
+            .uri("/api/v1/codes/7-crossover-clockwork")
+            .peer_addr(peer)
+            .insert_header(("X-Forwarded-For", forwarded_for.to_string()))
+            .to_request()
+    };
+
+    // Act & Assert - Direct clients cannot dodge the limit with made-up addresses
+    let client: std::net::SocketAddr = "203.0.113.5:50000".parse().unwrap();
+    for forwarded_for in ["198.51.100.1", "198.51.100.2"] {
+        let resp = test::call_service(&app, lookup(client, forwarded_for)).await;
+        assert_ne!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+    }
+    let resp = test::call_service(&app, lookup(client, "198.51.100.3")).await;
+    assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+
+    // Act & Assert - Clients behind a trusted proxy are told apart, and
+    // addresses they prepend themselves are ignored
+    for forwarded_for in ["198.51.100.7", "192.0.2.1, 198.51.100.7"] {
+        let resp = test::call_service(&app, lookup(proxy, forwarded_for)).await;
+        assert_ne!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+    }
+    let resp = test::call_service(&app, lookup(proxy, "192.0.2.2, 198.51.100.7")).await;
+    assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+    let resp = test::call_service(&app, lookup(proxy, "198.51.100.8")).await;
+    assert_ne!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+}
+
+/// Test reading a payload by a custom alias, and that aliases are unique.
+#[actix_web::test]
+async fn test_alias() {
+    // Arrange
+    let repository = Arc::new(InMemoryRepository::default());
+    let create_use_case = Arc::new(CreatePayloadUseCaseImpl::new(repository.clone()));
+    let get_use_case = Arc::new(GetPayloadUseCaseImpl::new(repository.clone()));
+    let resolve_use_case = Arc::new(ResolveAliasUseCaseImpl::new(repository.clone()));
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(create_use_case.clone()))
+            .app_data(web::Data::new(get_use_case.clone()))
+            .app_data(web::Data::new(resolve_use_case.clone()))
+            .app_data(configure_json_error_handling())
+            .configure(api::configure())
+    )
+    .await;
+
+    let req = test::TestRequest::post()
+        .uri("/api/v1/payloads")
+        .set_json(json!({
+            "content": "Release notes for 42",
+            "alias": "release-notes-42"
+        }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["alias"].as_str().unwrap(), "release-notes-42");
+    let hash_id = body["hash_id"].as_str().unwrap().to_string();
+
+    // Act & Assert - Alias resolves to the payload
+    let req = test::TestRequest::get()
+        .uri("/api/v1/aliases/release-notes-42")
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["hash_id"].as_str().unwrap(), hash_id);
+    assert_eq!(body["content"].as_str().unwrap(), "Release notes for 42");
+
+    // Act & Assert - A taken alias cannot be claimed again
+    let req = test::TestRequest::post()
+        .uri("/api/v1/payloads")
+        .set_json(json!({
+            "content": "Impostor",
+            "alias": "release-notes-42"
+        }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::CONFLICT);
+    assert_eq!(repository.len(), 1, "The rejected payload should not be kept");
+
+    // Act & Assert - Invalid and unknown aliases
+    let req = test::TestRequest::post()
+        .uri("/api/v1/payloads")
+        .set_json(json!({
+            "content": "Bad alias",
+            "alias": "Release Notes"
+        }))
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    let req = test::TestRequest::get()
+        .uri("/api/v1/aliases/release-notes-43")
+        .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+}
+
+/// A repository that fails the test if it is ever used.
+struct UnreachableRepository;
+
+#[async_trait::async_trait]
+impl Repository for UnreachableRepository {
+    async fn save(&self, _: &Payload) -> Result<(), RepositoryError> {
+        unreachable!("storage must not be touched")
+    }
+    async fn insert(&self, _: &Payload) -> Result<(), RepositoryError> {
+        unreachable!("storage must not be touched")
+    }
+    async fn get(&self, _: &HashId) -> Result<Option<Payload>, RepositoryError> {
+        unreachable!("storage must not be touched")
+    }
+    async fn view(&self, _: &HashId, _: bool) -> Result<Option<Payload>, RepositoryError> {
+        unreachable!("storage must not be touched")
+    }
+    async fn try_unlock_attempt(&self, _: &HashId, _: DateTime<Utc>) -> Result<Option<Payload>, RepositoryError> {
+        unreachable!("storage must not be touched")
+    }
+    async fn release_unlock_attempt(&self, _: &HashId, _: DateTime<Utc>, _: Option<DateTime<Utc>>) -> Result<(), RepositoryError> {
+        unreachable!("storage must not be touched")
+    }
+    async fn update_expiry(&self, _: &HashId, _: DateTime<Utc>, _: Option<u64>) -> Result<Option<Payload>, RepositoryError> {
+        unreachable!("storage must not be touched")
+    }
+    async fn replace_content(&self, _: &Payload, _: &Revision, _: u64, _: usize) -> Result<Option<Payload>, RepositoryError> {
+        unreachable!("storage must not be touched")
+    }
+    async fn get_revision(&self, _: &HashId, _: u64) -> Result<Option<Revision>, RepositoryError> {
+        unreachable!("storage must not be touched")
+    }
+    async fn delete(&self, _: &HashId, _: Option<u64>) -> Result<(), RepositoryError> {
+        unreachable!("storage must not be touched")
+    }
+    async fn insert_alias(&self, _: &str, _: &HashId, _: DateTime<Utc>) -> Result<(), RepositoryError> {
+        unreachable!("storage must not be touched")
+    }
+    async fn update_alias_expiry(&self, _: &str, _: &HashId, _: DateTime<Utc>) -> Result<(), RepositoryError> {
+        unreachable!("storage must not be touched")
+    }
+    async fn resolve_alias(&self, _: &str) -> Result<Option<HashId>, RepositoryError> {
+        unreachable!("storage must not be touched")
+    }
+}
+
+/// Test that malformed hash IDs are rejected before reaching storage.
+#[actix_web::test]
+async fn test_malformed_hash_id_rejected() {
+    // Arrange
+    let repository: Arc<dyn Repository> = Arc::new(UnreachableRepository);
+    let get_use_case = Arc::new(GetPayloadUseCaseImpl::new(repository.clone()));
+    let delete_use_case = Arc::new(DeletePayloadUseCaseImpl::new(repository.clone()));
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(get_use_case.clone()))
+            .app_data(web::Data::new(Arc::new(MimeRegistry::default())))
+            .app_data(web::Data::new(delete_use_case.clone()))
+            .app_data(configure_json_error_handling())
+            .configure(api::configure())
+    )
+    .await;
+
+    let too_long = "a".repeat(65);
+    for id in ["bad%3Aid", "bad.id", "%E2%9C%93", too_long.as_str()] {
+        // Act & Assert
+        let req = test::TestRequest::get()
+            .uri(&format!("/api/v1/payloads/{}", id))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST, "GET {}", id);
+
+        let req = test::TestRequest::get()
+            .uri(&format!("/api/v1/payloads/{}/raw", id))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST, "GET {}/raw", id);
+
+        let req = test::TestRequest::post()
+            .uri(&format!("/api/v1/payloads/{}/unlock", id))
+            .set_json(json!({ "password": "guess" }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST, "POST {}/unlock", id);
+
+        let req = test::TestRequest::delete()
+            .uri(&format!("/api/v1/payloads/{}", id))
+            .insert_header(("Authorization", "Bearer token"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST, "DELETE {}", id);
+    }
+}